@@ -1,10 +1,14 @@
 use dstore::Global;
 use std::error::Error;
 
-/// Start Global server on defined IP:PORT address
+/// Start Global server on defined IP:PORT address, until interrupted with Ctrl-C
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let addr = "[::1]:50051";
     println!("Dstore server listening on {}", addr);
-    Global::start_server(addr).await
+    Global::start_server_with_shutdown(addr, async {
+        tokio::signal::ctrl_c().await.ok();
+        println!("Shutting down dstore server");
+    })
+    .await
 }
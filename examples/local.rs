@@ -42,6 +42,34 @@ impl REPL {
         if cmd.starts_with(".") {
             match cmd.as_str() {
                 ".exit" => std::process::exit(0),
+                ".keys" => {
+                    match self.local.lock().await.keys().await {
+                        Ok(keys) => {
+                            for key in keys {
+                                println!("{}", String::from_utf8_lossy(&key));
+                            }
+                        }
+                        Err(e) => eprintln!("{}", e),
+                    }
+
+                    Ok(())
+                }
+                ".stats" => {
+                    let stats = self.local.lock().await.stats();
+                    println!(
+                        "hits: {}, misses: {}, remote fetches: {}",
+                        stats.hits, stats.misses, stats.remote_fetches
+                    );
+
+                    Ok(())
+                }
+                ".flush" => {
+                    // Drops only the cached copies, mappings remain on Global
+                    self.local.lock().await.clear_cache();
+                    println!("Local cache flushed");
+
+                    Ok(())
+                }
                 ".version" => {
                     if let Some(ver) = option_env!("CARGO_PKG_VERSION") {
                         println!("You are using KVDB v{}", ver);
@@ -49,7 +77,10 @@ impl REPL {
 
                     Ok(())
                 }
-                _ => Ok(eprintln!("Unsuccessful parsing!")),
+                _ => {
+                    eprintln!("Unsuccessful parsing!");
+                    Ok(())
+                }
             }
         } else {
             // Split commands into `operation key values..` for execution, a key can't be space-seperated.
@@ -57,24 +88,17 @@ impl REPL {
             let words: Vec<String> = cmd.split(" ").map(|x| x.to_string()).collect();
             match words[0].to_lowercase().as_ref() {
                 "set" | "put" | "insert" | "in" | "i" => {
-                    let key = Bytes::from(words[1].clone());
-                    let value = Bytes::from(words[2..].join(" "));
-                    if let Err(e) = self.local.lock().await.insert(key, value).await {
+                    let value = words[2..].join(" ");
+                    if let Err(e) = self.local.lock().await.insert_str(&words[1], &value).await {
                         eprintln!("{}", e);
                     }
 
                     Ok(())
                 }
                 "get" | "select" | "output" | "out" | "o" => {
-                    let key = Bytes::from(words[1].clone());
-                    match self.local.lock().await.get(&key).await {
-                        Ok(value) => {
-                            println!(
-                                "db: {} -> {}",
-                                String::from_utf8(key.to_vec())?,
-                                String::from_utf8(value.to_vec())?
-                            )
-                        }
+                    match self.local.lock().await.get_str(&words[1]).await {
+                        Ok(Some(value)) => println!("db: {} -> {}", words[1], value),
+                        Ok(None) => eprintln!("Key-Value mapping doesn't exist: {}", words[1]),
                         Err(e) => eprintln!("{}", e),
                     }
 
@@ -89,7 +113,10 @@ impl REPL {
 
                     Ok(())
                 }
-                _ => Ok(eprintln!("Unknown command!")),
+                _ => {
+                    eprintln!("Unknown command!");
+                    Ok(())
+                }
             }
         }
     }
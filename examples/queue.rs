@@ -0,0 +1,22 @@
+use bytes::Bytes;
+use dstore::Queue;
+use std::{error::Error, time::Duration};
+
+/// Push a few tasks onto a queue on Global and pop them back in order
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut queue = Queue::connect("[::1]:50051").await?;
+    let key = Bytes::from("tasks");
+
+    for task in &["first", "second", "third"] {
+        queue.push_back(key.clone(), Bytes::from(*task)).await?;
+    }
+
+    // Stop once no task has turned up for a second
+    while let Some((id, task)) = queue.pop_front_wait(&key, Duration::from_secs(1)).await? {
+        println!("Popped task: {}", String::from_utf8(task.to_vec())?);
+        queue.ack(id).await?;
+    }
+
+    Ok(())
+}
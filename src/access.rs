@@ -0,0 +1,154 @@
+use bytes::Bytes;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+};
+
+use crate::bloom::fnv1a;
+
+/// Number of KEYs Global tracks accesses of, beyond which the least accessed KEY is replaced
+pub(crate) const TRACKED_KEYS: usize = 1024;
+
+/// Number of independently locked buckets tracked KEYs are spread across, so that accesses of KEYs in
+/// different buckets don't contend with each other
+const SHARDS: usize = 16;
+
+/// Reads and writes recorded against a KEY
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Counts {
+    pub reads: u64,
+    pub writes: u64,
+}
+
+impl Counts {
+    fn total(&self) -> u64 {
+        self.reads + self.writes
+    }
+}
+
+/// Approximate per-KEY access counts within a bounded map, following the space-saving algorithm:
+/// an untracked KEY replaces the least accessed one and inherits it's counts. Frequently accessed
+/// KEYs are thus always tracked, while counts of rarely accessed KEYs may be overestimated.
+/// KEYs are split across buckets by hash, each tracking an equal share of KEYs behind it's own lock.
+pub(crate) struct AccessStats {
+    shards: Vec<Mutex<Tracked>>,
+}
+
+impl AccessStats {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = capacity.div_ceil(SHARDS).max(1);
+        Self {
+            shards: (0..SHARDS)
+                .map(|_| Mutex::new(Tracked::new(capacity)))
+                .collect(),
+        }
+    }
+
+    /// Record a read of KEY
+    pub(crate) fn read(&self, key: &[u8]) {
+        self.shard(key).lock().unwrap().record(key, |counts| {
+            counts.reads += 1;
+        });
+    }
+
+    /// Record a write of KEY
+    pub(crate) fn write(&self, key: &[u8]) {
+        self.shard(key).lock().unwrap().record(key, |counts| {
+            counts.writes += 1;
+        });
+    }
+
+    /// Upto `n` most accessed KEYs, most accessed first
+    pub(crate) fn top(&self, n: usize) -> Vec<(Bytes, Counts)> {
+        let mut top = vec![];
+        for shard in &self.shards {
+            top.extend(shard.lock().unwrap().top(n));
+        }
+        top.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.total()));
+        top.truncate(n);
+
+        top
+    }
+
+    /// Bucket tracking KEY
+    fn shard(&self, key: &[u8]) -> &Mutex<Tracked> {
+        &self.shards[fnv1a(key, 0xcbf2_9ce4_8422_2325) as usize % SHARDS]
+    }
+}
+
+/// KEYs tracked within a bucket, along with an index ordered by total accesses so that the least
+/// accessed KEY is found in logarithmic time
+struct Tracked {
+    counts: HashMap<Bytes, Counts>,
+    by_total: BTreeSet<(u64, Bytes)>,
+    capacity: usize,
+}
+
+impl Tracked {
+    fn new(capacity: usize) -> Self {
+        Self {
+            counts: HashMap::new(),
+            by_total: BTreeSet::new(),
+            capacity,
+        }
+    }
+
+    /// Apply `count` to counts of KEY, replacing the least accessed KEY if KEY isn't tracked and bucket is full
+    fn record(&mut self, key: &[u8], count: impl FnOnce(&mut Counts)) {
+        let (key, mut counts) = match self.counts.remove_entry(key) {
+            Some((key, counts)) => {
+                self.by_total.remove(&(counts.total(), key.clone()));
+                (key, counts)
+            }
+            None => {
+                let mut inherited = Counts::default();
+                if self.counts.len() >= self.capacity {
+                    if let Some((_, least)) = self.by_total.pop_first() {
+                        inherited = self.counts.remove(&least).unwrap_or_default();
+                    }
+                }
+                (Bytes::copy_from_slice(key), inherited)
+            }
+        };
+        count(&mut counts);
+        self.by_total.insert((counts.total(), key.clone()));
+        self.counts.insert(key, counts);
+    }
+
+    /// Upto `n` most accessed KEYs in bucket, most accessed first
+    fn top(&self, n: usize) -> impl Iterator<Item = (Bytes, Counts)> + '_ {
+        self.by_total
+            .iter()
+            .rev()
+            .take(n)
+            .map(move |(_, key)| (key.clone(), self.counts[key]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heavy_hitters_survive_replacement() {
+        let stats = AccessStats::new(8 * SHARDS);
+        for _ in 0..10 {
+            stats.read(b"hot");
+        }
+        stats.write(b"hot");
+        for i in 0..20 * SHARDS {
+            stats.read(format!("cold{}", i).as_bytes());
+        }
+
+        let top = stats.top(1);
+        assert_eq!(top[0].0, Bytes::from("hot"));
+        assert_eq!(
+            top[0].1,
+            Counts {
+                reads: 10,
+                writes: 1
+            }
+        );
+        assert_eq!(stats.top(1000).len(), 8 * SHARDS);
+    }
+}
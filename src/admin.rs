@@ -0,0 +1,160 @@
+use tonic::Request;
+
+use crate::{
+    dstore_proto::{
+        AccessList, Byte, NamespaceQuota, NamespaceUsage, Node, Nodes, Null, ServerStats, Size,
+    },
+    quota::{Quota, Usage},
+    transport::{Client, ConnectOptions},
+    DstoreError, KeyAccess, TlsConfig,
+};
+
+/// Size of Global's state, as reported by `Admin::stats()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlobalStats {
+    /// Number of KEYs mapped
+    pub keys: usize,
+    /// Approximate memory used by mappings, as the sum of KEY and VALUE lengths
+    pub bytes: usize,
+    /// Number of Locals in cluster
+    pub nodes: usize,
+}
+
+/// Local in Global's cluster, as listed by `Admin::list_nodes()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeInfo {
+    /// Address Local identifies itself by
+    pub addr: String,
+    /// Number of invalidations queued for Local, yet to be polled
+    pub queued: usize,
+}
+
+/// Client for operators to inspect Global
+pub struct Admin {
+    /// Stores client connection with Global
+    global: Client,
+}
+
+/// Builds an Admin with chained setters, for Globals served over TLS or with a token. Options not set are
+/// left at their defaults, as with `Admin::connect()`.
+pub struct AdminBuilder {
+    options: ConnectOptions,
+}
+
+impl AdminBuilder {
+    /// Start building an Admin to connect to Global on `global_addr`
+    pub fn new(global_addr: &str) -> Self {
+        Self {
+            options: ConnectOptions::new(global_addr),
+        }
+    }
+
+    /// Connect to Global over TLS
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.options.tls(tls);
+        self
+    }
+
+    /// Send shared secret `token` with every request, for Globals started with one
+    pub fn token(mut self, token: &str) -> Self {
+        self.options.token(token);
+        self
+    }
+
+    /// Connect to Global with the options set
+    pub async fn connect(self) -> Result<Admin, DstoreError> {
+        let global = self.options.connect().await?;
+
+        Ok(Admin { global })
+    }
+}
+
+impl Admin {
+    /// Connect to Global server on `global_addr`, a TCP address or a `unix:` prefixed socket path
+    pub async fn connect(global_addr: &str) -> Result<Self, DstoreError> {
+        Self::builder(global_addr).connect().await
+    }
+
+    /// Start building an Admin to connect to Global on `global_addr`, see `AdminBuilder`
+    pub fn builder(global_addr: &str) -> AdminBuilder {
+        AdminBuilder::new(global_addr)
+    }
+
+    /// Count KEYs, bytes stored and Locals in cluster on Global
+    pub async fn stats(&mut self) -> Result<GlobalStats, DstoreError> {
+        let ServerStats { keys, bytes, nodes } =
+            self.global.stats(Request::new(Null {})).await?.into_inner();
+
+        Ok(GlobalStats {
+            keys: keys as usize,
+            bytes: bytes as usize,
+            nodes: nodes as usize,
+        })
+    }
+
+    /// Remove every mapping from Global, for testing and operational resets. Locals in cluster flush their
+    /// caches on next update. Returns number of mappings removed.
+    pub async fn flush_all(&mut self) -> Result<usize, DstoreError> {
+        let Size { size } = self
+            .global
+            .clear_all(Request::new(Null {}))
+            .await?
+            .into_inner();
+
+        Ok(size as usize)
+    }
+
+    /// Locals in Global's cluster, ordered by address
+    pub async fn list_nodes(&mut self) -> Result<Vec<NodeInfo>, DstoreError> {
+        let Nodes { nodes } = self
+            .global
+            .list_nodes(Request::new(Null {}))
+            .await?
+            .into_inner();
+
+        Ok(nodes
+            .into_iter()
+            .map(|Node { addr, queued }| NodeInfo {
+                addr: String::from_utf8_lossy(&addr).to_string(),
+                queued: queued as usize,
+            })
+            .collect())
+    }
+
+    /// Upto `n` KEYs most accessed on Global by any Local, most accessed first, to decide what to preload
+    /// or shard. Reads Locals served from cache aren't counted.
+    pub async fn hot_keys(&mut self, n: usize) -> Result<Vec<KeyAccess>, DstoreError> {
+        let req = Request::new(Size {
+            size: n.min(i32::MAX as usize) as i32,
+        });
+        let AccessList { keys } = self.global.hot_keys(req).await?.into_inner();
+
+        Ok(keys.into_iter().map(KeyAccess::from).collect())
+    }
+
+    /// Limit KEYs and bytes held within `namespace`, the part of KEYs before their first `:`.
+    /// Writes that would take namespace beyond it's quota are rejected.
+    pub async fn set_quota(&mut self, namespace: &[u8], quota: Quota) -> Result<(), DstoreError> {
+        let req = Request::new(NamespaceQuota {
+            namespace: namespace.to_vec(),
+            max_keys: quota.max_keys.unwrap_or(0) as u64,
+            max_bytes: quota.max_bytes.unwrap_or(0) as u64,
+        });
+        self.global.set_quota(req).await?;
+
+        Ok(())
+    }
+
+    /// KEYs and bytes currently held within `namespace`
+    pub async fn usage(&mut self, namespace: &[u8]) -> Result<Usage, DstoreError> {
+        let req = Request::new(Byte {
+            body: namespace.to_vec(),
+        });
+        let NamespaceUsage { keys, bytes } = self.global.usage(req).await?.into_inner();
+
+        Ok(Usage {
+            keys: keys as usize,
+            bytes: bytes as usize,
+        })
+    }
+}
@@ -0,0 +1,104 @@
+use std::convert::TryInto;
+
+/// Bits set aside per KEY, which along with `HASHES` gives a false positive rate of about 1%
+const BITS_PER_KEY: usize = 10;
+
+/// Number of bits set per KEY, optimal for `BITS_PER_KEY` as `BITS_PER_KEY * ln 2`
+pub(crate) const HASHES: u32 = 7;
+
+/// Probabilistic set of KEYs, as reported by `Local::bloom_filter()`. A KEY that was added always tests
+/// positive, while one that wasn't tests negative except for about 1 in 100 KEYs, which test positive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// Filter of `keys`, sized for their number
+    pub(crate) fn build<'a>(keys: impl ExactSizeIterator<Item = &'a [u8]>) -> Self {
+        let words = (keys.len() * BITS_PER_KEY).div_ceil(64).max(1);
+        let mut filter = Self {
+            bits: vec![0; words],
+            hashes: HASHES,
+        };
+        for key in keys {
+            for bit in filter.positions(key) {
+                filter.bits[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+
+        filter
+    }
+
+    /// Check if KEY may have been added, `false` means it certainly wasn't
+    pub fn might_contain(&self, key: &[u8]) -> bool {
+        self.positions(key)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Bits KEY maps to, derived from two hashes by double hashing
+    fn positions(&self, key: &[u8]) -> impl Iterator<Item = usize> {
+        let len = (self.bits.len() * 64) as u64;
+        let (h1, h2) = (
+            fnv1a(key, 0xcbf2_9ce4_8422_2325),
+            fnv1a(key, 0x8422_2325_cbf2_9ce4),
+        );
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    pub(crate) fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    /// Serialize bits as little-endian words
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        self.bits
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+
+    /// Deserialize bits serialized by `to_bytes()`, `None` if they aren't a whole number of words
+    pub(crate) fn from_bytes(bytes: &[u8], hashes: u32) -> Option<Self> {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(8) {
+            return None;
+        }
+        let bits = bytes
+            .chunks(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+
+        Some(Self { bits, hashes })
+    }
+}
+
+/// FNV-1a hash of `bytes` starting from `basis`, stable across builds unlike `DefaultHasher`,
+/// so that Locals and Global agree on the bits a KEY maps to
+pub(crate) fn fnv1a(bytes: &[u8], basis: u64) -> u64 {
+    bytes.iter().fold(basis, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn false_positive_rate_is_about_one_percent() {
+        let keys: Vec<Vec<u8>> = (0..10_000)
+            .map(|i| format!("key-{}", i).into_bytes())
+            .collect();
+        let filter = BloomFilter::build(keys.iter().map(|key| &key[..]));
+        assert!(keys.iter().all(|key| filter.might_contain(key)));
+
+        let false_positives = (0..10_000)
+            .filter(|i| filter.might_contain(format!("absent-{}", i).as_bytes()))
+            .count();
+        assert!(false_positives < 200, "{} false positives", false_positives);
+
+        let decoded = BloomFilter::from_bytes(&filter.to_bytes(), HASHES).unwrap();
+        assert_eq!(decoded, filter);
+    }
+}
@@ -0,0 +1,58 @@
+use flate2::Crc;
+use tonic::{metadata::MetadataMap, Status};
+
+/// gRPC metadata header carrying the CRC32 of a streamed VALUE. Locals send it along with VALUEs they push,
+/// and with requests to pull one, which Global answers by setting it on it's response.
+pub(crate) const CHECKSUM_HEADER: &str = "dstore-checksum";
+
+/// Value of `CHECKSUM_HEADER` on pull requests, which don't yet know VALUE's checksum
+pub(crate) const REQUESTED: &str = "crc32";
+
+/// CRC32 of `value`
+pub(crate) fn checksum(value: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(value);
+    crc.sum()
+}
+
+/// CRC32 declared in `metadata`, if any
+pub(crate) fn declared(metadata: &MetadataMap) -> Option<u32> {
+    metadata
+        .get(CHECKSUM_HEADER)
+        .and_then(|sum| sum.to_str().ok()?.parse().ok())
+}
+
+/// Computes the CRC32 of a VALUE as it's recieved frame by frame, to check against the declared one once whole
+pub(crate) struct Verifier {
+    crc: Crc,
+    /// Checksum declared by the sender, nothing is checked if `None`
+    expected: Option<u32>,
+}
+
+impl Verifier {
+    pub(crate) fn new(expected: Option<u32>) -> Self {
+        Self {
+            crc: Crc::new(),
+            expected,
+        }
+    }
+
+    pub(crate) fn update(&mut self, frame: &[u8]) {
+        if self.expected.is_some() {
+            self.crc.update(frame);
+        }
+    }
+
+    /// Check VALUE recieved whole against the declared checksum, as frames may be dropped or reordered in transit
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn verify(&self) -> Result<(), Status> {
+        match self.expected {
+            Some(expected) if expected != self.crc.sum() => Err(Status::data_loss(format!(
+                "Checksum mismatch, expected {:08x} but computed {:08x}.",
+                expected,
+                self.crc.sum()
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
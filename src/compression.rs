@@ -0,0 +1,125 @@
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use std::io::{self, Read, Write};
+
+/// gRPC metadata header used by Locals to ask Global to compress VALUEs of atleast this many bytes
+pub(crate) const COMPRESSION_HEADER: &str = "dstore-compress-above";
+
+/// Decides whether a payload is worth compressing by deflating a sample of it first,
+/// so that CPU isn't wasted on already compressed data such as images and archives
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Sampling {
+    /// Number of leading bytes of payload compressed as a sample
+    pub(crate) sample_size: usize,
+    /// Payload is compressed only if sample shrinks to atmost this fraction of it's size
+    pub(crate) max_ratio: f64,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self {
+            sample_size: 4096,
+            max_ratio: 0.9,
+        }
+    }
+}
+
+impl Sampling {
+    /// Check if compressing a sample of `payload` saves enough to compress the whole of it
+    fn worthwhile(&self, payload: &[u8]) -> bool {
+        let sample = &payload[..payload.len().min(self.sample_size)];
+        if sample.is_empty() {
+            return false;
+        }
+
+        deflate(sample).len() as f64 <= sample.len() as f64 * self.max_ratio
+    }
+}
+
+fn deflate(payload: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(vec![], Compression::fast());
+    // Writing into a Vec can't fail
+    encoder.write_all(payload).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Deflate `payload` if compression is enabled, it is atleast `threshold` bytes long and compresses
+/// well by default sampling, returns payload along with whether it was compressed
+pub(crate) fn encode(payload: &[u8], threshold: Option<usize>) -> (Vec<u8>, bool) {
+    encode_with(payload, threshold, &Sampling::default())
+}
+
+/// Same as `encode()`, deciding whether `payload` compresses well with `sampling`
+pub(crate) fn encode_with(
+    payload: &[u8],
+    threshold: Option<usize>,
+    sampling: &Sampling,
+) -> (Vec<u8>, bool) {
+    match threshold {
+        Some(threshold) if payload.len() >= threshold && sampling.worthwhile(payload) => {
+            (deflate(payload), true)
+        }
+        _ => (payload.to_vec(), false),
+    }
+}
+
+/// Inflate `payload` if it was compressed
+pub(crate) fn decode(payload: Vec<u8>, compressed: bool) -> io::Result<Vec<u8>> {
+    if !compressed {
+        return Ok(payload);
+    }
+
+    let mut buf = vec![];
+    DeflateDecoder::new(&payload[..]).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_payloads_over_threshold_are_compressed() {
+        let small = b"tiny".to_vec();
+        let (encoded, compressed) = encode(&small, Some(1024));
+        assert!(!compressed);
+        assert_eq!(encoded, small);
+
+        let large = vec![b'a'; 64 * 1024];
+        let (encoded, compressed) = encode(&large, Some(1024));
+        assert!(compressed);
+        assert!(encoded.len() < large.len());
+        assert_eq!(decode(encoded, compressed).unwrap(), large);
+
+        // Nothing is compressed when disabled
+        assert!(!encode(&large, None).1);
+    }
+
+    #[test]
+    fn incompressible_payloads_are_left_as_is() {
+        // xorshift generated bytes, which deflate can't shrink
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let random: Vec<u8> = (0..64 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let (encoded, compressed) = encode(&random, Some(1024));
+        assert!(!compressed);
+        assert_eq!(encoded, random);
+
+        let text = b"the quick brown fox jumps over the lazy dog ".repeat(1024);
+        let (encoded, compressed) = encode(&text, Some(1024));
+        assert!(compressed);
+        assert_eq!(decode(encoded, compressed).unwrap(), text);
+
+        // A stricter ratio refuses even compressible payloads
+        let strict = Sampling {
+            sample_size: 512,
+            max_ratio: 0.0,
+        };
+        assert!(!encode_with(&text, Some(1024), &strict).1);
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+use tokio::sync::watch;
+
+use crate::wal::Record;
+
+/// Number of most recent mutations retained for consumers of the change feed to resume from
+pub(crate) const FEED_CAPACITY: usize = 65_536;
+
+/// Sequenced record of every mutation to Global's mappings, for change-data-capture consumers to
+/// replay from any retained sequence number and then follow. Sequence numbers start at 1.
+pub(crate) struct Feed {
+    /// Mutations in order of sequence, oldest first
+    records: VecDeque<(u64, Record)>,
+    /// Sequence number of the next mutation
+    next: u64,
+    capacity: usize,
+    /// Announces the sequence number of the next mutation, each time one is appended
+    appended: watch::Sender<u64>,
+    /// Kept to hand out clones of, as receivers can't be subscribed from the sender
+    following: watch::Receiver<u64>,
+}
+
+impl Feed {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let (appended, following) = watch::channel(1);
+        Self {
+            records: VecDeque::new(),
+            next: 1,
+            capacity,
+            appended,
+            following,
+        }
+    }
+
+    /// Append a mutation, dropping the oldest once over capacity, returns it's sequence number
+    pub(crate) fn push(&mut self, record: Record) -> u64 {
+        let sequence = self.next;
+        self.records.push_back((sequence, record));
+        if self.records.len() > self.capacity {
+            self.records.pop_front();
+        }
+        self.next += 1;
+        // Never errs, as a receiver is always kept
+        let _ = self.appended.send(self.next);
+
+        sequence
+    }
+
+    /// Mutations from sequence number `from` onwards, `None` if some of them are no longer retained
+    pub(crate) fn since(&self, from: u64) -> Option<Vec<(u64, Record)>> {
+        let oldest = self.next - self.records.len() as u64;
+        if from < oldest && oldest > 1 {
+            return None;
+        }
+
+        Some(
+            self.records
+                .iter()
+                .filter(|(sequence, _)| *sequence >= from)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Receiver notified each time a mutation is appended
+    pub(crate) fn subscribe(&self) -> watch::Receiver<u64> {
+        self.following.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    #[test]
+    fn truncated_sequences_are_reported() {
+        let mut feed = Feed::new(2);
+        for key in ["a", "b", "c"] {
+            feed.push(Record::Remove(Bytes::from(key)));
+        }
+
+        assert!(feed.since(1).is_none());
+        let since = feed.since(2).unwrap();
+        assert_eq!(
+            since.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert!(feed.since(4).unwrap().is_empty());
+        assert_eq!(Feed::new(2).since(0).unwrap().len(), 0);
+    }
+}
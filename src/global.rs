@@ -1,194 +1,4322 @@
 use bytes::Bytes;
-use futures::StreamExt;
+use futures::{future, Future, StreamExt};
 use std::{
-    collections::{HashMap, VecDeque},
-    str,
-    sync::Arc,
+    collections::{HashMap, HashSet, VecDeque},
+    convert::TryInto,
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    fs,
+    io::AsyncWriteExt,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, Mutex,
+    },
+    time::{self, Duration, Instant},
 };
-use tokio::sync::{mpsc, Mutex};
 use tokio_stream::wrappers::ReceiverStream;
-use tonic::{transport::Server, Request, Response, Status};
+use tonic::{
+    metadata::MetadataValue,
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::{Identity, Server, ServerTlsConfig},
+    Code, Request, Response, Status,
+};
 
+#[cfg(feature = "metrics")]
+use crate::metrics::{self, Metrics};
 use crate::{
+    access::{AccessStats, Counts, TRACKED_KEYS},
+    bloom::BloomFilter,
+    checksum::{self, Verifier, CHECKSUM_HEADER},
+    compression::{self, COMPRESSION_HEADER},
     dstore_proto::{
         dstore_server::{Dstore, DstoreServer},
-        Byte, KeyValue, Null, Size,
+        operation::Kind,
+        AccessList, Batch, BatchResult, Bool, Byte, ByteRange, CachedKeys, Change, Condition,
+        ConditionalBatch, Counter, Events, Expiry, Fence, FieldDelta, Filter, Invalidation,
+        Invalidations, KeyAccess, KeyField, KeyInfo, KeyValue, KeyValueTtl, KeyVersion, Keys,
+        LogRange, Lookup, NamespaceQuota, NamespaceUsage, Node, Nodes, Null, Offset, Operation,
+        Presence, QueueConfig, QueueMessage, QueueWait, Reconciliation, Seconds, ServerStats, Size,
+        Swap, Swapped, UpdateAck, Upserted, Value, Values, VersionedValue,
     },
-    MAX_BYTE_SIZE,
+    feed::{Feed, FEED_CAPACITY},
+    quota::{Quota, Quotas, Usage},
+    sharded_db::ShardedDb,
+    snapshot,
+    tasks::Queues,
+    transport,
+    wal::{Record, SyncPolicy, Wal},
+    DstoreError, CHUNK_SIZE_HEADER, FENCE_HEADER, MAX_BYTE_SIZE, MAX_VALUE_HEADER, SIZE_HEADER,
+    SLIDING_HEADER, TERMINATED_HEADER, TOKEN_HEADER, UID_HEADER, VERSION_HEADER,
 };
 
+/// Interval between consecutive sweeps for expired mappings
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Age beyond which Global's bloom filter of KEYs is rebuilt when next requested
+const BLOOM_REFRESH: Duration = Duration::from_secs(5);
+
+/// Time given to Locals to pick up a draining event before Global stops serving
+const DRAIN_PERIOD: Duration = Duration::from_secs(5);
+
+/// Number of changes a watcher may fall behind by before it's stream is ended
+const WATCH_BACKLOG: usize = 1024;
+
+/// VALUEs larger than this aren't streamed to Locals warming their cache, they're fetched on first read
+const BOOTSTRAP_MAX_VALUE: usize = 64 * 1024;
+
+/// Number of invalidations a Local may fall behind by before it's told to flush it's cache instead
+const MAX_QUEUE_LEN: usize = 100_000;
+
+/// Time a Local may go without a heartbeat before it's presumed dead and dropped from cluster
+const NODE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maps Local UIDs to a queue of pending cache invalidations
+type Cluster = Mutex<HashMap<Bytes, Mutex<InvalidationQueue>>>;
+
+/// Cache invalidations pending for a Local, bounded so that a Local that stops polling can't grow it forever
+struct InvalidationQueue {
+    pending: VecDeque<Invalidation>,
+    max_len: usize,
+    /// Whether the queue overflowed and Local is to flush it's entire cache, making other invalidations moot
+    resync: bool,
+    /// Instant Local last joined or sent a heartbeat
+    last_seen: Instant,
+    /// Invalidations of the last drained batch, kept until Local acknowledges receiving it
+    unacked: Vec<Invalidation>,
+    /// Sequence number of the last drained batch
+    seq: u64,
+}
+
+impl InvalidationQueue {
+    fn new(max_len: usize) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            max_len,
+            resync: false,
+            last_seen: Instant::now(),
+            unacked: vec![],
+            seq: 0,
+        }
+    }
+
+    /// Queue an invalidation. On overflow, pending invalidations are replaced by a single flush.
+    fn push(&mut self, invalidation: Invalidation) {
+        if self.resync && !invalidation.draining {
+            return;
+        }
+        if self.pending.len() >= self.max_len {
+            self.pending.retain(|pending| pending.draining);
+            self.pending.push_front(Invalidation {
+                flush: true,
+                ..Default::default()
+            });
+            self.resync = true;
+            if !invalidation.draining {
+                return;
+            }
+        }
+        self.pending.push_back(invalidation);
+    }
+
+    fn pop_front(&mut self) -> Option<Invalidation> {
+        let invalidation = self.pending.pop_front()?;
+        if invalidation.flush {
+            self.resync = false;
+        }
+
+        Some(invalidation)
+    }
+
+    /// Take every pending invalidation, oldest first, along with those of the last batch unless `ack` shows
+    /// Local received it. A batch whose reply was lost is thus sent again, rather than lost with it.
+    /// Returns the sequence number Local acknowledges the batch with.
+    fn drain(&mut self, ack: u64) -> (u64, Vec<Invalidation>) {
+        self.resync = false;
+        if ack == self.seq {
+            self.unacked.clear();
+        }
+        self.unacked.extend(self.pending.drain(..));
+        // Local that keeps missing batches is told to flush it's cache, as on overflow
+        if self.unacked.len() > self.max_len {
+            self.unacked.retain(|unacked| unacked.draining);
+            self.unacked.insert(
+                0,
+                Invalidation {
+                    flush: true,
+                    ..Default::default()
+                },
+            );
+        }
+        self.seq += 1;
+
+        (self.seq, self.unacked.clone())
+    }
+}
+
+/// Expiry of a mapping that's pushed back by it's TTL each time it's read
+struct Sliding {
+    ttl: Duration,
+    /// Instant deadlines are measured from
+    base: Instant,
+    /// Milliseconds since `base` the mapping was last read at. Atomic, so that reads may extend the
+    /// deadline while sharing the database lock, rather than taking it exclusively.
+    read_at: AtomicU64,
+}
+
+impl Sliding {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            base: Instant::now(),
+            read_at: AtomicU64::new(0),
+        }
+    }
+
+    /// Instant the mapping expires at, unless read before
+    fn deadline(&self) -> Instant {
+        self.base + Duration::from_millis(self.read_at.load(Ordering::Relaxed)) + self.ttl
+    }
+
+    /// Push deadline back to a TTL from now
+    fn extend(&self) {
+        let now = self.base.elapsed().as_millis() as u64;
+        self.read_at.fetch_max(now, Ordering::Relaxed);
+    }
+}
+
+/// Source of the versions of every mapping on Global. Versions only ever grow, so that a KEY removed and
+/// mapped anew, or restored once Global restarts, never repeats a version a client may still hold. Kept
+/// abreast of the system clock in microseconds, so versions issued after a restart exceed those before.
+struct Versions(AtomicU64);
+
+impl Versions {
+    fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    /// Issue a version greater than any issued before
+    fn next(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_micros() as u64);
+        let last = self
+            .0
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+                Some(last.max(now) + 1)
+            })
+            .unwrap();
+
+        last.max(now) + 1
+    }
+}
+
+/// VALUE stored on Global, along with metadata on it's lifetime and whereabouts
+struct Entry {
+    value: Bytes,
+    /// Instant the mapping expires at, if any. Superseded by `sliding`, if set.
+    expiry: Option<Instant>,
+    /// Set if expiry is pushed back on every read, rather than fixed
+    sliding: Option<Sliding>,
+    /// Drawn afresh from Global's `Versions` each time VALUE is written
+    version: u64,
+    /// UID of Local that inserted the mapping
+    owner: Bytes,
+    /// Time VALUE was last written
+    modified: SystemTime,
+    /// UIDs of Locals that may hold VALUE in cache, recorded by reads under a shared lock on the database
+    holders: std::sync::Mutex<HashSet<Bytes>>,
+}
+
+impl Entry {
+    /// Create a first `version` of mapping, inserted by `owner`
+    fn new(value: Bytes, expiry: Option<Instant>, owner: Bytes, version: u64) -> Self {
+        let mut holders = HashSet::new();
+        if !owner.is_empty() {
+            holders.insert(owner.clone());
+        }
+
+        Self {
+            value,
+            expiry,
+            sliding: None,
+            version,
+            owner,
+            modified: SystemTime::now(),
+            holders: std::sync::Mutex::new(holders),
+        }
+    }
+
+    /// Replace VALUE with a new `version` written by `writer`, other holders are left with stale copies
+    fn overwrite(&mut self, value: Bytes, writer: Bytes, version: u64) {
+        self.value = value;
+        self.version = version;
+        self.modified = SystemTime::now();
+        let holders = self.holders.get_mut().unwrap();
+        holders.clear();
+        if !writer.is_empty() {
+            holders.insert(writer);
+        }
+    }
+
+    /// Record that Local `holder` may hold VALUE in cache
+    fn hold(&self, holder: Bytes) {
+        if !holder.is_empty() {
+            self.holders.lock().unwrap().insert(holder);
+        }
+    }
+
+    /// Instant the mapping expires at, if any
+    fn deadline(&self) -> Option<Instant> {
+        match &self.sliding {
+            Some(sliding) => Some(sliding.deadline()),
+            None => self.expiry,
+        }
+    }
+
+    /// Record a read of the mapping, pushing back it's expiry if sliding
+    fn touch(&self) {
+        if let Some(sliding) = &self.sliding {
+            sliding.extend();
+        }
+    }
+
+    /// Check if mapping has outlived it's TTL, expired mappings are treated as absent
+    fn is_expired(&self) -> bool {
+        matches!(self.deadline(), Some(deadline) if deadline <= Instant::now())
+    }
+}
+
+/// Whether KEY is mapped with a TTL, so that writes replacing it's VALUE aren't made durable either
+fn transient(shard: &HashMap<Bytes, Entry>, key: &[u8]) -> bool {
+    shard
+        .get(key)
+        .is_some_and(|entry| !entry.is_expired() && entry.expiry.is_some())
+}
+
+/// Bytes a mapping counts for towards it's namespace's quota
+fn footprint(key: &[u8], value: &[u8]) -> usize {
+    key.len() + value.len()
+}
+
+/// Counter stored as an 8 byte big-endian integer
+fn decode_counter(bytes: &[u8]) -> Option<i64> {
+    bytes.try_into().ok().map(i64::from_be_bytes)
+}
+
+/// Hash stored as FIELD -> counter records, laid out as in a snapshot
+fn decode_hash(bytes: &Bytes) -> Option<Vec<(Bytes, i64)>> {
+    snapshot::decode(bytes.clone())
+        .ok()?
+        .into_iter()
+        .map(|(field, counter)| Some((field, decode_counter(&counter)?)))
+        .collect()
+}
+
+fn encode_hash(fields: &[(Bytes, i64)]) -> Bytes {
+    let fields: Vec<(Bytes, Bytes)> = fields
+        .iter()
+        .map(|(field, counter)| {
+            (
+                field.clone(),
+                Bytes::copy_from_slice(&counter.to_be_bytes()),
+            )
+        })
+        .collect();
+    snapshot::encode(fields.iter().map(|(field, counter)| (field, counter)))
+}
+
+/// UID of Local making a request, empty if request isn't from a Local
+fn uid<T>(req: &Request<T>) -> Bytes {
+    req.metadata()
+        .get(UID_HEADER)
+        .map(|uid| Bytes::copy_from_slice(uid.as_bytes()))
+        .unwrap_or_default()
+}
+
+/// Size of VALUE frames a streamed transfer was negotiated with, `default` if the request doesn't say.
+/// Sizes asked for are capped to `limit`.
+fn chunk_size<T>(req: &Request<T>, default: usize, limit: usize) -> usize {
+    req.metadata()
+        .get(CHUNK_SIZE_HEADER)
+        .and_then(|size| size.to_str().ok()?.parse().ok())
+        .map_or(default, |size: usize| size.clamp(1, limit))
+}
+
+/// Rejects requests that don't carry `token`, if Global expects one
+#[derive(Clone)]
+pub struct Authenticate {
+    token: Option<Bytes>,
+}
+
+impl Interceptor for Authenticate {
+    fn call(&mut self, req: Request<()>) -> Result<Request<()>, Status> {
+        match &self.token {
+            Some(token)
+                if req.metadata().get(TOKEN_HEADER).map(|t| t.as_bytes()) != Some(&token[..]) =>
+            {
+                Err(Status::unauthenticated("Missing or invalid token."))
+            }
+            _ => Ok(req),
+        }
+    }
+}
+
 /// Strore reference counted pointers to HashMaps maintaining state of Global
 pub struct Global {
     /// In-memory database mapping KEY -> VALUE
-    db: Arc<Mutex<HashMap<Bytes, Bytes>>>,
+    db: Arc<ShardedDb<Entry>>,
     /// Maps Local UIDs to a KEY invalidation queue
-    cluster: Arc<Mutex<HashMap<Bytes, Mutex<VecDeque<Bytes>>>>>,
+    cluster: Arc<Cluster>,
+    /// Task queues by name, along with VALUEs popped from them awaiting acknowledgement
+    queues: Arc<Mutex<Queues>>,
+    /// Maps log names to append-only event logs, an event's offset is it's index
+    logs: Arc<Mutex<HashMap<Bytes, Vec<Bytes>>>>,
+    /// Approximate read and write counts of the most accessed KEYs
+    access: Arc<AccessStats>,
+    /// Maximum number of mappings held, least recently written ones are evicted to fit. Unbounded if `None`
+    max_entries: Option<usize>,
+    /// Notifies watchers of KEYs whose mapping was written or removed
+    changes: broadcast::Sender<Bytes>,
+    /// Shared secret every request must carry, any request is served if `None`
+    token: Option<Bytes>,
+    /// Latest fencing token issued, writes carrying an older one are rejected
+    fence: AtomicU64,
+    /// Versions of mappings are drawn from here
+    versions: Versions,
+    /// Write-ahead log each write is recorded in before it's applied, if durability is enabled
+    wal: Option<Wal>,
+    /// Bloom filter of KEYs last served, along with when it was built
+    bloom: Mutex<Option<(Instant, Filter)>>,
+    /// Quotas on and usage of each namespace
+    quotas: Arc<std::sync::Mutex<Quotas>>,
+    /// Sequenced record of every mutation, for change-data-capture consumers
+    feed: Arc<std::sync::Mutex<Feed>>,
+    /// Number of invalidations queued for a Local before it's told to flush it's cache instead
+    max_queue_len: usize,
+    /// Size of VALUE frames streamed to Locals that don't ask for one
+    chunk_size: usize,
+    /// Whether responses are compressed with gzip, for clients that accept it
+    gzip: bool,
+    /// Largest mapping accepted in a single packet, as well as the largest frame of a stream
+    max_message_size: usize,
+    /// Path a final snapshot is saved to once the server shuts down, if any
+    shutdown_snapshot: Option<PathBuf>,
+    /// Time a Local may go without a heartbeat before it's dropped from cluster, never dropped if `None`
+    node_timeout: Option<Duration>,
+    /// Interval between sweeps for expired mappings, dead Locals and unacknowledged queue VALUEs
+    sweep_interval: Duration,
+    /// Counters and gauges served to Prometheus, see `serve_metrics()`
+    #[cfg(feature = "metrics")]
+    metrics: Arc<Metrics>,
+}
+
+impl Default for Global {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds and serves a Global with chained setters, as an alternative to calling setters on `Global::new()`.
+/// Options not set are left at their defaults, see `Global`'s setters.
+#[derive(Default)]
+pub struct GlobalBuilder {
+    token: Option<String>,
+    max_entries: Option<usize>,
+    snapshot: Option<(PathBuf, Duration)>,
+    shutdown_snapshot: Option<PathBuf>,
+    wal: Option<(PathBuf, SyncPolicy)>,
+    sweep_interval: Option<Duration>,
+    node_timeout: Option<Option<Duration>>,
+    max_queue_len: Option<usize>,
+    chunk_size: Option<usize>,
+    gzip: Option<bool>,
+    max_message_size: Option<usize>,
+    tls: Option<ServerTlsConfig>,
+    #[cfg(feature = "metrics")]
+    metrics_addr: Option<String>,
+}
+
+impl GlobalBuilder {
+    /// Start building a Global with default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve only requests that carry shared secret `token`
+    pub fn token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Hold atmost `max_entries` mappings, evicting least recently written ones to fit
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Restore mappings from a snapshot at `path` if there is one, saving a fresh snapshot there every `interval`
+    pub fn snapshot(mut self, path: impl AsRef<Path>, interval: Duration) -> Self {
+        self.snapshot = Some((path.as_ref().to_path_buf(), interval));
+        self
+    }
+
+    /// Save a final snapshot to `path` once the server shuts down
+    pub fn shutdown_snapshot(mut self, path: impl AsRef<Path>) -> Self {
+        self.shutdown_snapshot = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Record every write in a write-ahead log at `path`, replayed on top of the snapshot if any. Log is
+    /// compacted each time a snapshot is saved.
+    pub fn wal(mut self, path: impl AsRef<Path>, policy: SyncPolicy) -> Self {
+        self.wal = Some((path.as_ref().to_path_buf(), policy));
+        self
+    }
+
+    /// Interval between sweeps for expired mappings, dead Locals and unacknowledged queue VALUEs, a second by default
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = Some(interval);
+        self
+    }
+
+    /// Drop Locals that go without a heartbeat for `timeout` from cluster, see `Global::set_node_timeout()`
+    pub fn node_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.node_timeout = Some(timeout);
+        self
+    }
+
+    /// Number of invalidations queued for a Local before it's told to flush it's cache instead
+    pub fn max_queue_len(mut self, max_len: usize) -> Self {
+        self.max_queue_len = Some(max_len);
+        self
+    }
+
+    /// Size of VALUE frames streamed to Locals that don't ask for a size of their own
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = Some(size);
+        self
+    }
+
+    /// Compress responses with gzip for clients that accept it
+    pub fn gzip(mut self, enabled: bool) -> Self {
+        self.gzip = Some(enabled);
+        self
+    }
+
+    /// Largest mapping accepted in a single packet, see `Global::set_max_message_size()`
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = Some(size);
+        self
+    }
+
+    /// Serve over TLS, identified by PEM encoded `cert` and `key`
+    pub fn tls(mut self, cert: &[u8], key: &[u8]) -> Self {
+        self.tls = Some(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)));
+        self
+    }
+
+    /// Serve Prometheus metrics at `/metrics` over HTTP on `addr`, see `Global::serve_metrics()`
+    #[cfg(feature = "metrics")]
+    pub fn metrics(mut self, addr: &str) -> Self {
+        self.metrics_addr = Some(addr.to_string());
+        self
+    }
+
+    /// Build Global as configured, restoring mappings from the snapshot and write-ahead log if set.
+    /// Background tasks, such as periodic snapshots, only start once served.
+    pub async fn build(&self) -> Result<Global, DstoreError> {
+        let mut global = match &self.token {
+            Some(token) => Global::with_token(token),
+            None => Global::new(),
+        };
+        global.max_entries = self.max_entries;
+        if let Some(size) = self.max_message_size {
+            global.set_max_message_size(size);
+        }
+        if let Some(size) = self.chunk_size {
+            global.set_chunk_size(size);
+        }
+        if let Some(max_len) = self.max_queue_len {
+            global.set_max_queue_len(max_len);
+        }
+        if let Some(enabled) = self.gzip {
+            global.set_gzip(enabled);
+        }
+        if let Some(timeout) = self.node_timeout {
+            global.set_node_timeout(timeout);
+        }
+        if let Some(interval) = self.sweep_interval {
+            global.set_sweep_interval(interval);
+        }
+        if let Some(path) = &self.shutdown_snapshot {
+            global.set_shutdown_snapshot(path);
+        }
+        if let Some((path, _)) = &self.snapshot {
+            if path.exists() {
+                global.load_snapshot(path).await?;
+            }
+        }
+        if let Some((path, policy)) = &self.wal {
+            global.open_wal(path, *policy).await?;
+        }
+
+        Ok(global)
+    }
+
+    /// Build Global and serve it on `addr`, until `signal` completes
+    pub async fn serve(
+        self,
+        addr: &str,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let global = self.build().await?;
+        if let Some((path, interval)) = self.snapshot {
+            global.save_snapshots(path, interval);
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics_addr) = &self.metrics_addr {
+            global.serve_metrics(metrics_addr)?;
+        }
+        global.serve(addr, signal, self.tls).await
+    }
+
+    /// Build Global and serve it on `addr`, indefinitely
+    pub async fn start(self, addr: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.serve(addr, future::pending()).await
+    }
 }
 
 impl Global {
-    /// Generate initial, empty state of Global
-    fn new() -> Self {
+    /// Start building a Global with non-default options, see `GlobalBuilder`
+    pub fn builder() -> GlobalBuilder {
+        GlobalBuilder::new()
+    }
+
+    /// Generate initial, empty state of Global, for embedding with `into_service()`
+    pub fn new() -> Self {
         Self {
-            db: Arc::new(Mutex::new(HashMap::new())),
+            db: Arc::new(ShardedDb::new()),
             cluster: Arc::new(Mutex::new(HashMap::new())),
+            queues: Arc::new(Mutex::new(Queues::default())),
+            logs: Arc::new(Mutex::new(HashMap::new())),
+            access: Arc::new(AccessStats::new(TRACKED_KEYS)),
+            max_entries: None,
+            changes: broadcast::channel(WATCH_BACKLOG).0,
+            token: None,
+            fence: AtomicU64::new(0),
+            versions: Versions::new(),
+            wal: None,
+            bloom: Mutex::new(None),
+            quotas: Arc::new(std::sync::Mutex::new(Quotas::default())),
+            feed: Arc::new(std::sync::Mutex::new(Feed::new(FEED_CAPACITY))),
+            max_queue_len: MAX_QUEUE_LEN,
+            chunk_size: MAX_BYTE_SIZE,
+            gzip: true,
+            max_message_size: MAX_BYTE_SIZE,
+            shutdown_snapshot: None,
+            node_timeout: Some(NODE_TIMEOUT),
+            sweep_interval: SWEEP_INTERVAL,
+            #[cfg(feature = "metrics")]
+            metrics: Arc::new(Metrics::new()),
         }
     }
 
-    /// Initialiaze server and start Global service on `addr`
-    pub async fn start_server(addr: &str) -> Result<(), Box<dyn std::error::Error>> {
-        Server::builder()
-            .add_service(DstoreServer::new(Self::new()))
-            .serve(addr.parse().unwrap())
-            .await?;
+    /// Limit the number of invalidations queued for each Local joining from now on, 100,000 by default.
+    /// A Local that falls further behind, as when it stops polling, is told to flush it's entire cache.
+    pub fn set_max_queue_len(&mut self, max_len: usize) {
+        self.max_queue_len = max_len;
+    }
 
-        Ok(())
+    /// Compress responses with gzip for clients that accept it, on by default. Turning it off spares the CPU
+    /// spent compressing, where VALUEs are small. Requests compressed by clients are accepted regardless.
+    pub fn set_gzip(&mut self, enabled: bool) {
+        self.gzip = enabled;
     }
-}
 
-#[tonic::async_trait]
-impl Dstore for Global {
-    /// RPC to add new Local to cluster, with empty invalidation queue
-    async fn join(&self, args: Request<Byte>) -> Result<Response<Null>, Status> {
-        self.cluster.lock().await.insert(
-            Bytes::from(args.into_inner().body),
-            Mutex::new(VecDeque::new()),
-        );
+    /// Size VALUE frames streamed to Locals that don't ask for a size of their own, `MAX_BYTE_SIZE` by default.
+    /// Sizes beyond the maximum message size are capped to it, see `set_max_message_size()`.
+    pub fn set_chunk_size(&mut self, size: usize) {
+        self.chunk_size = size.clamp(1, self.max_message_size);
+    }
 
-        Ok(Response::new(Null {}))
+    /// Accept mappings of upto `size` bytes in a single packet, and frames of upto as many bytes in streams,
+    /// `MAX_BYTE_SIZE` by default. Locals must raise `LocalConfig::max_message_size` alike to send larger
+    /// VALUEs whole. Streamed frames remain `MAX_BYTE_SIZE` unless a Local or `set_chunk_size()` asks otherwise.
+    pub fn set_max_message_size(&mut self, size: usize) {
+        self.max_message_size = size.max(1);
+        self.chunk_size = self.chunk_size.min(self.max_message_size);
     }
 
-    /// Check if a certain KEY exists on Global, if yes return size of associated VALUE
-    async fn contains(&self, args: Request<Byte>) -> Result<Response<Size>, Status> {
-        match self.db.lock().await.get(&args.into_inner().body[..]) {
-            Some(value) => Ok(Response::new(Size {
-                size: value.len() as i32,
-            })),
-            None => Err(Status::not_found("Value doesn't exist")),
-        }
+    /// Save a final snapshot to `path` once the server shuts down, so that no writes are lost between
+    /// periodic snapshots. Restore it on restart with `load_snapshot()`.
+    pub fn set_shutdown_snapshot(&mut self, path: impl AsRef<Path>) {
+        self.shutdown_snapshot = Some(path.as_ref().to_path_buf());
     }
 
-    /// RPC that maps KEY to VALUE, if it doesn't already exist on Global
-    async fn push(&self, args: Request<KeyValue>) -> Result<Response<Null>, Status> {
-        let mut db = self.db.lock().await;
-        let KeyValue { key, value } = args.into_inner();
-        match db.contains_key(&key[..]) {
-            true => Err(Status::already_exists(format!(
-                "{} already in use.",
-                str::from_utf8(&key).unwrap()
-            ))),
-            false => {
-                db.insert(Bytes::from(key), Bytes::from(value));
-                Ok(Response::new(Null {}))
-            }
+    /// Drop Locals that go without a heartbeat for `timeout` from cluster, along with their invalidation
+    /// queues, 60 seconds by default. Should be well over the `update_interval` Locals heartbeat at.
+    /// Locals are never dropped if `None`, unless they leave.
+    pub fn set_node_timeout(&mut self, timeout: Option<Duration>) {
+        self.node_timeout = timeout;
+    }
+
+    /// Sweep for expired mappings, dead Locals and unacknowledged queue VALUEs every `interval`, a second
+    /// by default. Expired mappings are never served, but occupy memory and stay cached by Locals until swept.
+    pub fn set_sweep_interval(&mut self, interval: Duration) {
+        self.sweep_interval = interval;
+    }
+
+    /// Reject a single packet mapping larger than Global accepts
+    #[allow(clippy::result_large_err)]
+    fn check_size(&self, key: &[u8], value: &[u8]) -> Result<(), Status> {
+        let size = key.len() + value.len();
+        if size > self.max_message_size {
+            return Err(Status::invalid_argument(format!(
+                "Message of {} bytes exceeds limit of {} bytes.",
+                size, self.max_message_size
+            )));
         }
+
+        Ok(())
     }
 
-    /// RPC that maps KEY to streamed VALUE, provided it doesn't already exist on Global
-    async fn push_file(
-        &self,
-        args: Request<tonic::Streaming<Byte>>,
-    ) -> Result<Response<Null>, Status> {
-        // Logic to recieve streamed VALUES
-        let mut stream = args.into_inner();
-        let mut i = 0;
-        let (mut key, mut buf) = (vec![], vec![]);
-        while let Some(byte) = stream.next().await {
-            let Byte { body } = byte?;
-            if i == 0 {
-                key.append(&mut body.clone());
-            } else {
-                buf.append(&mut body.clone());
-            }
-            i += 1;
+    /// Generate Global as with `new()`, serving only requests that carry `token`
+    pub fn with_token(token: &str) -> Self {
+        Self {
+            token: Some(Bytes::copy_from_slice(token.as_bytes())),
+            ..Self::new()
         }
+    }
 
-        self.db
-            .lock()
+    /// Initialiaze server and start Global service on `addr`. On Unix, Global may instead listen on a
+    /// Unix domain socket, with `addr` it's path prefixed `unix:`, as in `unix:/tmp/dstore.sock`.
+    pub async fn start_server(addr: &str) -> Result<(), Box<dyn std::error::Error>> {
+        Self::start_server_with_shutdown(addr, future::pending()).await
+    }
+
+    /// Initialiaze server and start Global service on `addr`, until `signal` completes.
+    /// On shutdown, Locals in cluster are notified that Global is draining before it stops serving.
+    pub async fn start_server_with_shutdown(
+        addr: &str,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Self::new().serve(addr, signal, None).await
+    }
+
+    /// Start serving this Global on `addr` as configured, until `signal` completes. On shutdown, Locals in
+    /// cluster are notified that Global is draining and a final snapshot is saved, if set.
+    pub async fn serve_with_shutdown(
+        self,
+        addr: &str,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.serve(addr, signal, None).await
+    }
+
+    /// Initialiaze server and start Global service on `addr`, holding atmost `max_entries` mappings.
+    /// Least recently written mappings are evicted to fit, Locals in cluster are notified of evictions.
+    pub async fn start_server_with_max_entries(
+        addr: &str,
+        max_entries: usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let global = Self {
+            max_entries: Some(max_entries),
+            ..Self::new()
+        };
+        global.serve(addr, future::pending(), None).await
+    }
+
+    /// Initialiaze server and start Global service on `addr`, rejecting requests that don't carry `token`
+    pub async fn start_server_with_token(
+        addr: &str,
+        token: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Self::with_token(token)
+            .serve(addr, future::pending(), None)
             .await
-            .insert(Bytes::from(key), Bytes::from(buf));
+    }
 
-        Ok(Response::new(Null {}))
+    /// Initialiaze server and start Global service on `addr`, restoring mappings from a snapshot at `path`
+    /// if there is one and saving a fresh snapshot there every `interval`
+    pub async fn start_server_with_snapshot(
+        addr: &str,
+        path: impl AsRef<Path>,
+        interval: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Self::builder().snapshot(path, interval).start(addr).await
     }
 
-    /// RPC that returns VALUE associated with KEY, provided it exist on Global
-    async fn pull(&self, args: Request<Byte>) -> Result<Response<Byte>, Status> {
-        let db = self.db.lock().await;
-        let Byte { body } = args.into_inner();
-        match db.get(&body[..]) {
-            Some(val) => Ok(Response::new(Byte { body: val.to_vec() })),
-            None => Err(Status::not_found(format!(
-                "{} mapping doesn't exist.",
-                str::from_utf8(&body).unwrap()
-            ))),
-        }
+    /// Save a fresh snapshot to `path` every `interval`, in the background. Write-ahead log, if any, is
+    /// compacted after each, as the writes recorded before it are held in the snapshot.
+    fn save_snapshots(&self, path: PathBuf, interval: Duration) {
+        // Start thread to periodically save snapshots
+        let (db, wal) = (self.db.clone(), self.wal.clone());
+        tokio::spawn(async move {
+            let mut timer = time::interval_at(Instant::now() + interval, interval);
+            loop {
+                timer.tick().await;
+                if let Err(e) = Self::checkpoint(&db, wal.as_ref(), &path).await {
+                    eprintln!("Couldn't save snapshot: {}", e);
+                }
+            }
+        });
     }
 
-    /// Type to allow streaming of VALUE via RPC
-    type PullFileStream = ReceiverStream<Result<Byte, Status>>;
+    /// Save a snapshot to `path`, then drop the writes it holds from write-ahead log. Writes logged before
+    /// the snapshot is begun are applied by the time their shard is saved, those logged after are kept.
+    async fn checkpoint(
+        db: &ShardedDb<Entry>,
+        wal: Option<&Wal>,
+        path: &Path,
+    ) -> Result<(), DstoreError> {
+        let end = match wal {
+            Some(wal) => Some(wal.end().await?),
+            None => None,
+        };
+        Self::write_snapshot(db, path).await?;
+        if let (Some(wal), Some(end)) = (wal, end) {
+            wal.compact(end).await?;
+        }
 
-    /// RPC that streams VALUE associated with KEY, if it exist on Global
-    async fn pull_file(
-        &self,
-        args: Request<Byte>,
-    ) -> Result<Response<Self::PullFileStream>, Status> {
-        // Create a double ended channel for transporting VALUE packets processed within thread
-        let (tx, rx) = mpsc::channel(4);
-        let db = self.db.clone();
-        let Byte { body } = args.into_inner();
+        Ok(())
+    }
 
-        // Spawn thread to manage partitioning of a large VALUE into packet frames
+    /// Initialiaze server and start Global service on `addr`, serving Prometheus metrics at `/metrics`
+    /// over HTTP on `metrics_addr`
+    #[cfg(feature = "metrics")]
+    pub async fn start_server_with_metrics(
+        addr: &str,
+        metrics_addr: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let global = Self::new();
+        global.serve_metrics(metrics_addr)?;
+        global.serve(addr, future::pending(), None).await
+    }
+
+    /// Serve Prometheus metrics of requests per RPC, bytes transferred, sizes of VALUEs pushed, mappings held,
+    /// Locals in cluster and items queued, at `/metrics` over HTTP on `addr`. Served in the background, so must
+    /// be called from within a tokio runtime.
+    #[cfg(feature = "metrics")]
+    pub fn serve_metrics(&self, addr: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let addr: SocketAddr = addr.parse()?;
+        let (db, cluster) = (self.db.clone(), self.cluster.clone());
+        let (queues, metrics) = (self.queues.clone(), self.metrics.clone());
+        // Gauges are brought up to date on scrape, rather than on every change
+        let refresh = move || {
+            let (db, cluster) = (db.clone(), cluster.clone());
+            let (queues, metrics) = (queues.clone(), metrics.clone());
+            async move {
+                let mut keys = 0;
+                for shard in db.shards() {
+                    let shard = shard.read().await;
+                    keys += shard.values().filter(|entry| !entry.is_expired()).count();
+                }
+                let (mut nodes, mut invalidations) = (0, 0);
+                for queue in cluster.lock().await.values() {
+                    nodes += 1;
+                    invalidations += queue.lock().await.pending.len();
+                }
+                metrics.keys.set(keys as i64);
+                metrics.nodes.set(nodes);
+                let queued = &metrics.queued;
+                queued
+                    .with_label_values(&["invalidations"])
+                    .set(invalidations as i64);
+                queued
+                    .with_label_values(&["tasks"])
+                    .set(queues.lock().await.queued() as i64);
+            }
+        };
+        let server = metrics::serve(addr, self.metrics.clone(), refresh)?;
         tokio::spawn(async move {
-            let val = db.lock().await.get(&body[..]).unwrap().to_vec();
-            // Size each frame upto MAX_BYTE_SIZE and encapsulate in response packet
-            for i in 0..val.len() / MAX_BYTE_SIZE {
-                tx.send(Ok(Byte {
-                    body: val[i * MAX_BYTE_SIZE..(i + 1) * MAX_BYTE_SIZE].to_vec(),
-                }))
-                .await
-                .unwrap();
+            if let Err(e) = server.await {
+                eprintln!("Couldn't serve metrics: {}", e);
             }
         });
 
-        Ok(Response::new(ReceiverStream::new(rx)))
+        Ok(())
     }
 
-    /// RPC to remove KEY mappings on Global and add KEY to invalidate queues of Locals in cluster
-    async fn remove(&self, args: Request<Byte>) -> Result<Response<Null>, Status> {
-        let key = args.into_inner().body;
+    /// Initialiaze server and start Global service on `addr` over TLS, identified by PEM encoded `cert` and `key`
+    pub async fn start_server_tls(
+        addr: &str,
+        cert: &[u8],
+        key: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+        Self::new().serve(addr, future::pending(), Some(tls)).await
+    }
 
-        // Push KEY into invalidate queue of all node
-        for addr in self.cluster.lock().await.values() {
-            addr.lock().await.push_back(Bytes::from(key.clone()));
-        }
+    /// Wrap Global as a gRPC service, to be added to a tonic `Server` alongside other services.
+    /// Starts sweeping expired mappings, so must be called from within a tokio runtime.
+    pub fn into_service(self) -> InterceptedService<DstoreServer<Self>, Authenticate> {
+        // Start thread to periodically sweep expired mappings off Global
+        let (db, cluster) = (self.db.clone(), self.cluster.clone());
+        let (changes, quotas) = (self.changes.clone(), self.quotas.clone());
+        let (feed, queues, wal) = (self.feed.clone(), self.queues.clone(), self.wal.clone());
+        let (node_timeout, sweep_interval) = (self.node_timeout, self.sweep_interval);
+        tokio::spawn(async move {
+            let mut timer = time::interval(sweep_interval);
+            loop {
+                timer.tick().await;
+                Self::sweep(&db, &cluster, &changes, &quotas, &feed, wal.as_ref()).await;
+                if let Some(timeout) = node_timeout {
+                    Self::reap_nodes(&cluster, timeout).await;
+                }
+                // Redeliver queued VALUEs left unacknowledged, even if no consumer pops to notice
+                queues.lock().await.reap(Instant::now());
+            }
+        });
 
-        // Remove KEY mapping from Global
-        match self.db.lock().await.remove(&key[..]) {
-            Some(_) => Ok(Response::new(Null {})),
-            None => Err(Status::not_found(format!(
-                "Couldn't remove {}",
-                str::from_utf8(&key).unwrap()
-            ))),
+        // Check token on every request, before it reaches any RPC
+        let (token, gzip) = (self.token.clone(), self.gzip);
+        let mut server = DstoreServer::new(self).accept_gzip();
+        if gzip {
+            server = server.send_gzip();
         }
+        InterceptedService::new(server, Authenticate { token })
     }
 
-    /// RPC to help Local invalidate cached VALUEs
-    async fn update(&self, args: Request<Byte>) -> Result<Response<Byte>, Status> {
-        // Extract and return a KEY from invalidate queue associated with requesting Local
-        let Byte { body } = args.into_inner();
-        match self
-            .cluster
+    /// Start Global service on `addr`, over TLS if configured, until `signal` completes. `addr` is either a
+    /// TCP address or the path of a Unix domain socket prefixed `unix:`, as in `unix:/tmp/dstore.sock`.
+    async fn serve(
+        self,
+        addr: &str,
+        signal: impl Future<Output = ()>,
+        tls: Option<ServerTlsConfig>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        #[cfg(unix)]
+        let incoming = match transport::unix_path(addr) {
+            Some(path) => Some(transport::incoming(path)?),
+            None => None,
+        };
+        #[cfg(not(unix))]
+        if transport::unix_path(addr).is_some() {
+            return Err("Unix domain sockets are only supported on Unix".into());
+        }
+
+        // Broadcast draining event on shutdown, allowing Locals a period to recieve it
+        let cluster = self.cluster.clone();
+        let signal = async move {
+            signal.await;
+            Self::broadcast(
+                &cluster,
+                Invalidation {
+                    draining: true,
+                    ..Default::default()
+                },
+            )
+            .await;
+            time::sleep(DRAIN_PERIOD).await;
+        };
+
+        let mut server = Server::builder();
+        if let Some(tls) = tls {
+            server = server.tls_config(tls)?;
+        }
+        let (db, snapshot) = (self.db.clone(), self.shutdown_snapshot.clone());
+        let router = server.add_service(self.into_service());
+        #[cfg(unix)]
+        if let Some(incoming) = incoming {
+            router
+                .serve_with_incoming_shutdown(incoming, signal)
+                .await?;
+            return Self::save_shutdown_snapshot(&db, snapshot).await;
+        }
+        let addr: SocketAddr = addr.parse()?;
+        router.serve_with_shutdown(addr, signal).await?;
+
+        Self::save_shutdown_snapshot(&db, snapshot).await
+    }
+
+    /// Save a final snapshot to `path` if set, once Global stops serving and no more writes are applied
+    async fn save_shutdown_snapshot(
+        db: &ShardedDb<Entry>,
+        path: Option<PathBuf>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(path) = path {
+            Self::write_snapshot(db, &path).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove expired mappings and add their KEYs to invalidate queues of Locals in cluster
+    async fn sweep(
+        db: &ShardedDb<Entry>,
+        cluster: &Cluster,
+        changes: &broadcast::Sender<Bytes>,
+        quotas: &std::sync::Mutex<Quotas>,
+        feed: &std::sync::Mutex<Feed>,
+        wal: Option<&Wal>,
+    ) {
+        for shard in db.shards() {
+            let mut shard = shard.write().await;
+            let expired: Vec<Bytes> = shard
+                .iter()
+                .filter(|(_, entry)| entry.is_expired())
+                .map(|(key, _)| key.clone())
+                .collect();
+
+            for key in expired {
+                // Left in place should it not be logged, to be swept again next time
+                let evicted =
+                    Self::evict_key(&mut shard, cluster, changes, quotas, feed, wal, &key).await;
+                if let Err(e) = evicted {
+                    eprintln!("Couldn't log eviction: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Drop Locals not seen for `timeout` from cluster, as when crashed without leaving. A Local dropped while
+    /// alive rejoins on it's next update, flushing it's cache of invalidations it missed.
+    async fn reap_nodes(cluster: &Cluster, timeout: Duration) {
+        let mut cluster = cluster.lock().await;
+        let mut dead = vec![];
+        for (uid, queue) in cluster.iter() {
+            if queue.lock().await.last_seen.elapsed() >= timeout {
+                dead.push(uid.clone());
+            }
+        }
+        for uid in dead {
+            cluster.remove(&uid);
+        }
+    }
+
+    /// Remove a mapping evicted by Global and add KEY to invalidate queues of Locals in cluster,
+    /// so that no Local keeps serving a VALUE that's gone. Used by every eviction path. Removal is
+    /// recorded in write-ahead log first, if there's one, and mapping is left as is if that fails.
+    async fn evict_key(
+        shard: &mut HashMap<Bytes, Entry>,
+        cluster: &Cluster,
+        changes: &broadcast::Sender<Bytes>,
+        quotas: &std::sync::Mutex<Quotas>,
+        feed: &std::sync::Mutex<Feed>,
+        wal: Option<&Wal>,
+        key: &[u8],
+    ) -> io::Result<()> {
+        if let (true, Some(wal)) = (shard.contains_key(key), wal) {
+            wal.append(&[Record::Remove(Bytes::copy_from_slice(key))])
+                .await?;
+        }
+        if let Some(entry) = shard.remove(key) {
+            let old = footprint(key, &entry.value);
+            quotas.lock().unwrap().record(key, Some(old), None);
+            feed.lock()
+                .unwrap()
+                .push(Record::Remove(Bytes::copy_from_slice(key)));
+        }
+        Self::notify(changes, key);
+        Self::broadcast(
+            cluster,
+            Invalidation {
+                key: key.to_vec(),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Evict least recently written mappings until those held fit within `max_entries`. Called once a write
+    /// is applied and it's shard unlocked, so that only writes that succeed make room for themselves. Every
+    /// KEY is charged for before it's written, so concurrent writers together evict enough for all of them.
+    async fn evict_overflow(&self) {
+        let max_entries = match self.max_entries {
+            Some(max_entries) => max_entries,
+            None => return,
+        };
+        loop {
+            let oldest = {
+                let mut quotas = self.quotas.lock().unwrap();
+                match quotas.keys() > max_entries {
+                    true => quotas.least_recently_written(),
+                    false => None,
+                }
+            };
+            let oldest = match oldest {
+                Some(oldest) => oldest,
+                None => break,
+            };
+            let mut shard = self.db.shard(&oldest).write().await;
+            let (cluster, changes, quotas) = (&self.cluster, &self.changes, &self.quotas);
+            let (feed, wal) = (&self.feed, self.wal.as_ref());
+            // Left in place should it not be logged, to be evicted by the next write
+            let evicted =
+                Self::evict_key(&mut shard, cluster, changes, quotas, feed, wal, &oldest).await;
+            if let Err(e) = evicted {
+                eprintln!("Couldn't log eviction: {}", e);
+                break;
+            }
+        }
+    }
+
+    /// Push an invalidation into the queue of every Local in cluster. Cluster stays locked throughout, so a
+    /// Local leaving concurrently is dropped either before or after, never midway, and pushing can't fail.
+    async fn broadcast(cluster: &Cluster, invalidation: Invalidation) {
+        for addr in cluster.lock().await.values() {
+            addr.lock().await.push(invalidation.clone());
+        }
+    }
+
+    /// Push an invalidation of KEY written by Local `writer` into the queue of every other Local in cluster,
+    /// so that none keep serving a stale VALUE. The writer keeps it's own cache up to date as it writes, so
+    /// isn't invalidated. Writes from outside cluster, with an empty `writer`, invalidate every Local.
+    async fn invalidate(cluster: &Cluster, key: Vec<u8>, writer: &[u8]) {
+        let invalidation = Invalidation {
+            key,
+            ..Default::default()
+        };
+        for (uid, addr) in cluster.lock().await.iter() {
+            if uid[..] != *writer {
+                addr.lock().await.push(invalidation.clone());
+            }
+        }
+    }
+
+    /// Reject a write carrying a fencing token older than the latest issued, as from a writer since
+    /// superseded by a newer one. Writes without a token aren't fenced.
+    #[allow(clippy::result_large_err)]
+    fn check_fence<T>(&self, req: &Request<T>) -> Result<(), Status> {
+        let token = match req.metadata().get(FENCE_HEADER) {
+            Some(token) => token
+                .to_str()
+                .ok()
+                .and_then(|token| token.parse::<u64>().ok())
+                .ok_or_else(|| Status::invalid_argument("Malformed fencing token."))?,
+            None => return Ok(()),
+        };
+        let latest = self.fence.load(Ordering::SeqCst);
+        if token < latest {
+            return Err(Status::aborted(format!(
+                "Fencing token {} superseded by {}.",
+                token, latest
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Save every mapping to a snapshot file at `path`, replacing any earlier snapshot, returns number of
+    /// mappings saved. Mappings with a TTL are transient and left out, as is cluster membership.
+    pub async fn save_snapshot(&self, path: impl AsRef<Path>) -> Result<usize, DstoreError> {
+        Self::write_snapshot(&self.db, path.as_ref()).await
+    }
+
+    /// Snapshot mappings in `db` to `path`, as `save_snapshot()` does
+    async fn write_snapshot(db: &ShardedDb<Entry>, path: &Path) -> Result<usize, DstoreError> {
+        let mut entries = vec![];
+        for shard in db.shards() {
+            entries.extend(
+                shard
+                    .read()
+                    .await
+                    .iter()
+                    .filter(|(_, entry)| entry.expiry.is_none())
+                    .map(|(key, entry)| (key.clone(), entry.value.clone())),
+            );
+        }
+        let buf = snapshot::encode(entries.iter().map(|(key, value)| (key, value)));
+
+        // Write to a temporary file first, so that a crash midway leaves the earlier snapshot intact. It's
+        // flushed to disk before replacing the earlier one, as write-ahead log may be compacted after.
+        let mut tmp = PathBuf::from(path);
+        tmp.set_extension("tmp");
+        let mut file = fs::File::create(&tmp).await?;
+        file.write_all(&buf).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp, path).await?;
+
+        Ok(entries.len())
+    }
+
+    /// Restore mappings from a snapshot file at `path`, as saved by `save_snapshot()`, returns number of
+    /// mappings restored. Restored mappings replace those of the same KEY and are issued new versions.
+    pub async fn load_snapshot(&self, path: impl AsRef<Path>) -> Result<usize, DstoreError> {
+        let entries = snapshot::decode(Bytes::from(fs::read(path).await?))?;
+        let restored = entries.len();
+        for (key, value) in entries {
+            let new = Some(footprint(&key, &value));
+            let entry = Entry::new(value, None, Bytes::new(), self.versions.next());
+            let old = self.db.shard(&key).write().await.insert(key.clone(), entry);
+            let old = old.map(|entry| footprint(&key, &entry.value));
+            self.quotas.lock().unwrap().record(&key, old, new);
+        }
+
+        Ok(restored)
+    }
+
+    /// Replay writes recorded in a write-ahead log at `path` onto current mappings, as restored from a
+    /// snapshot, then record every write and eviction there before it's applied. Returns number of writes
+    /// replayed. As with snapshots, mappings with a TTL aren't made durable, so setting one is recorded as
+    /// a removal. Log is compacted to the writes made since, each time a periodic snapshot is saved.
+    pub async fn open_wal(
+        &mut self,
+        path: impl AsRef<Path>,
+        policy: SyncPolicy,
+    ) -> Result<usize, DstoreError> {
+        let (wal, records) = Wal::open(path.as_ref(), policy)?;
+        let replayed = records.len();
+        for record in records {
+            match record {
+                Record::Put { key, value } => {
+                    let mut shard = self.db.shard(&key).write().await;
+                    let new = Some(footprint(&key, &value));
+                    let old = match shard.get_mut(&key) {
+                        Some(entry) => {
+                            let old = footprint(&key, &entry.value);
+                            entry.overwrite(value, Bytes::new(), self.versions.next());
+                            Some(old)
+                        }
+                        None => {
+                            shard.insert(
+                                key.clone(),
+                                Entry::new(value, None, Bytes::new(), self.versions.next()),
+                            );
+                            None
+                        }
+                    };
+                    self.quotas.lock().unwrap().record(&key, old, new);
+                }
+                Record::Remove(key) => {
+                    let old = self.db.shard(&key).write().await.remove(&key);
+                    let old = old.map(|entry| footprint(&key, &entry.value));
+                    self.quotas.lock().unwrap().record(&key, old, None);
+                }
+            }
+        }
+        self.wal = Some(wal);
+
+        Ok(replayed)
+    }
+
+    /// Record a write in write-ahead log before it's applied, if there's a log, and in change feed. Call with
+    /// KEY's shard locked, so that writes to a KEY are recorded in the order they're applied.
+    async fn log(&self, record: Record) -> Result<(), Status> {
+        self.log_all(vec![(record, false)]).await
+    }
+
+    /// Record a write as `log()` does, though only in change feed if it's to a `transient` mapping, one with a
+    /// TTL, as those aren't made durable
+    async fn log_write(&self, record: Record, transient: bool) -> Result<(), Status> {
+        self.log_all(vec![(record, transient)]).await
+    }
+
+    /// Charge KEY's mapping changing from `old` bytes to `new` against quotas, then record it being written
+    /// `value` as `log_write()` does. Charge is refunded should logging fail, so a failed write takes up no room.
+    async fn charge_and_log(
+        &self,
+        key: &[u8],
+        old: Option<usize>,
+        new: Option<usize>,
+        value: Bytes,
+        transient: bool,
+    ) -> Result<(), Status> {
+        self.quotas.lock().unwrap().charge(key, old, new)?;
+        let record = Record::Put {
+            key: Bytes::copy_from_slice(key),
+            value,
+        };
+        if let Err(e) = self.log_write(record, transient).await {
+            self.quotas.lock().unwrap().record(key, new, old);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Record writes as `log_write()` does, either all of them or, should logging fail, none
+    async fn log_all(&self, records: Vec<(Record, bool)>) -> Result<(), Status> {
+        let durable: Vec<Record> = records
+            .iter()
+            .filter(|(_, transient)| !transient)
+            .map(|(record, _)| record.clone())
+            .collect();
+        self.write_ahead(&durable).await?;
+        let mut feed = self.feed.lock().unwrap();
+        for (record, _) in records {
+            feed.push(record);
+        }
+
+        Ok(())
+    }
+
+    /// Record writes in write-ahead log only, for changes to mappings that aren't writes to change consumers
+    async fn write_ahead(&self, records: &[Record]) -> Result<(), Status> {
+        if let Some(wal) = &self.wal {
+            wal.append(records)
+                .await
+                .map_err(|e| Status::internal(format!("Couldn't log write: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Let watchers know KEY's mapping changed, call after the change is made.
+    /// Watchers read the mapping's current state themselves, so need only KEY.
+    fn notify(changes: &broadcast::Sender<Bytes>, key: &[u8]) {
+        // Errs only if nobody is watching
+        let _ = changes.send(Bytes::copy_from_slice(key));
+    }
+
+    /// Apply `ops` atomically and in order, provided every one of `conditions` holds, returning whether they
+    /// did. Errs, applying none, if the source KEY of a remove, copy or rename isn't mapped when it's reached.
+    async fn apply(
+        &self,
+        ops: Vec<Operation>,
+        conditions: Vec<Condition>,
+        writer: Bytes,
+    ) -> Result<bool, Status> {
+        let mut touched: Vec<&[u8]> = vec![];
+        for op in &ops {
+            touched.push(&op.key);
+            if matches!(op.kind(), Kind::Copy | Kind::Rename) {
+                touched.push(&op.target);
+            }
+        }
+        // KEYs only checked against aren't written, but are locked alike
+        touched.extend(conditions.iter().map(|condition| &condition.key[..]));
+
+        let staged = {
+            let mut shards = self.db.write_many(touched).await;
+            // Should any condition not hold, nothing is staged let alone applied
+            for Condition {
+                key,
+                expected,
+                absent,
+            } in &conditions
+            {
+                let current = shards[&self.db.index(key)]
+                    .get(&key[..])
+                    .filter(|entry| !entry.is_expired());
+                let holds = match current {
+                    Some(entry) => !absent && entry.value[..] == expected[..],
+                    None => *absent,
+                };
+                if !holds {
+                    return Ok(false);
+                }
+            }
+
+            // Resulting VALUE of each KEY written so far, `None` if removed
+            let mut staged: HashMap<Bytes, Option<Bytes>> = HashMap::new();
+            for op in &ops {
+                let key = Bytes::copy_from_slice(&op.key);
+                if op.kind() == Kind::Put {
+                    staged.insert(key, Some(Bytes::copy_from_slice(&op.value)));
+                    continue;
+                }
+                let value = match staged.get(&key) {
+                    Some(value) => value.clone(),
+                    None => shards[&self.db.index(&key)]
+                        .get(&key)
+                        .filter(|entry| !entry.is_expired())
+                        .map(|entry| entry.value.clone()),
+                }
+                .ok_or_else(|| {
+                    Status::failed_precondition(format!(
+                        "Batch aborted, {} mapping doesn't exist.",
+                        String::from_utf8_lossy(&key)
+                    ))
+                })?;
+                let target = Bytes::copy_from_slice(&op.target);
+                match op.kind() {
+                    Kind::Remove => {
+                        staged.insert(key, None);
+                    }
+                    Kind::Copy => {
+                        staged.insert(target, Some(value));
+                    }
+                    Kind::Rename => {
+                        staged.insert(key, None);
+                        staged.insert(target, Some(value));
+                    }
+                    Kind::Put => unreachable!(),
+                }
+            }
+
+            // Charge every change against quotas, refunding those already charged if one doesn't fit
+            let mut charged = vec![];
+            {
+                let mut quotas = self.quotas.lock().unwrap();
+                for (key, value) in &staged {
+                    let shard = &shards[&self.db.index(key)];
+                    let old = shard.get(key).map(|entry| footprint(key, &entry.value));
+                    let new = value.as_ref().map(|value| footprint(key, value));
+                    if let Err(e) = quotas.charge(key, old, new) {
+                        for (key, old, new) in charged {
+                            quotas.record(key, new, old);
+                        }
+                        return Err(e);
+                    }
+                    charged.push((key, old, new));
+                }
+            }
+
+            // Every change is logged before any is applied, so that a batch is never left half applied
+            let records = staged
+                .iter()
+                .map(|(key, value)| match value {
+                    Some(value) => {
+                        let record = Record::Put {
+                            key: key.clone(),
+                            value: value.clone(),
+                        };
+                        (record, transient(&shards[&self.db.index(key)], key))
+                    }
+                    None => (Record::Remove(key.clone()), false),
+                })
+                .collect();
+            if let Err(e) = self.log_all(records).await {
+                let mut quotas = self.quotas.lock().unwrap();
+                for (key, old, new) in charged {
+                    quotas.record(key, new, old);
+                }
+                return Err(e);
+            }
+
+            for (key, value) in &staged {
+                let shard = shards.get_mut(&self.db.index(key)).unwrap();
+                match value {
+                    Some(value) => match shard.get_mut(key).filter(|entry| !entry.is_expired()) {
+                        Some(entry) => {
+                            entry.overwrite(value.clone(), writer.clone(), self.versions.next())
+                        }
+                        None => {
+                            shard.insert(
+                                key.clone(),
+                                Entry::new(
+                                    value.clone(),
+                                    None,
+                                    writer.clone(),
+                                    self.versions.next(),
+                                ),
+                            );
+                        }
+                    },
+                    None => {
+                        shard.remove(key);
+                    }
+                }
+                Self::notify(&self.changes, key);
+            }
+            staged
+        };
+        self.evict_overflow().await;
+
+        // Push every KEY written into invalidate queue of all node, so stale caches are refreshed
+        for (key, value) in staged {
+            if value.is_some() {
+                self.access.write(&key);
+            }
+            Self::invalidate(&self.cluster, key.to_vec(), &writer).await;
+        }
+
+        Ok(true)
+    }
+
+    /// Map KEY to VALUE with an optional TTL, if it isn't already mapped on Global. A `sliding` TTL is
+    /// extended on every read.
+    async fn insert(
+        &self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+        sliding: bool,
+        owner: Bytes,
+    ) -> Result<Response<KeyVersion>, Status> {
+        let mut db = self.db.shard(&key).write().await;
+        match db.get(&key[..]).is_some_and(|entry| !entry.is_expired()) {
+            true => Err(Status::already_exists(format!(
+                "{} already in use.",
+                String::from_utf8_lossy(&key)
+            ))),
+            false => {
+                let value = Bytes::from(value);
+                let old = db.get(&key[..]).map(|entry| footprint(&key, &entry.value));
+                let new = Some(footprint(&key, &value));
+                self.charge_and_log(&key, old, new, value.clone(), ttl.is_some())
+                    .await?;
+                let expiry = ttl.map(|ttl| Instant::now() + ttl);
+                let mut entry = Entry::new(value, expiry, owner.clone(), self.versions.next());
+                if sliding {
+                    entry.sliding = ttl.map(Sliding::new);
+                }
+                let version = entry.version;
+                let replaced = db.insert(Bytes::from(key.clone()), entry).is_some();
+                Self::notify(&self.changes, &key);
+                drop(db);
+                self.access.write(&key);
+                self.evict_overflow().await;
+                // An expired mapping yet to be swept may still be cached elsewhere
+                if replaced {
+                    Self::invalidate(&self.cluster, key.clone(), &owner).await;
+                }
+                Ok(Response::new(KeyVersion { key, version }))
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Dstore for Global {
+    /// RPC that replies immediately, for Locals to check their connection with Global
+    async fn ping(&self, _: Request<Null>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("ping"));
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC reporting number of KEYs, bytes they and their VALUEs take up and Locals in cluster
+    async fn stats(&self, _: Request<Null>) -> Result<Response<ServerStats>, Status> {
+        metric!(self.metrics.request("stats"));
+        let (mut keys, mut bytes) = (0, 0);
+        for shard in self.db.shards() {
+            let shard = shard.read().await;
+            for (key, entry) in shard.iter().filter(|(_, entry)| !entry.is_expired()) {
+                keys += 1;
+                bytes += (key.len() + entry.value.len()) as u64;
+            }
+        }
+        let nodes = self.cluster.lock().await.len() as u64;
+
+        Ok(Response::new(ServerStats { keys, bytes, nodes }))
+    }
+
+    /// RPC that lists Locals in cluster, along with the number of invalidations queued for each
+    async fn list_nodes(&self, _: Request<Null>) -> Result<Response<Nodes>, Status> {
+        metric!(self.metrics.request("list_nodes"));
+        let mut nodes = vec![];
+        for (uid, queue) in self.cluster.lock().await.iter() {
+            nodes.push(Node {
+                addr: uid.to_vec(),
+                queued: queue.lock().await.pending.len() as u64,
+            });
+        }
+        nodes.sort_by(|a, b| a.addr.cmp(&b.addr));
+
+        Ok(Response::new(Nodes { nodes }))
+    }
+
+    /// RPC issuing a fencing token newer than any before it, to the writer being promoted
+    async fn promote(&self, _: Request<Null>) -> Result<Response<Fence>, Status> {
+        metric!(self.metrics.request("promote"));
+        let token = self.fence.fetch_add(1, Ordering::SeqCst) + 1;
+
+        Ok(Response::new(Fence { token }))
+    }
+
+    /// RPC replying with a bloom filter of every KEY mapped, as built within the last `BLOOM_REFRESH`
+    async fn bloom_filter(&self, _: Request<Null>) -> Result<Response<Filter>, Status> {
+        metric!(self.metrics.request("bloom_filter"));
+        let mut cached = self.bloom.lock().await;
+        if let Some((built, filter)) = &*cached {
+            if built.elapsed() < BLOOM_REFRESH {
+                return Ok(Response::new(filter.clone()));
+            }
+        }
+
+        let mut keys = vec![];
+        for shard in self.db.shards() {
+            keys.extend(
+                shard
+                    .read()
+                    .await
+                    .iter()
+                    .filter(|(_, entry)| !entry.is_expired())
+                    .map(|(key, _)| key.clone()),
+            );
+        }
+        let bloom = BloomFilter::build(keys.iter().map(|key| &key[..]));
+        let filter = Filter {
+            bits: bloom.to_bytes(),
+            hashes: bloom.hashes(),
+        };
+        *cached = Some((Instant::now(), filter.clone()));
+
+        Ok(Response::new(filter))
+    }
+
+    /// RPC to limit KEYs and bytes held within a namespace, zero being unlimited. Writes beyond a
+    /// namespace's quota are rejected, though it isn't reduced to fit if already beyond a new quota.
+    async fn set_quota(&self, args: Request<NamespaceQuota>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("set_quota"));
+        let NamespaceQuota {
+            namespace,
+            max_keys,
+            max_bytes,
+        } = args.into_inner();
+        let quota = Quota {
+            max_keys: Some(max_keys as usize).filter(|max| *max > 0),
+            max_bytes: Some(max_bytes as usize).filter(|max| *max > 0),
+        };
+        self.quotas.lock().unwrap().set(&namespace, quota);
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC reporting KEYs and bytes held within a namespace
+    async fn usage(&self, args: Request<Byte>) -> Result<Response<NamespaceUsage>, Status> {
+        metric!(self.metrics.request("usage"));
+        let Usage { keys, bytes } = self.quotas.lock().unwrap().usage(&args.into_inner().body);
+
+        Ok(Response::new(NamespaceUsage {
+            keys: keys as u64,
+            bytes: bytes as u64,
+        }))
+    }
+
+    /// RPC to add new Local to cluster, with empty invalidation queue
+    async fn join(&self, args: Request<Byte>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("join"));
+        trace_event!(node = %String::from_utf8_lossy(&args.get_ref().body), "join");
+        self.cluster.lock().await.insert(
+            Bytes::from(args.into_inner().body),
+            Mutex::new(InvalidationQueue::new(self.max_queue_len)),
+        );
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC to remove Local from cluster, dropping it's invalidation queue
+    async fn leave(&self, args: Request<Byte>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("leave"));
+        self.cluster
             .lock()
             .await
+            .remove(&args.into_inner().body[..]);
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC refreshing when Local was last seen, so that it isn't dropped from cluster as dead
+    async fn heartbeat(&self, args: Request<Byte>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("heartbeat"));
+        let Byte { body } = args.into_inner();
+        let cluster = self.cluster.lock().await;
+        let queue = cluster.get(&body[..]).ok_or_else(|| {
+            Status::failed_precondition(format!(
+                "{} node not registered.",
+                String::from_utf8_lossy(&body)
+            ))
+        })?;
+        queue.lock().await.last_seen = Instant::now();
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC to bring a Local's cache in line with Global in a single round trip, as on reconnecting.
+    /// Replies with cached KEYs that no longer exist and those whose version has since changed.
+    async fn reconcile(
+        &self,
+        args: Request<CachedKeys>,
+    ) -> Result<Response<Reconciliation>, Status> {
+        metric!(self.metrics.request("reconcile"));
+        let CachedKeys { uid, keys } = args.into_inner();
+        let uid = Bytes::from(uid);
+
+        // Reconciliation supersedes any backlog of invalidations, (re)join with an empty queue
+        self.cluster.lock().await.insert(
+            uid.clone(),
+            Mutex::new(InvalidationQueue::new(self.max_queue_len)),
+        );
+
+        let (mut evict, mut refresh) = (vec![], vec![]);
+        for KeyVersion { key, version } in keys {
+            match self
+                .db
+                .shard(&key)
+                .read()
+                .await
+                .get(&key[..])
+                .filter(|entry| !entry.is_expired())
+            {
+                Some(entry) => {
+                    entry.hold(uid.clone());
+                    if entry.version != version {
+                        refresh.push(key);
+                    }
+                }
+                None => evict.push(key),
+            }
+        }
+
+        Ok(Response::new(Reconciliation { evict, refresh }))
+    }
+
+    /// Check if a certain KEY exists on Global, if yes return size and version of associated VALUE
+    async fn contains(&self, args: Request<Byte>) -> Result<Response<Presence>, Status> {
+        metric!(self.metrics.request("contains"));
+        let Byte { body } = args.into_inner();
+        let presence = match self
+            .db
+            .shard(&body)
+            .read()
+            .await
             .get(&body[..])
-            .unwrap()
-            .lock()
+            .filter(|entry| !entry.is_expired())
+        {
+            Some(entry) => {
+                entry.touch();
+                Ok(Presence {
+                    size: entry.value.len() as i32,
+                    version: entry.version,
+                })
+            }
+            None => Err(Status::not_found("Value doesn't exist")),
+        }?;
+        self.access.read(&body);
+
+        Ok(Response::new(presence))
+    }
+
+    /// RPC that reports everything Global knows about a KEY, for diagnosing coherence
+    async fn inspect(&self, args: Request<Byte>) -> Result<Response<KeyInfo>, Status> {
+        metric!(self.metrics.request("inspect"));
+        let Byte { body } = args.into_inner();
+        match self
+            .db
+            .shard(&body)
+            .read()
             .await
-            .pop_front()
+            .get(&body[..])
+            .filter(|entry| !entry.is_expired())
         {
-            Some(keys) => Ok(Response::new(Byte {
-                body: keys.to_vec(),
+            Some(entry) => Ok(Response::new(KeyInfo {
+                size: entry.value.len() as i32,
+                version: entry.version,
+                ttl_millis: entry.deadline().map_or(0, |expiry| {
+                    expiry.saturating_duration_since(Instant::now()).as_millis() as u64
+                }),
+                owner: entry.owner.to_vec(),
+                modified_millis: entry
+                    .modified
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+                holders: entry
+                    .holders
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|uid| uid.to_vec())
+                    .collect(),
+                tags: vec![],
             })),
-            None => Err(Status::not_found("")),
+            None => Err(Status::not_found(format!(
+                "{} mapping doesn't exist.",
+                String::from_utf8_lossy(&body)
+            ))),
+        }
+    }
+
+    /// RPC that maps KEY to VALUE, if it doesn't already exist on Global
+    async fn push(&self, args: Request<KeyValue>) -> Result<Response<KeyVersion>, Status> {
+        metric!(self.metrics.request("push"));
+        self.check_fence(&args)?;
+        let owner = uid(&args);
+        let KeyValue {
+            key,
+            value,
+            compressed,
+            ..
+        } = args.into_inner();
+        self.check_size(&key, &value)?;
+        let value = compression::decode(value, compressed)
+            .map_err(|e| Status::invalid_argument(format!("Couldn't decompress VALUE: {}", e)))?;
+        trace_event!(
+            key_len = key.len(),
+            size = value.len(),
+            node = %String::from_utf8_lossy(&owner),
+            streaming = false,
+            "push"
+        );
+        metric!(self.metrics.pushed(key.len(), value.len()));
+        let res = self.insert(key, value, None, false, owner).await;
+        trace_event!(ok = res.is_ok(), "push applied");
+        res
+    }
+
+    /// RPC that returns upto `size` most accessed KEYs with their approximate read and write counts
+    async fn hot_keys(&self, args: Request<Size>) -> Result<Response<AccessList>, Status> {
+        metric!(self.metrics.request("hot_keys"));
+        let Size { size } = args.into_inner();
+        let keys = self
+            .access
+            .top(size.max(0) as usize)
+            .into_iter()
+            .map(|(key, Counts { reads, writes })| KeyAccess {
+                key: key.to_vec(),
+                reads,
+                writes,
+            })
+            .collect();
+
+        Ok(Response::new(AccessList { keys }))
+    }
+
+    /// RPC that maps KEY to VALUE for `ttl_millis` milliseconds, if it doesn't already exist on Global.
+    /// If `sliding`, the TTL restarts each time the mapping is read.
+    async fn push_with_ttl(
+        &self,
+        args: Request<KeyValueTtl>,
+    ) -> Result<Response<KeyVersion>, Status> {
+        metric!(self.metrics.request("push_with_ttl"));
+        self.check_fence(&args)?;
+        let owner = uid(&args);
+        let KeyValueTtl {
+            key,
+            value,
+            ttl_millis,
+            sliding,
+        } = args.into_inner();
+        self.check_size(&key, &value)?;
+        let ttl = Duration::from_millis(ttl_millis);
+        self.insert(key, value, Some(ttl), sliding, owner).await
+    }
+
+    /// RPC that sets or updates the TTL of an existing KEY, which expires `ttl_millis` milliseconds from now.
+    /// KEY is added to invalidate queues of other Locals, so that none keep serving it past expiry.
+    async fn expire(&self, args: Request<Expiry>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("expire"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let Expiry { key, ttl_millis } = args.into_inner();
+        let mut db = self.db.shard(&key).write().await;
+        match db.get_mut(&key[..]).filter(|entry| !entry.is_expired()) {
+            Some(entry) => {
+                // Mappings with a TTL aren't made durable, so it's as good as removed from write-ahead log
+                if entry.expiry.is_none() {
+                    self.write_ahead(&[Record::Remove(Bytes::copy_from_slice(&key))])
+                        .await?;
+                }
+                entry.expiry = Some(Instant::now() + Duration::from_millis(ttl_millis));
+                entry.sliding = None;
+            }
+            None => {
+                return Err(Status::not_found(format!(
+                    "{} mapping doesn't exist.",
+                    String::from_utf8_lossy(&key)
+                )))
+            }
+        }
+        drop(db);
+        Self::invalidate(&self.cluster, key, &writer).await;
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC that removes the TTL of an existing KEY, replies with whether it had one
+    async fn persist(&self, args: Request<Byte>) -> Result<Response<Bool>, Status> {
+        metric!(self.metrics.request("persist"));
+        self.check_fence(&args)?;
+        let Byte { body } = args.into_inner();
+        let mut db = self.db.shard(&body).write().await;
+        match db.get_mut(&body[..]).filter(|entry| !entry.is_expired()) {
+            Some(entry) => {
+                // Mapping is durable again, so is written back to write-ahead log
+                if entry.expiry.is_some() {
+                    let record = Record::Put {
+                        key: Bytes::from(body.clone()),
+                        value: entry.value.clone(),
+                    };
+                    self.write_ahead(&[record]).await?;
+                }
+                entry.sliding = None;
+                Ok(Response::new(Bool {
+                    value: entry.expiry.take().is_some(),
+                }))
+            }
+            None => Err(Status::not_found(format!(
+                "{} mapping doesn't exist.",
+                String::from_utf8_lossy(&body)
+            ))),
+        }
+    }
+
+    /// RPC replying with seconds left before KEY expires, rounded up. As with Redis, `-1` if KEY has no TTL
+    /// and `-2` if it isn't mapped.
+    async fn ttl(&self, args: Request<Byte>) -> Result<Response<Seconds>, Status> {
+        metric!(self.metrics.request("ttl"));
+        let Byte { body } = args.into_inner();
+        let db = self.db.shard(&body).read().await;
+        let secs = match db.get(&body[..]).filter(|entry| !entry.is_expired()) {
+            Some(entry) => match entry.deadline() {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    left.as_millis().div_ceil(1000) as i64
+                }
+                None => -1,
+            },
+            None => -2,
+        };
+
+        Ok(Response::new(Seconds { secs }))
+    }
+
+    /// RPC that replaces VALUE of an existing KEY and adds KEY to invalidate queues of other Locals in cluster,
+    /// replying with the resulting version
+    async fn overwrite(&self, args: Request<KeyValue>) -> Result<Response<KeyVersion>, Status> {
+        metric!(self.metrics.request("overwrite"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let KeyValue {
+            key,
+            value,
+            compressed,
+            ..
+        } = args.into_inner();
+        self.check_size(&key, &value)?;
+        let value = compression::decode(value, compressed)
+            .map_err(|e| Status::invalid_argument(format!("Couldn't decompress VALUE: {}", e)))?;
+        let version = match self
+            .db
+            .shard(&key)
+            .write()
+            .await
+            .get_mut(&key[..])
+            .filter(|entry| !entry.is_expired())
+        {
+            Some(entry) => {
+                let value = Bytes::from(value);
+                let (old, new) = (footprint(&key, &entry.value), footprint(&key, &value));
+                let transient = entry.expiry.is_some();
+                self.charge_and_log(&key, Some(old), Some(new), value.clone(), transient)
+                    .await?;
+                entry.overwrite(value, writer.clone(), self.versions.next());
+                Self::notify(&self.changes, &key);
+                entry.version
+            }
+            None => {
+                return Err(Status::not_found(format!(
+                    "{} mapping doesn't exist.",
+                    String::from_utf8_lossy(&key)
+                )))
+            }
+        };
+
+        self.access.write(&key);
+        Self::invalidate(&self.cluster, key.clone(), &writer).await;
+
+        Ok(Response::new(KeyVersion { key, version }))
+    }
+
+    /// RPC that maps KEY to VALUE if it doesn't exist on Global, else replaces VALUE and adds KEY
+    /// to invalidate queues of other Locals in cluster, replying with the resulting version
+    async fn upsert(&self, args: Request<KeyValue>) -> Result<Response<Upserted>, Status> {
+        metric!(self.metrics.request("upsert"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let KeyValue {
+            key,
+            value,
+            compressed,
+            ..
+        } = args.into_inner();
+        self.check_size(&key, &value)?;
+        let value = compression::decode(value, compressed)
+            .map_err(|e| Status::invalid_argument(format!("Couldn't decompress VALUE: {}", e)))?;
+        let upserted = {
+            let mut db = self.db.shard(&key).write().await;
+            let value = Bytes::from(value);
+            let old = db.get(&key[..]).map(|entry| footprint(&key, &entry.value));
+            let new = Some(footprint(&key, &value));
+            self.charge_and_log(&key, old, new, value.clone(), transient(&db, &key))
+                .await?;
+            let upserted = match db.get_mut(&key[..]).filter(|entry| !entry.is_expired()) {
+                Some(entry) => {
+                    entry.overwrite(value, writer.clone(), self.versions.next());
+                    Upserted {
+                        created: false,
+                        version: entry.version,
+                    }
+                }
+                None => {
+                    let entry = Entry::new(value, None, writer.clone(), self.versions.next());
+                    let version = entry.version;
+                    db.insert(Bytes::from(key.clone()), entry);
+                    Upserted {
+                        created: true,
+                        version,
+                    }
+                }
+            };
+            Self::notify(&self.changes, &key);
+            upserted
+        };
+        self.access.write(&key);
+        self.evict_overflow().await;
+
+        // Push KEY into invalidate queue of all node, so stale caches are refreshed
+        if !upserted.created {
+            Self::invalidate(&self.cluster, key, &writer).await;
         }
+
+        Ok(Response::new(upserted))
+    }
+
+    /// RPC that maps KEY to VALUE only if KEY is currently at `expected_version`, or unmapped if it's 0,
+    /// replying with the resulting version. Rejected as aborted otherwise, with the current version attached.
+    async fn push_if_version(
+        &self,
+        args: Request<VersionedValue>,
+    ) -> Result<Response<KeyVersion>, Status> {
+        metric!(self.metrics.request("push_if_version"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let VersionedValue {
+            key,
+            value,
+            expected_version,
+        } = args.into_inner();
+        self.check_size(&key, &value)?;
+        let version = {
+            let mut db = self.db.shard(&key).write().await;
+            let current = db
+                .get(&key[..])
+                .filter(|entry| !entry.is_expired())
+                .map_or(0, |entry| entry.version);
+            if current != expected_version {
+                let mut status = Status::aborted(format!(
+                    "{} is at version {}, not {}.",
+                    String::from_utf8_lossy(&key),
+                    current,
+                    expected_version
+                ));
+                status
+                    .metadata_mut()
+                    .insert(VERSION_HEADER, MetadataValue::from(current));
+                return Err(status);
+            }
+            let value = Bytes::from(value);
+            let old = db.get(&key[..]).map(|entry| footprint(&key, &entry.value));
+            let new = Some(footprint(&key, &value));
+            self.charge_and_log(&key, old, new, value.clone(), transient(&db, &key))
+                .await?;
+            let version = match db.get_mut(&key[..]).filter(|entry| !entry.is_expired()) {
+                Some(entry) => {
+                    entry.overwrite(value, writer.clone(), self.versions.next());
+                    entry.version
+                }
+                None => {
+                    let entry = Entry::new(value, None, writer.clone(), self.versions.next());
+                    let version = entry.version;
+                    db.insert(Bytes::from(key.clone()), entry);
+                    version
+                }
+            };
+            Self::notify(&self.changes, &key);
+            version
+        };
+        self.access.write(&key);
+        self.evict_overflow().await;
+
+        // Push KEY into invalidate queue of all node, so stale caches are refreshed
+        Self::invalidate(&self.cluster, key.clone(), &writer).await;
+
+        Ok(Response::new(KeyVersion { key, version }))
+    }
+
+    /// RPC that replaces VALUE of KEY only if it currently equals `expected`, atomically under lock, replying
+    /// with whether it did along with the resulting version
+    async fn compare_and_swap(&self, args: Request<Swap>) -> Result<Response<Swapped>, Status> {
+        metric!(self.metrics.request("compare_and_swap"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let Swap {
+            key,
+            expected,
+            value,
+        } = args.into_inner();
+        self.check_size(&key, &value)?;
+        let version = {
+            let mut db = self.db.shard(&key).write().await;
+            match db.get_mut(&key[..]).filter(|entry| !entry.is_expired()) {
+                Some(entry) if entry.value[..] == expected[..] => {
+                    let value = Bytes::from(value);
+                    let (old, new) = (footprint(&key, &entry.value), footprint(&key, &value));
+                    let transient = entry.expiry.is_some();
+                    self.charge_and_log(&key, Some(old), Some(new), value.clone(), transient)
+                        .await?;
+                    entry.overwrite(value, writer.clone(), self.versions.next());
+                    Self::notify(&self.changes, &key);
+                    entry.version
+                }
+                Some(entry) => {
+                    return Ok(Response::new(Swapped {
+                        swapped: false,
+                        version: entry.version,
+                    }))
+                }
+                None => {
+                    return Err(Status::not_found(format!(
+                        "{} mapping doesn't exist.",
+                        String::from_utf8_lossy(&key)
+                    )))
+                }
+            }
+        };
+        self.access.write(&key);
+
+        Self::invalidate(&self.cluster, key, &writer).await;
+
+        Ok(Response::new(Swapped {
+            swapped: true,
+            version,
+        }))
+    }
+
+    /// RPC that atomically adds a delta, sent as VALUE, to the counter mapped to KEY and replies with the
+    /// result. Counters are stored as 8 byte big-endian integers, an unmapped KEY counts from 0.
+    async fn increment(&self, args: Request<KeyValue>) -> Result<Response<Counter>, Status> {
+        metric!(self.metrics.request("increment"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let KeyValue { key, value, .. } = args.into_inner();
+        let delta = decode_counter(&value)
+            .ok_or_else(|| Status::invalid_argument("Delta isn't an 8 byte integer."))?;
+
+        let counter = {
+            let mut db = self.db.shard(&key).write().await;
+            let current = db.get(&key[..]).filter(|entry| !entry.is_expired());
+            let counter = match current {
+                Some(entry) => decode_counter(&entry.value).ok_or_else(|| {
+                    Status::failed_precondition(format!(
+                        "{} isn't mapped to a counter.",
+                        String::from_utf8_lossy(&key)
+                    ))
+                })?,
+                None => 0,
+            }
+            .checked_add(delta)
+            .ok_or_else(|| Status::out_of_range("Counter would overflow."))?;
+
+            let value = Bytes::copy_from_slice(&counter.to_be_bytes());
+            let old = db.get(&key[..]).map(|entry| footprint(&key, &entry.value));
+            let new = Some(footprint(&key, &value));
+            self.charge_and_log(&key, old, new, value.clone(), transient(&db, &key))
+                .await?;
+            match db.get_mut(&key[..]).filter(|entry| !entry.is_expired()) {
+                Some(entry) => entry.overwrite(value, writer.clone(), self.versions.next()),
+                None => {
+                    db.insert(
+                        Bytes::from(key.clone()),
+                        Entry::new(value, None, writer.clone(), self.versions.next()),
+                    );
+                }
+            }
+            Self::notify(&self.changes, &key);
+            counter
+        };
+        self.access.write(&key);
+        self.evict_overflow().await;
+
+        Self::invalidate(&self.cluster, key, &writer).await;
+
+        Ok(Response::new(Counter { value: counter }))
+    }
+
+    /// RPC that concatenates VALUE to the end of the VALUE mapped to KEY, mapping KEY to VALUE if it isn't
+    /// mapped, and adds KEY to invalidate queues of other Locals in cluster
+    async fn append(&self, args: Request<KeyValue>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("append"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let KeyValue {
+            key,
+            value,
+            compressed,
+            ..
+        } = args.into_inner();
+        self.check_size(&key, &value)?;
+        let chunk = compression::decode(value, compressed)
+            .map_err(|e| Status::invalid_argument(format!("Couldn't decompress VALUE: {}", e)))?;
+
+        {
+            let mut db = self.db.shard(&key).write().await;
+            let current = db.get(&key[..]).filter(|entry| !entry.is_expired());
+            let mut value =
+                Vec::with_capacity(current.map_or(0, |entry| entry.value.len()) + chunk.len());
+            if let Some(entry) = current {
+                value.extend_from_slice(&entry.value);
+            }
+            value.extend_from_slice(&chunk);
+
+            let value = Bytes::from(value);
+            let old = db.get(&key[..]).map(|entry| footprint(&key, &entry.value));
+            let new = Some(footprint(&key, &value));
+            self.charge_and_log(&key, old, new, value.clone(), transient(&db, &key))
+                .await?;
+            match db.get_mut(&key[..]).filter(|entry| !entry.is_expired()) {
+                Some(entry) => entry.overwrite(value, writer.clone(), self.versions.next()),
+                None => {
+                    db.insert(
+                        Bytes::from(key.clone()),
+                        Entry::new(value, None, writer.clone(), self.versions.next()),
+                    );
+                }
+            }
+            Self::notify(&self.changes, &key);
+        }
+        self.access.write(&key);
+        self.evict_overflow().await;
+
+        Self::invalidate(&self.cluster, key, &writer).await;
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC that applies a batch of puts, removes, copies and renames atomically, in order. Either every
+    /// operation takes effect or, if any operation's source KEY isn't mapped when it's reached, none do.
+    async fn apply_batch(&self, args: Request<Batch>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("apply_batch"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let Batch { ops } = args.into_inner();
+        self.apply(ops, vec![], writer).await?;
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC that applies a batch of operations atomically as `apply_batch` does, provided every condition
+    /// holds, replying with whether they did. Conditions are checked under the same locks the batch is
+    /// applied under, so no write can come between.
+    async fn transaction(&self, args: Request<ConditionalBatch>) -> Result<Response<Bool>, Status> {
+        metric!(self.metrics.request("transaction"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let ConditionalBatch { ops, conditions } = args.into_inner();
+        let value = self.apply(ops, conditions, writer).await?;
+
+        Ok(Response::new(Bool { value }))
+    }
+
+    /// RPC that maps KEY to streamed VALUE, provided it doesn't already exist on Global
+    async fn push_file(
+        &self,
+        args: Request<tonic::Streaming<Byte>>,
+    ) -> Result<Response<KeyVersion>, Status> {
+        metric!(self.metrics.request("push_file"));
+        self.check_fence(&args)?;
+        // Logic to recieve streamed VALUES
+        let owner = uid(&args);
+        let terminated = args.metadata().contains_key(TERMINATED_HEADER);
+        // KEY is sent whole in the first frame, VALUE frames are sized as the client declares
+        let chunk = chunk_size(
+            &args,
+            MAX_BYTE_SIZE.min(self.max_message_size),
+            self.max_message_size,
+        );
+        let mut verifier = Verifier::new(checksum::declared(args.metadata()));
+        let mut stream = args.into_inner();
+        let mut i = 0;
+        let (mut key, mut buf) = (vec![], vec![]);
+        let mut complete = !terminated;
+        while let Some(byte) = stream.next().await {
+            let Byte { body } = byte?;
+            complete = !terminated || (i > 0 && body.is_empty());
+            // Reject frames larger than negotiated, as from a client framing with a mismatched size
+            let limit = if i == 0 { self.max_message_size } else { chunk };
+            if body.len() > limit {
+                return Err(Status::invalid_argument(format!(
+                    "Frame of {} bytes exceeds limit of {} bytes.",
+                    body.len(),
+                    limit
+                )));
+            }
+            if i == 0 {
+                key.append(&mut body.clone());
+            } else {
+                verifier.update(&body);
+                buf.append(&mut body.clone());
+            }
+            i += 1;
+        }
+        if !complete {
+            return Err(Status::aborted(
+                "Stream ended before it's terminating frame.",
+            ));
+        }
+        verifier.verify()?;
+
+        trace_event!(
+            key_len = key.len(),
+            size = buf.len(),
+            node = %String::from_utf8_lossy(&owner),
+            streaming = true,
+            frames = i,
+            "push"
+        );
+        metric!(self.metrics.pushed(key.len(), buf.len()));
+        // As with single packet pushes, KEYs already mapped are left as they are
+        let res = self.insert(key, buf, None, false, owner).await;
+        trace_event!(ok = res.is_ok(), "push applied");
+        res
+    }
+
+    /// RPC that returns VALUE associated with KEY, provided it exist on Global and is no larger than the
+    /// requesting Local takes in a single packet
+    async fn pull(&self, args: Request<Byte>) -> Result<Response<Value>, Status> {
+        metric!(self.metrics.request("pull"));
+        let holder = uid(&args);
+        let threshold = args
+            .metadata()
+            .get(COMPRESSION_HEADER)
+            .and_then(|threshold| threshold.to_str().ok()?.parse().ok());
+        let max_value: Option<usize> = args
+            .metadata()
+            .get(MAX_VALUE_HEADER)
+            .and_then(|max| max.to_str().ok()?.parse().ok());
+        let Byte { body } = args.into_inner();
+        let db = self.db.shard(&body).read().await;
+        let value = match db.get(&body[..]).filter(|entry| !entry.is_expired()) {
+            Some(entry) if max_value.is_some_and(|max| entry.value.len() > max) => {
+                let mut status = Status::out_of_range(format!(
+                    "{} is too large for a single packet.",
+                    String::from_utf8_lossy(&body)
+                ));
+                status
+                    .metadata_mut()
+                    .insert(SIZE_HEADER, MetadataValue::from(entry.value.len() as u64));
+                return Err(status);
+            }
+            Some(entry) => {
+                trace_event!(
+                    key_len = body.len(),
+                    size = entry.value.len(),
+                    node = %String::from_utf8_lossy(&holder),
+                    streaming = false,
+                    "pull"
+                );
+                metric!(self.metrics.pulled(body.len(), entry.value.len()));
+                entry.touch();
+                entry.hold(holder);
+                let (body, compressed) = compression::encode(&entry.value, threshold);
+                Value {
+                    body,
+                    compressed,
+                    version: entry.version,
+                    sliding: entry.sliding.is_some(),
+                }
+            }
+            None => {
+                return Err(Status::not_found(format!(
+                    "{} mapping doesn't exist.",
+                    String::from_utf8_lossy(&body)
+                )))
+            }
+        };
+        drop(db);
+        self.access.read(&body);
+
+        Ok(Response::new(value))
+    }
+
+    /// RPC that replies with upto `length` bytes of VALUE associated with KEY, starting at `offset`.
+    /// Ranges running past the end of VALUE are cut short, those starting past it are out of range.
+    async fn pull_range(&self, args: Request<ByteRange>) -> Result<Response<Byte>, Status> {
+        metric!(self.metrics.request("pull_range"));
+        let ByteRange {
+            key,
+            offset,
+            length,
+        } = args.into_inner();
+        let db = self.db.shard(&key).read().await;
+        let entry = db
+            .get(&key[..])
+            .filter(|entry| !entry.is_expired())
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "{} mapping doesn't exist.",
+                    String::from_utf8_lossy(&key)
+                ))
+            })?;
+        let len = entry.value.len() as u64;
+        if offset > len {
+            return Err(Status::out_of_range(format!(
+                "Offset {} beyond end of {} byte VALUE.",
+                offset, len
+            )));
+        }
+        let end = offset.saturating_add(length).min(len);
+        let body = entry.value[offset as usize..end as usize].to_vec();
+        metric!(self.metrics.pulled(key.len(), body.len()));
+        entry.touch();
+        drop(db);
+        self.access.read(&key);
+
+        Ok(Response::new(Byte { body }))
+    }
+
+    /// Type to allow streaming of VALUE via RPC
+    type PullFileStream = ReceiverStream<Result<Byte, Status>>;
+
+    /// RPC that streams VALUE associated with KEY, if it exist on Global
+    async fn pull_file(
+        &self,
+        args: Request<Byte>,
+    ) -> Result<Response<Self::PullFileStream>, Status> {
+        metric!(self.metrics.request("pull_file"));
+        // Create a double ended channel for transporting VALUE packets processed within thread
+        let (tx, rx) = mpsc::channel(4);
+        let holder = uid(&args);
+        let chunk = chunk_size(&args, self.chunk_size, self.max_message_size);
+        let requested = args.metadata().contains_key(CHECKSUM_HEADER);
+        let Byte { body } = args.into_inner();
+        let (val, version, sliding) = {
+            let db = self.db.shard(&body).read().await;
+            // KEY may have been removed since the client checked, report it rather than panicking
+            let entry = match db.get(&body[..]).filter(|entry| !entry.is_expired()) {
+                Some(entry) => entry,
+                None => {
+                    return Err(Status::not_found(format!(
+                        "{} mapping doesn't exist.",
+                        String::from_utf8_lossy(&body)
+                    )))
+                }
+            };
+            trace_event!(
+                key_len = body.len(),
+                size = entry.value.len(),
+                node = %String::from_utf8_lossy(&holder),
+                streaming = true,
+                chunk,
+                "pull"
+            );
+            metric!(self.metrics.pulled(body.len(), entry.value.len()));
+            entry.touch();
+            entry.hold(holder);
+            (entry.value.clone(), entry.version, entry.sliding.is_some())
+        };
+        self.access.read(&body);
+        let mut res = Response::new(ReceiverStream::new(rx));
+        if requested {
+            res.metadata_mut().insert(
+                CHECKSUM_HEADER,
+                MetadataValue::from(checksum::checksum(&val) as u64),
+            );
+        }
+        res.metadata_mut()
+            .insert(SIZE_HEADER, MetadataValue::from(val.len() as u64));
+        res.metadata_mut()
+            .insert(VERSION_HEADER, MetadataValue::from(version));
+        if sliding {
+            res.metadata_mut()
+                .insert(SLIDING_HEADER, MetadataValue::from_static("1"));
+        }
+
+        // Spawn thread to manage partitioning of a large VALUE into packet frames
+        tokio::spawn(async move {
+            // Size each frame upto the negotiated chunk size and encapsulate in response packet,
+            // the final frame carries any trailing bytes that don't fill a whole frame
+            for frame in val.chunks(chunk) {
+                let frame = Byte {
+                    body: frame.to_vec(),
+                };
+                // Stop streaming if Local has gone away
+                if tx.send(Ok(frame)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(res)
+    }
+
+    /// RPC that maps each streamed KEY to VALUE if it isn't already mapped on Global,
+    /// replying with the accepted KEYs along with their versions and a count of those already present.
+    /// Errs on the first mapping that fails otherwise, those accepted before it remain mapped.
+    async fn push_batch(
+        &self,
+        args: Request<tonic::Streaming<KeyValue>>,
+    ) -> Result<Response<BatchResult>, Status> {
+        metric!(self.metrics.request("push_batch"));
+        self.check_fence(&args)?;
+        let owner = uid(&args);
+        let mut stream = args.into_inner();
+        let (mut accepted, mut versions, mut present) = (vec![], vec![], 0);
+        while let Some(pair) = stream.next().await {
+            let KeyValue {
+                key,
+                value,
+                compressed,
+                ..
+            } = pair?;
+            self.check_size(&key, &value)?;
+            let value = compression::decode(value, compressed).map_err(|e| {
+                Status::invalid_argument(format!("Couldn't decompress VALUE: {}", e))
+            })?;
+            match self
+                .insert(key.clone(), value, None, false, owner.clone())
+                .await
+            {
+                Ok(res) => {
+                    accepted.push(key);
+                    versions.push(res.into_inner().version);
+                }
+                Err(status) if status.code() == Code::AlreadyExists => present += 1,
+                Err(status) => return Err(status),
+            }
+        }
+
+        Ok(Response::new(BatchResult {
+            accepted,
+            present,
+            versions,
+        }))
+    }
+
+    /// Type to allow streaming of KEY -> VALUE mappings via RPC
+    type PullBatchStream = ReceiverStream<Result<KeyValue, Status>>;
+
+    /// RPC that streams a mapping for each of the requested KEYs that exist on Global, skipping missing ones
+    async fn pull_batch(
+        &self,
+        args: Request<Keys>,
+    ) -> Result<Response<Self::PullBatchStream>, Status> {
+        metric!(self.metrics.request("pull_batch"));
+        let holder = uid(&args);
+        let threshold = args
+            .metadata()
+            .get(COMPRESSION_HEADER)
+            .and_then(|threshold| threshold.to_str().ok()?.parse().ok());
+        let Keys { keys } = args.into_inner();
+
+        // Lookup mappings under lock, so that lock isn't held while streaming
+        let mut pairs = vec![];
+        for key in keys {
+            let db = self.db.shard(&key).read().await;
+            if let Some(entry) = db.get(&key[..]).filter(|entry| !entry.is_expired()) {
+                entry.touch();
+                entry.hold(holder.clone());
+                let (value, compressed) = compression::encode(&entry.value, threshold);
+                pairs.push(KeyValue {
+                    key,
+                    value,
+                    compressed,
+                    sliding: entry.sliding.is_some(),
+                    version: entry.version,
+                });
+            }
+        }
+        for pair in &pairs {
+            self.access.read(&pair.key);
+        }
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            for pair in pairs {
+                if tx.send(Ok(pair)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// Type to allow streaming of KEY lookups via RPC
+    type MultiPullStreamStream = ReceiverStream<Result<Lookup, Status>>;
+
+    /// RPC that streams the result of looking up each requested KEY as soon as it's retrieved, in order,
+    /// KEYs that don't exist on Global are marked as not found
+    async fn multi_pull_stream(
+        &self,
+        args: Request<Keys>,
+    ) -> Result<Response<Self::MultiPullStreamStream>, Status> {
+        metric!(self.metrics.request("multi_pull_stream"));
+        let (db, access) = (self.db.clone(), self.access.clone());
+        let holder = uid(&args);
+        let threshold = args
+            .metadata()
+            .get(COMPRESSION_HEADER)
+            .and_then(|threshold| threshold.to_str().ok()?.parse().ok());
+        let Keys { keys } = args.into_inner();
+
+        // Lookup each KEY only once the client has room for it, so lookups don't run ahead of the client
+        let (tx, rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            for key in keys {
+                let permit = match tx.reserve().await {
+                    Ok(permit) => permit,
+                    Err(_) => break,
+                };
+                let db = db.shard(&key).read().await;
+                let lookup = match db.get(&key[..]).filter(|entry| !entry.is_expired()) {
+                    Some(entry) => {
+                        entry.touch();
+                        entry.hold(holder.clone());
+                        let (value, compressed) = compression::encode(&entry.value, threshold);
+                        Lookup {
+                            key,
+                            value,
+                            compressed,
+                            found: true,
+                            sliding: entry.sliding.is_some(),
+                            version: entry.version,
+                        }
+                    }
+                    None => Lookup {
+                        key,
+                        ..Default::default()
+                    },
+                };
+                drop(db);
+                if lookup.found {
+                    access.read(&lookup.key);
+                }
+                permit.send(Ok(lookup));
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// Type to allow streaming of KEYs via RPC
+    type ListKeysStream = ReceiverStream<Result<Byte, Status>>;
+
+    /// RPC that streams every KEY mapped on Global, one per frame
+    async fn list_keys(&self, _: Request<Null>) -> Result<Response<Self::ListKeysStream>, Status> {
+        metric!(self.metrics.request("list_keys"));
+        // Snapshot KEYs under lock, so that lock isn't held while streaming
+        let mut keys: Vec<Bytes> = vec![];
+        for shard in self.db.shards() {
+            keys.extend(
+                shard
+                    .read()
+                    .await
+                    .iter()
+                    .filter(|(_, entry)| !entry.is_expired())
+                    .map(|(key, _)| key.clone()),
+            );
+        }
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            for key in keys {
+                if tx.send(Ok(Byte { body: key.to_vec() })).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// Type to allow streaming of mappings via RPC
+    type ScanPrefixStream = ReceiverStream<Result<KeyValue, Status>>;
+
+    /// RPC that streams every mapping whose KEY starts with prefix, in order of KEY. Mappings aren't held
+    /// in order, so this visits each of them, at a cost linear in the number of mappings on Global.
+    async fn scan_prefix(
+        &self,
+        args: Request<Byte>,
+    ) -> Result<Response<Self::ScanPrefixStream>, Status> {
+        metric!(self.metrics.request("scan_prefix"));
+        let holder = uid(&args);
+        let threshold = args
+            .metadata()
+            .get(COMPRESSION_HEADER)
+            .and_then(|threshold| threshold.to_str().ok()?.parse().ok());
+        let prefix = args.into_inner().body;
+
+        // Snapshot matching mappings under lock, so that lock isn't held while streaming
+        let mut pairs = vec![];
+        for shard in self.db.shards() {
+            for (key, entry) in shard.read().await.iter() {
+                if entry.is_expired() || !key.starts_with(&prefix) {
+                    continue;
+                }
+                entry.touch();
+                entry.hold(holder.clone());
+                let (value, compressed) = compression::encode(&entry.value, threshold);
+                pairs.push(KeyValue {
+                    key: key.to_vec(),
+                    value,
+                    compressed,
+                    sliding: entry.sliding.is_some(),
+                    version: entry.version,
+                });
+            }
+        }
+        pairs.sort_by(|a, b| a.key.cmp(&b.key));
+        for pair in &pairs {
+            self.access.read(&pair.key);
+        }
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            for pair in pairs {
+                if tx.send(Ok(pair)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// Type to allow streaming of mappings via RPC
+    type BootstrapStream = ReceiverStream<Result<KeyValue, Status>>;
+
+    /// RPC that streams every mapping with a small enough VALUE, for a Local to warm it's cache with on
+    /// joining. Requesting Local is recorded as holding each VALUE streamed.
+    async fn bootstrap(
+        &self,
+        args: Request<Byte>,
+    ) -> Result<Response<Self::BootstrapStream>, Status> {
+        metric!(self.metrics.request("bootstrap"));
+        let uid = Bytes::from(args.into_inner().body);
+        // Snapshot mappings under lock, so that lock isn't held while streaming
+        let mut pairs: Vec<(Bytes, Bytes, bool, u64)> = vec![];
+        for shard in self.db.shards() {
+            for (key, entry) in shard.read().await.iter() {
+                if entry.is_expired() || entry.value.len() > BOOTSTRAP_MAX_VALUE {
+                    continue;
+                }
+                entry.touch();
+                entry.hold(uid.clone());
+                pairs.push((
+                    key.clone(),
+                    entry.value.clone(),
+                    entry.sliding.is_some(),
+                    entry.version,
+                ));
+            }
+        }
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            for (key, value, sliding, version) in pairs {
+                let pair = KeyValue {
+                    key: key.to_vec(),
+                    value: value.to_vec(),
+                    sliding,
+                    version,
+                    ..Default::default()
+                };
+                if tx.send(Ok(pair)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// Type to allow streaming of changed KEYs via RPC
+    type WatchStream = ReceiverStream<Result<Byte, Status>>;
+
+    /// RPC that streams each KEY starting with prefix as soon as it's written, overwritten or removed.
+    /// Complements the polled invalidation queue, which is left as is.
+    async fn watch(&self, args: Request<Byte>) -> Result<Response<Self::WatchStream>, Status> {
+        metric!(self.metrics.request("watch"));
+        let prefix = args.into_inner().body;
+        let mut changes = self.changes.subscribe();
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            loop {
+                let key = tokio::select! {
+                    _ = tx.closed() => return,
+                    key = changes.recv() => key,
+                };
+                let key = match key {
+                    Ok(key) if key.starts_with(&prefix) => key,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => {
+                        let status = Status::data_loss("Watcher fell behind on changes.");
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if tx.send(Ok(Byte { body: key.to_vec() })).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// Type to allow streaming of mutations via RPC
+    type ChangesStream = ReceiverStream<Result<Change, Status>>;
+
+    /// RPC that streams every mutation from a sequence number onwards, in order, then each new one as it's
+    /// made. Errs if mutations since the sequence number are no longer retained. Sequence numbers restart
+    /// from 1 along with Global.
+    async fn changes(
+        &self,
+        args: Request<Offset>,
+    ) -> Result<Response<Self::ChangesStream>, Status> {
+        metric!(self.metrics.request("changes"));
+        let mut from = args.into_inner().offset;
+        let mut appended = self.feed.lock().unwrap().subscribe();
+        let feed = self.feed.clone();
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            loop {
+                let records = feed.lock().unwrap().since(from);
+                let records = match records {
+                    Some(records) => records,
+                    None => {
+                        let status = Status::out_of_range(format!(
+                            "Changes since {} are no longer retained.",
+                            from
+                        ));
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                };
+                for (sequence, record) in records {
+                    let change = match record {
+                        Record::Put { key, value } => Change {
+                            key: key.to_vec(),
+                            value: value.to_vec(),
+                            sequence,
+                            ..Default::default()
+                        },
+                        Record::Remove(key) => Change {
+                            key: key.to_vec(),
+                            removed: true,
+                            sequence,
+                            ..Default::default()
+                        },
+                    };
+                    if tx.send(Ok(change)).await.is_err() {
+                        return;
+                    }
+                    from = sequence + 1;
+                }
+                tokio::select! {
+                    _ = tx.closed() => return,
+                    changed = appended.changed() => if changed.is_err() {
+                        return;
+                    },
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// Type to allow streaming of changes via RPC
+    type WatchPrefixStream = ReceiverStream<Result<Change, Status>>;
+
+    /// RPC that streams every mapping whose KEY starts with prefix, then a marker that this snapshot is
+    /// complete, followed by the state of each such mapping as it's written or removed from then on
+    async fn watch_prefix(
+        &self,
+        args: Request<Byte>,
+    ) -> Result<Response<Self::WatchPrefixStream>, Status> {
+        metric!(self.metrics.request("watch_prefix"));
+        let prefix = args.into_inner().body;
+
+        // Subscribe before taking snapshot, so that no change made in between is missed
+        let mut changes = self.changes.subscribe();
+        let mut snapshot = vec![];
+        for shard in self.db.shards() {
+            snapshot.extend(
+                shard
+                    .read()
+                    .await
+                    .iter()
+                    .filter(|(key, entry)| key.starts_with(&prefix) && !entry.is_expired())
+                    .map(|(key, entry)| Change {
+                        key: key.to_vec(),
+                        value: entry.value.to_vec(),
+                        ..Default::default()
+                    }),
+            );
+        }
+        snapshot.push(Change {
+            synced: true,
+            ..Default::default()
+        });
+
+        let db = self.db.clone();
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            for change in snapshot {
+                if tx.send(Ok(change)).await.is_err() {
+                    return;
+                }
+            }
+
+            loop {
+                let key = tokio::select! {
+                    _ = tx.closed() => return,
+                    key = changes.recv() => key,
+                };
+                let key = match key {
+                    Ok(key) if key.starts_with(&prefix) => key,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => {
+                        let status = Status::data_loss("Watcher fell behind on changes.");
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+
+                // Current state of mapping covers every change to it so far
+                let change = match db.shard(&key).read().await.get(&key) {
+                    Some(entry) if !entry.is_expired() => Change {
+                        key: key.to_vec(),
+                        value: entry.value.to_vec(),
+                        ..Default::default()
+                    },
+                    _ => Change {
+                        key: key.to_vec(),
+                        removed: true,
+                        ..Default::default()
+                    },
+                };
+                if tx.send(Ok(change)).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// RPC to remove KEY mappings on Global and add KEY to invalidate queues of other Locals in cluster
+    async fn remove(&self, args: Request<Byte>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("remove"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let key = args.into_inner().body;
+
+        // Remove KEY mapping from Global
+        let mut db = self.db.shard(&key).write().await;
+        // An expired mapping yet to be swept is already gone, as it is to reads
+        if db.get(&key[..]).is_none_or(|entry| entry.is_expired()) {
+            return Err(Status::not_found(format!(
+                "Couldn't remove {}",
+                String::from_utf8_lossy(&key)
+            )));
+        }
+        self.log(Record::Remove(Bytes::copy_from_slice(&key)))
+            .await?;
+        trace_event!(key_len = key.len(), "remove");
+        if let Some(entry) = db.remove(&key[..]) {
+            let old = footprint(&key, &entry.value);
+            self.quotas.lock().unwrap().record(&key, Some(old), None);
+        }
+        Self::notify(&self.changes, &key);
+        drop(db);
+        // Peers are only invalidated once the mapping is gone, lest they re-fetch the removed VALUE
+        Self::invalidate(&self.cluster, key, &writer).await;
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC to remove every mapping whose KEY starts with prefix and add each KEY to invalidate queues of
+    /// other Locals in cluster, returning number of mappings removed. Visits every mapping, as `scan_prefix` does.
+    async fn remove_prefix(&self, args: Request<Byte>) -> Result<Response<Size>, Status> {
+        metric!(self.metrics.request("remove_prefix"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let prefix = args.into_inner().body;
+
+        let mut size = 0;
+        for shard in self.db.shards() {
+            let mut shard = shard.write().await;
+            let keys: Vec<Bytes> = shard
+                .keys()
+                .filter(|key| key.starts_with(&prefix))
+                .cloned()
+                .collect();
+            // Each removal is logged under it's shard's lock, ordering it among writes to that shard
+            for key in keys {
+                self.log(Record::Remove(key.clone())).await?;
+                if let Some(entry) = shard.remove(&key) {
+                    // Expired mappings are dropped along the way, but were already gone
+                    if !entry.is_expired() {
+                        size += 1;
+                    }
+                    let old = footprint(&key, &entry.value);
+                    self.quotas.lock().unwrap().record(&key, Some(old), None);
+                }
+                Self::notify(&self.changes, &key);
+                Self::invalidate(&self.cluster, key.to_vec(), &writer).await;
+            }
+        }
+        trace_event!(prefix_len = prefix.len(), removed = size, "remove_prefix");
+
+        Ok(Response::new(Size { size }))
+    }
+
+    /// RPC to help Local invalidate cached VALUEs
+    async fn update(&self, args: Request<Byte>) -> Result<Response<Invalidation>, Status> {
+        metric!(self.metrics.request("update"));
+        // Extract and return a KEY from invalidate queue associated with requesting Local
+        let Byte { body } = args.into_inner();
+        let cluster = self.cluster.lock().await;
+        let queue = cluster.get(&body[..]).ok_or_else(|| {
+            Status::failed_precondition(format!(
+                "{} node not registered.",
+                String::from_utf8_lossy(&body)
+            ))
+        })?;
+        let invalidation = queue.lock().await.pop_front();
+        match invalidation {
+            Some(invalidation) => Ok(Response::new(invalidation)),
+            None => Err(Status::not_found("")),
+        }
+    }
+
+    /// RPC to help Local invalidate cached VALUEs, draining it's entire invalidate queue in a single reply.
+    /// KEYs are sent as a length-prefixed list, compressed if larger than the Local asks for. Invalidations
+    /// are resent until Local acknowledges them with the batch's sequence number on it's next update.
+    async fn update_batch(
+        &self,
+        args: Request<UpdateAck>,
+    ) -> Result<Response<Invalidations>, Status> {
+        metric!(self.metrics.request("update_batch"));
+        let threshold = args
+            .metadata()
+            .get(COMPRESSION_HEADER)
+            .and_then(|threshold| threshold.to_str().ok()?.parse().ok());
+        let UpdateAck { uid, seq } = args.into_inner();
+        let (seq, queue) = match self.cluster.lock().await.get(&uid[..]) {
+            Some(queue) => queue.lock().await.drain(seq),
+            None => {
+                return Err(Status::failed_precondition(format!(
+                    "{} node not registered.",
+                    String::from_utf8_lossy(&uid)
+                )))
+            }
+        };
+
+        // KEYs invalidated before a flush are flushed regardless, only those after the last one matter
+        let draining = queue.iter().any(|invalidation| invalidation.draining);
+        let last_flush = queue.iter().rposition(|invalidation| invalidation.flush);
+        let keys = snapshot::encode_keys(
+            queue[last_flush.map_or(0, |i| i + 1)..]
+                .iter()
+                .filter(|invalidation| !invalidation.draining)
+                .map(|invalidation| &invalidation.key[..]),
+        );
+        let (keys, compressed) = compression::encode(&keys, threshold);
+
+        Ok(Response::new(Invalidations {
+            keys,
+            compressed,
+            flush: last_flush.is_some(),
+            draining,
+            seq,
+        }))
+    }
+
+    /// RPC to remove all mappings on Global and direct every Local in cluster to flush it's cache
+    async fn clear_all(&self, args: Request<Null>) -> Result<Response<Size>, Status> {
+        metric!(self.metrics.request("clear_all"));
+        self.check_fence(&args)?;
+        let mut size = 0;
+        for shard in self.db.shards() {
+            let mut shard = shard.write().await;
+            // Expired mappings are dropped along the way, but were already gone
+            size += shard.values().filter(|entry| !entry.is_expired()).count() as i32;
+            // Each removal is logged under it's shard's lock, ordering it among writes to that shard
+            for key in shard.keys() {
+                self.log(Record::Remove(key.clone())).await?;
+            }
+            for (key, entry) in shard.drain() {
+                let old = footprint(&key, &entry.value);
+                self.quotas.lock().unwrap().record(&key, Some(old), None);
+                Self::notify(&self.changes, &key);
+            }
+        }
+        Self::broadcast(
+            &self.cluster,
+            Invalidation {
+                flush: true,
+                ..Default::default()
+            },
+        )
+        .await;
+
+        Ok(Response::new(Size { size }))
+    }
+
+    /// RPC to push VALUE onto the back of queue named KEY, creating queue if it doesn't exist
+    async fn en_queue(&self, args: Request<KeyValue>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("en_queue"));
+        self.check_fence(&args)?;
+        let KeyValue { key, value, .. } = args.into_inner();
+        self.queues.lock().await.push(&key, Bytes::from(value))?;
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC to pop VALUE from the front of queue named KEY, along with an id to acknowledge it by.
+    /// VALUE is redelivered unless acknowledged within the queue's visibility timeout.
+    async fn de_queue(&self, args: Request<Byte>) -> Result<Response<QueueMessage>, Status> {
+        metric!(self.metrics.request("de_queue"));
+        self.check_fence(&args)?;
+        let Byte { body } = args.into_inner();
+        let popped = self.queues.lock().await.pop(&body);
+        match popped {
+            Some((id, value)) => Ok(Response::new(QueueMessage {
+                key: body,
+                id,
+                value: value.to_vec(),
+            })),
+            None => Err(Status::not_found(format!(
+                "Queue {} is empty.",
+                String::from_utf8_lossy(&body)
+            ))),
+        }
+    }
+
+    /// RPC to acknowledge a VALUE popped from queue as processed, so that it isn't redelivered
+    async fn ack(&self, args: Request<QueueMessage>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("ack"));
+        self.check_fence(&args)?;
+        let QueueMessage { id, .. } = args.into_inner();
+        match self.queues.lock().await.ack(id) {
+            true => Ok(Response::new(Null {})),
+            false => Err(Status::not_found(format!(
+                "Message {} isn't awaiting acknowledgement.",
+                id
+            ))),
+        }
+    }
+
+    /// RPC to create queue named KEY holding upto capacity VALUEs, unbounded if capacity is 0, and giving
+    /// popped VALUEs the visibility timeout to be acknowledged within, the default if 0. VALUEs delivered
+    /// max deliveries times without acknowledgement are moved to the queue's dead-letter queue, never if 0.
+    /// Configures an existing queue, VALUEs already held beyond capacity are kept.
+    async fn create_queue(&self, args: Request<QueueConfig>) -> Result<Response<Null>, Status> {
+        metric!(self.metrics.request("create_queue"));
+        self.check_fence(&args)?;
+        let QueueConfig {
+            key,
+            capacity,
+            visibility_timeout_millis,
+            max_deliveries,
+        } = args.into_inner();
+        self.queues.lock().await.configure(
+            &key,
+            Some(capacity as usize).filter(|capacity| *capacity > 0),
+            Some(Duration::from_millis(visibility_timeout_millis))
+                .filter(|timeout| !timeout.is_zero()),
+            Some(max_deliveries).filter(|max| *max > 0),
+        );
+
+        Ok(Response::new(Null {}))
+    }
+
+    /// RPC to list VALUEs moved to the dead-letter queue of queue named KEY, which may be consumed
+    /// as the queue named `KEY:dlq`
+    async fn dead_letters(&self, args: Request<Byte>) -> Result<Response<Values>, Status> {
+        metric!(self.metrics.request("dead_letters"));
+        let Byte { body } = args.into_inner();
+        let values = self
+            .queues
+            .lock()
+            .await
+            .dead_letters(&body)
+            .iter()
+            .map(|value| value.to_vec())
+            .collect();
+
+        Ok(Response::new(Values { values }))
+    }
+
+    /// RPC to count VALUEs awaiting delivery from queue named KEY, 0 if there's no such queue
+    async fn queue_len(&self, args: Request<Byte>) -> Result<Response<Size>, Status> {
+        metric!(self.metrics.request("queue_len"));
+        let Byte { body } = args.into_inner();
+        let size = self.queues.lock().await.len(&body);
+
+        Ok(Response::new(Size { size: size as i32 }))
+    }
+
+    /// RPC to get VALUE at the front of queue named KEY, without popping it
+    async fn queue_peek(&self, args: Request<Byte>) -> Result<Response<Byte>, Status> {
+        metric!(self.metrics.request("queue_peek"));
+        let Byte { body } = args.into_inner();
+        match self.queues.lock().await.peek(&body) {
+            Some(value) => Ok(Response::new(Byte {
+                body: value.to_vec(),
+            })),
+            None => Err(Status::not_found(format!(
+                "Queue {} is empty.",
+                String::from_utf8_lossy(&body)
+            ))),
+        }
+    }
+
+    /// RPC to pop VALUE from the front of queue named KEY as with `de_queue`, awaiting one for upto the
+    /// given timeout if queue is empty
+    async fn de_queue_blocking(
+        &self,
+        args: Request<QueueWait>,
+    ) -> Result<Response<QueueMessage>, Status> {
+        metric!(self.metrics.request("de_queue_blocking"));
+        self.check_fence(&args)?;
+        let QueueWait {
+            key,
+            timeout_millis,
+        } = args.into_inner();
+        let deadline = Instant::now() + Duration::from_millis(timeout_millis);
+        loop {
+            let pushed = {
+                let mut queues = self.queues.lock().await;
+                if let Some((id, value)) = queues.pop(&key) {
+                    return Ok(Response::new(QueueMessage {
+                        key,
+                        id,
+                        value: value.to_vec(),
+                    }));
+                }
+                queues.pushed(&key)
+            };
+            // A VALUE pushed since lock was released leaves a permit, so the wakeup isn't missed
+            if time::timeout_at(deadline, pushed.notified()).await.is_err() {
+                return Err(Status::not_found(format!(
+                    "Queue {} is empty.",
+                    String::from_utf8_lossy(&key)
+                )));
+            }
+        }
+    }
+
+    /// RPC to append VALUE as an event onto log named KEY, returning the event's offset
+    async fn log_append(&self, args: Request<KeyValue>) -> Result<Response<Offset>, Status> {
+        metric!(self.metrics.request("log_append"));
+        self.check_fence(&args)?;
+        let KeyValue { key, value, .. } = args.into_inner();
+        let mut logs = self.logs.lock().await;
+        let log = logs.entry(Bytes::from(key)).or_insert_with(Vec::new);
+        log.push(Bytes::from(value));
+
+        Ok(Response::new(Offset {
+            offset: log.len() as u64 - 1,
+        }))
+    }
+
+    /// RPC to atomically add `delta` to FIELD of hash mapped to KEY, absent hashes and fields count from 0.
+    /// Hashes are VALUEs like any other, so are persisted, removed and invalidated alike.
+    async fn h_incr_by(&self, args: Request<FieldDelta>) -> Result<Response<Counter>, Status> {
+        metric!(self.metrics.request("h_incr_by"));
+        self.check_fence(&args)?;
+        let writer = uid(&args);
+        let FieldDelta { key, field, delta } = args.into_inner();
+
+        let counter = {
+            let mut db = self.db.shard(&key).write().await;
+            let current = db.get(&key[..]).filter(|entry| !entry.is_expired());
+            let mut fields = match current {
+                Some(entry) => decode_hash(&entry.value).ok_or_else(|| {
+                    Status::failed_precondition(format!(
+                        "{} isn't mapped to a hash.",
+                        String::from_utf8_lossy(&key)
+                    ))
+                })?,
+                None => vec![],
+            };
+            let index = match fields.iter().position(|(name, _)| name[..] == field[..]) {
+                Some(index) => index,
+                None => {
+                    fields.push((Bytes::from(field), 0));
+                    fields.len() - 1
+                }
+            };
+            let counter = fields[index]
+                .1
+                .checked_add(delta)
+                .ok_or_else(|| Status::out_of_range("Counter would overflow."))?;
+            fields[index].1 = counter;
+
+            let value = encode_hash(&fields);
+            let old = db.get(&key[..]).map(|entry| footprint(&key, &entry.value));
+            let new = Some(footprint(&key, &value));
+            self.charge_and_log(&key, old, new, value.clone(), transient(&db, &key))
+                .await?;
+            match db.get_mut(&key[..]).filter(|entry| !entry.is_expired()) {
+                Some(entry) => entry.overwrite(value, writer.clone(), self.versions.next()),
+                None => {
+                    db.insert(
+                        Bytes::from(key.clone()),
+                        Entry::new(value, None, writer.clone(), self.versions.next()),
+                    );
+                }
+            }
+            Self::notify(&self.changes, &key);
+            counter
+        };
+        self.access.write(&key);
+        self.evict_overflow().await;
+
+        Self::invalidate(&self.cluster, key, &writer).await;
+
+        Ok(Response::new(Counter { value: counter }))
+    }
+
+    /// RPC to read FIELD of hash mapped to KEY
+    async fn h_get(&self, args: Request<KeyField>) -> Result<Response<Counter>, Status> {
+        metric!(self.metrics.request("h_get"));
+        let KeyField { key, field } = args.into_inner();
+        let db = self.db.shard(&key).read().await;
+        let entry = db
+            .get(&key[..])
+            .filter(|entry| !entry.is_expired())
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "{} mapping doesn't exist.",
+                    String::from_utf8_lossy(&key)
+                ))
+            })?;
+        let fields = decode_hash(&entry.value).ok_or_else(|| {
+            Status::failed_precondition(format!(
+                "{} isn't mapped to a hash.",
+                String::from_utf8_lossy(&key)
+            ))
+        })?;
+        entry.touch();
+        drop(db);
+        self.access.read(&key);
+
+        match fields.into_iter().find(|(name, _)| name[..] == field[..]) {
+            Some((_, value)) => Ok(Response::new(Counter { value })),
+            None => Err(Status::not_found(format!(
+                "{} has no field {}.",
+                String::from_utf8_lossy(&key),
+                String::from_utf8_lossy(&field)
+            ))),
+        }
+    }
+
+    /// RPC to read upto `max` events from log named KEY, starting at `from_offset`
+    async fn log_read(&self, args: Request<LogRange>) -> Result<Response<Events>, Status> {
+        metric!(self.metrics.request("log_read"));
+        let LogRange {
+            key,
+            from_offset,
+            max,
+        } = args.into_inner();
+        match self.logs.lock().await.get(&key[..]) {
+            Some(log) => Ok(Response::new(Events {
+                from_offset,
+                events: log
+                    .iter()
+                    .skip(from_offset as usize)
+                    .take(max as usize)
+                    .map(|event| event.to_vec())
+                    .collect(),
+            })),
+            None => Err(Status::not_found(format!(
+                "Log {} doesn't exist.",
+                String::from_utf8_lossy(&key)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dstore_proto::dstore_client::DstoreClient;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{transport::Channel, Code};
+
+    /// Serve a default Global on a port chosen by the OS, connecting a client once it's bound
+    async fn serve() -> DstoreClient<Channel> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(Global::new().into_service())
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        DstoreClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn update_from_unregistered_node_fails_cleanly() {
+        let global = Global::new();
+        let uid = Byte {
+            body: b"127.0.0.1:50000".to_vec(),
+        };
+
+        let status = global.update(Request::new(uid.clone())).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        let ack = UpdateAck {
+            uid: uid.body,
+            seq: 0,
+        };
+        let status = global.update_batch(Request::new(ack)).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn push_emits_event() {
+        let global = Global::new();
+        let req = KeyValue {
+            key: b"hello".to_vec(),
+            value: b"world".to_vec(),
+            ..Default::default()
+        };
+        global.push(Request::new(req)).await.unwrap();
+
+        assert!(logs_contain("push"));
+        assert!(logs_contain("key_len=5 size=5"));
+        assert!(logs_contain("streaming=false"));
+    }
+
+    #[tokio::test]
+    async fn leave_removes_node_from_cluster() {
+        let global = Global::new();
+        let uid = Byte {
+            body: b"127.0.0.1:50000".to_vec(),
+        };
+
+        global.join(Request::new(uid.clone())).await.unwrap();
+        assert!(global.cluster.lock().await.contains_key(&uid.body[..]));
+
+        global.leave(Request::new(uid.clone())).await.unwrap();
+        assert!(!global.cluster.lock().await.contains_key(&uid.body[..]));
+    }
+
+    #[tokio::test]
+    async fn remove_completes_while_node_leaves() {
+        let global = Global::new();
+        for uid in &["a", "b", "c"] {
+            let uid = Byte {
+                body: uid.as_bytes().to_vec(),
+            };
+            global.join(Request::new(uid)).await.unwrap();
+        }
+        let pair = KeyValue {
+            key: b"key".to_vec(),
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        global.push(Request::new(pair)).await.unwrap();
+
+        // Release cluster only once both remove and b leaving are waiting on it, as `join!` polls in order
+        let cluster = global.cluster.lock().await;
+        let remove = global.remove(Request::new(Byte {
+            body: b"key".to_vec(),
+        }));
+        let leave = global.leave(Request::new(Byte {
+            body: b"b".to_vec(),
+        }));
+        let release = async move { drop(cluster) };
+        let (removed, left, _) = tokio::join!(remove, leave, release);
+        removed.unwrap();
+        left.unwrap();
+
+        assert!(!global
+            .db
+            .shard(b"key")
+            .read()
+            .await
+            .contains_key(&b"key"[..]));
+        let cluster = global.cluster.lock().await;
+        assert!(!cluster.contains_key(&b"b"[..]));
+        for uid in &["a", "c"] {
+            let queue = cluster[uid.as_bytes()].lock().await;
+            assert_eq!(queue.pending.front().unwrap().key, b"key");
+        }
+    }
+
+    #[tokio::test]
+    async fn snapshot_restores_mappings() {
+        let global = Global::new();
+        for key in &["a", "b", "c"] {
+            let pair = KeyValue {
+                key: key.as_bytes().to_vec(),
+                value: key.repeat(3).into_bytes(),
+                ..Default::default()
+            };
+            global.push(Request::new(pair)).await.unwrap();
+        }
+        let transient = KeyValueTtl {
+            key: b"transient".to_vec(),
+            value: b"value".to_vec(),
+            ttl_millis: 60_000,
+            sliding: false,
+        };
+        global.push_with_ttl(Request::new(transient)).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("dstore-{}.snapshot", std::process::id()));
+        assert_eq!(global.save_snapshot(&path).await.unwrap(), 3);
+
+        let restored = Global::new();
+        assert_eq!(restored.load_snapshot(&path).await.unwrap(), 3);
+        std::fs::remove_file(&path).unwrap();
+        for key in &["a", "b", "c"] {
+            let req = Request::new(Byte {
+                body: key.as_bytes().to_vec(),
+            });
+            let value = restored.pull(req).await.unwrap().into_inner().body;
+            assert_eq!(value, key.repeat(3).into_bytes());
+        }
+        let req = Request::new(Byte {
+            body: b"transient".to_vec(),
+        });
+        assert!(restored.pull(req).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn versions_never_repeat_for_a_key() {
+        let global = Global::new();
+        let pair = KeyValue {
+            key: b"key".to_vec(),
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        async fn version(global: &Global) -> u64 {
+            let req = Request::new(Byte {
+                body: b"key".to_vec(),
+            });
+            global.contains(req).await.unwrap().into_inner().version
+        }
+        let first = global.push(Request::new(pair.clone())).await.unwrap();
+        let first = first.into_inner().version;
+        assert_eq!(version(&global).await, first);
+
+        // Mapped anew once removed
+        let req = Request::new(Byte {
+            body: b"key".to_vec(),
+        });
+        global.remove(req).await.unwrap();
+        let second = global.push(Request::new(pair)).await.unwrap();
+        let second = second.into_inner().version;
+        assert!(second > first);
+
+        // Restored by a Global started afresh
+        let path = std::env::temp_dir().join(format!("dstore-{}.versions", std::process::id()));
+        global.save_snapshot(&path).await.unwrap();
+        let restored = Global::new();
+        restored.load_snapshot(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(version(&restored).await > second);
+    }
+
+    #[tokio::test]
+    async fn wal_recovers_writes_after_crash() {
+        let path = std::env::temp_dir().join(format!("dstore-{}.wal", std::process::id()));
+        let mut global = Global::new();
+        assert_eq!(
+            global
+                .open_wal(&path, SyncPolicy::Batched(2))
+                .await
+                .unwrap(),
+            0
+        );
+        for key in &["a", "b", "c"] {
+            let pair = KeyValue {
+                key: key.as_bytes().to_vec(),
+                value: b"old".to_vec(),
+                ..Default::default()
+            };
+            global.push(Request::new(pair)).await.unwrap();
+        }
+        let pair = KeyValue {
+            key: b"b".to_vec(),
+            value: b"new".to_vec(),
+            ..Default::default()
+        };
+        global.overwrite(Request::new(pair)).await.unwrap();
+        let req = Request::new(Byte {
+            body: b"c".to_vec(),
+        });
+        global.remove(req).await.unwrap();
+
+        // Crash without snapshotting, midway through appending another write
+        drop(global);
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, &[0, 0, 0]).unwrap();
+
+        let mut recovered = Global::new();
+        let replayed = recovered.open_wal(&path, SyncPolicy::Always).await;
+        assert_eq!(replayed.unwrap(), 5);
+        for (key, value) in &[("a", "old"), ("b", "new")] {
+            let req = Request::new(Byte {
+                body: key.as_bytes().to_vec(),
+            });
+            let body = recovered.pull(req).await.unwrap().into_inner().body;
+            assert_eq!(body, value.as_bytes());
+        }
+        let req = Request::new(Byte {
+            body: b"c".to_vec(),
+        });
+        assert!(recovered.pull(req).await.is_err());
+
+        // Incomplete write was cut off, so later writes are replayed too
+        let pair = KeyValue {
+            key: b"d".to_vec(),
+            value: b"later".to_vec(),
+            ..Default::default()
+        };
+        recovered.push(Request::new(pair)).await.unwrap();
+        drop(recovered);
+        let mut global = Global::new();
+        assert_eq!(global.open_wal(&path, SyncPolicy::Always).await.unwrap(), 6);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn wal_records_evictions_and_ttls() {
+        let path = std::env::temp_dir().join(format!("dstore-{}.evicted.wal", std::process::id()));
+        let mut global = Global::new();
+        global.max_entries = Some(3);
+        global.open_wal(&path, SyncPolicy::Always).await.unwrap();
+        for key in &["a", "b", "c", "d"] {
+            let pair = KeyValue {
+                key: key.as_bytes().to_vec(),
+                value: b"value".to_vec(),
+                ..Default::default()
+            };
+            global.push(Request::new(pair)).await.unwrap();
+        }
+        // "a" was evicted to fit "d", "b" is made transient and "c" durable again
+        for key in &["b", "c"] {
+            let req = Request::new(Expiry {
+                key: key.as_bytes().to_vec(),
+                ttl_millis: 60_000,
+            });
+            global.expire(req).await.unwrap();
+        }
+        let req = Request::new(Byte {
+            body: b"c".to_vec(),
+        });
+        assert!(global.persist(req).await.unwrap().into_inner().value);
+
+        drop(global);
+        let mut recovered = Global::new();
+        recovered.open_wal(&path, SyncPolicy::Always).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        for (key, durable) in &[("a", false), ("b", false), ("c", true), ("d", true)] {
+            let req = Request::new(Byte {
+                body: key.as_bytes().to_vec(),
+            });
+            assert_eq!(recovered.pull(req).await.is_ok(), *durable, "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn hashes_are_replayed_from_wal() {
+        let path = std::env::temp_dir().join(format!("dstore-{}.hash.wal", std::process::id()));
+        let mut global = Global::new();
+        global.open_wal(&path, SyncPolicy::Always).await.unwrap();
+        for (field, delta) in &[("views", 5), ("clicks", 1), ("views", -2)] {
+            let req = FieldDelta {
+                key: b"metrics".to_vec(),
+                field: field.as_bytes().to_vec(),
+                delta: *delta,
+            };
+            global.h_incr_by(Request::new(req)).await.unwrap();
+        }
+
+        drop(global);
+        let mut recovered = Global::new();
+        recovered.open_wal(&path, SyncPolicy::Always).await.unwrap();
+        for (field, value) in &[("views", 3), ("clicks", 1)] {
+            let req = KeyField {
+                key: b"metrics".to_vec(),
+                field: field.as_bytes().to_vec(),
+            };
+            let counter = recovered.h_get(Request::new(req)).await.unwrap();
+            assert_eq!(counter.into_inner().value, *value);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn wal_leaves_out_writes_to_transient_mappings() {
+        let path =
+            std::env::temp_dir().join(format!("dstore-{}.transient.wal", std::process::id()));
+        let mut global = Global::new();
+        global.open_wal(&path, SyncPolicy::Always).await.unwrap();
+        let pair = KeyValueTtl {
+            key: b"ttl".to_vec(),
+            value: b"old".to_vec(),
+            ttl_millis: 60_000,
+            ..Default::default()
+        };
+        global.push_with_ttl(Request::new(pair)).await.unwrap();
+        let pair = KeyValue {
+            key: b"expiring".to_vec(),
+            value: b"old".to_vec(),
+            ..Default::default()
+        };
+        global.push(Request::new(pair)).await.unwrap();
+        let req = Request::new(Expiry {
+            key: b"expiring".to_vec(),
+            ttl_millis: 60_000,
+        });
+        global.expire(req).await.unwrap();
+
+        // Overwritten mappings keep their TTL, so remain transient
+        for key in &["ttl", "expiring"] {
+            let pair = KeyValue {
+                key: key.as_bytes().to_vec(),
+                value: b"new".to_vec(),
+                ..Default::default()
+            };
+            global.overwrite(Request::new(pair.clone())).await.unwrap();
+            global.upsert(Request::new(pair)).await.unwrap();
+            let swap = Swap {
+                key: key.as_bytes().to_vec(),
+                expected: b"new".to_vec(),
+                value: b"newer".to_vec(),
+            };
+            assert!(
+                global
+                    .compare_and_swap(Request::new(swap))
+                    .await
+                    .unwrap()
+                    .into_inner()
+                    .swapped
+            );
+        }
+
+        drop(global);
+        let mut recovered = Global::new();
+        recovered.open_wal(&path, SyncPolicy::Always).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        for key in &["ttl", "expiring"] {
+            let req = Request::new(Byte {
+                body: key.as_bytes().to_vec(),
+            });
+            assert!(recovered.pull(req).await.is_err(), "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn wal_is_compacted_after_snapshot() {
+        let dir = std::env::temp_dir();
+        let wal = dir.join(format!("dstore-{}.compacted.wal", std::process::id()));
+        let snapshot = dir.join(format!("dstore-{}.compacted.snap", std::process::id()));
+        let mut global = Global::new();
+        global.open_wal(&wal, SyncPolicy::Always).await.unwrap();
+        for key in &["a", "b", "c"] {
+            if *key == "c" {
+                Global::checkpoint(&global.db, global.wal.as_ref(), &snapshot)
+                    .await
+                    .unwrap();
+                // Writes held in snapshot are dropped from log
+                assert_eq!(std::fs::metadata(&wal).unwrap().len(), 0);
+            }
+            let pair = KeyValue {
+                key: key.as_bytes().to_vec(),
+                value: b"value".to_vec(),
+                ..Default::default()
+            };
+            global.push(Request::new(pair)).await.unwrap();
+        }
+
+        // Writes since are replayed onto snapshot
+        drop(global);
+        let mut recovered = Global::new();
+        assert_eq!(recovered.load_snapshot(&snapshot).await.unwrap(), 2);
+        assert_eq!(
+            recovered.open_wal(&wal, SyncPolicy::Always).await.unwrap(),
+            1
+        );
+        std::fs::remove_file(&wal).unwrap();
+        std::fs::remove_file(&snapshot).unwrap();
+        for key in &["a", "b", "c"] {
+            let req = Request::new(Byte {
+                body: key.as_bytes().to_vec(),
+            });
+            assert!(recovered.pull(req).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_to_mapped_keys_are_size_checked() {
+        let mut global = Global::new();
+        global.set_max_message_size(64);
+        let pair = KeyValue {
+            key: b"key".to_vec(),
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        global.push(Request::new(pair)).await.unwrap();
+
+        // Limit can't be evaded by replacing VALUE of a KEY already mapped
+        let large = vec![0; 64];
+        let pair = KeyValue {
+            key: b"key".to_vec(),
+            value: large.clone(),
+            ..Default::default()
+        };
+        let status = global.overwrite(Request::new(pair.clone())).await;
+        assert_eq!(status.unwrap_err().code(), Code::InvalidArgument);
+        let status = global.upsert(Request::new(pair)).await;
+        assert_eq!(status.unwrap_err().code(), Code::InvalidArgument);
+        let swap = Swap {
+            key: b"key".to_vec(),
+            expected: b"value".to_vec(),
+            value: large.clone(),
+        };
+        let status = global.compare_and_swap(Request::new(swap)).await;
+        assert_eq!(status.unwrap_err().code(), Code::InvalidArgument);
+        let pair = KeyValueTtl {
+            key: b"transient".to_vec(),
+            value: large,
+            ttl_millis: 60_000,
+            ..Default::default()
+        };
+        let status = global.push_with_ttl(Request::new(pair)).await;
+        assert_eq!(status.unwrap_err().code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn compressed_overwrite_is_decompressed() {
+        let global = Global::new();
+        let pair = KeyValue {
+            key: b"key".to_vec(),
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        global.push(Request::new(pair)).await.unwrap();
+        let value = b"new".repeat(100);
+        let (compressed_value, compressed) = compression::encode(&value, Some(0));
+        assert!(compressed);
+        let pair = KeyValue {
+            key: b"key".to_vec(),
+            value: compressed_value,
+            compressed,
+            ..Default::default()
+        };
+        global.overwrite(Request::new(pair)).await.unwrap();
+        let req = Request::new(Byte {
+            body: b"key".to_vec(),
+        });
+        assert_eq!(global.pull(req).await.unwrap().into_inner().body, value);
+    }
+
+    #[tokio::test]
+    async fn expired_mappings_arent_removed() {
+        let global = Global::new();
+        let pair = KeyValue {
+            key: b"durable".to_vec(),
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        global.push(Request::new(pair)).await.unwrap();
+        // Expires at once, but isn't swept as Global isn't serving
+        let pair = KeyValueTtl {
+            key: b"expired".to_vec(),
+            value: b"value".to_vec(),
+            ttl_millis: 0,
+            ..Default::default()
+        };
+        global.push_with_ttl(Request::new(pair)).await.unwrap();
+
+        let req = Request::new(Byte {
+            body: b"expired".to_vec(),
+        });
+        let status = global.remove(req).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        let cleared = global.clear_all(Request::new(Null {})).await.unwrap();
+        assert_eq!(cleared.into_inner().size, 1);
+    }
+
+    #[tokio::test]
+    async fn non_utf8_key_conflict_doesnt_panic() {
+        let global = Global::new();
+        let pair = KeyValue {
+            key: vec![0xff, 0xfe, 0xfd],
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+
+        global.push(Request::new(pair.clone())).await.unwrap();
+        let status = global.push(Request::new(pair)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::AlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn multi_pull_stream_doesnt_wait_for_all_lookups() {
+        let global = Global::new();
+        for key in &["fast", "slow"] {
+            let pair = KeyValue {
+                key: key.as_bytes().to_vec(),
+                value: b"value".to_vec(),
+                ..Default::default()
+            };
+            global.push(Request::new(pair)).await.unwrap();
+        }
+
+        let keys = Keys {
+            keys: vec![b"fast".to_vec(), b"slow".to_vec(), b"missing".to_vec()],
+        };
+        let mut stream = global
+            .multi_pull_stream(Request::new(keys))
+            .await
+            .unwrap()
+            .into_inner();
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!((&first.key[..], first.found), (&b"fast"[..], true));
+
+        // Delay lookup of the second KEY, first result is already recieved regardless
+        let db = global.db.shard(b"slow").write().await;
+        assert!(time::timeout(Duration::from_millis(100), stream.next())
+            .await
+            .is_err());
+        drop(db);
+
+        let second = stream.next().await.unwrap().unwrap();
+        assert_eq!((&second.key[..], second.found), (&b"slow"[..], true));
+        let third = stream.next().await.unwrap().unwrap();
+        assert_eq!((&third.key[..], third.found), (&b"missing"[..], false));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn concurrent_reads_proceed_in_parallel() {
+        let global = Arc::new(Global::new());
+        let pair = KeyValue {
+            key: b"key".to_vec(),
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        global.push(Request::new(pair)).await.unwrap();
+
+        // Reads complete while another reader holds the database, as a writer would have to wait
+        let guard = global.db.shard(b"key").read().await;
+        let reads = (0..100).map(|_| {
+            let global = global.clone();
+            tokio::spawn(async move {
+                let key = Byte {
+                    body: b"key".to_vec(),
+                };
+                global.contains(Request::new(key)).await.map(|_| ())
+            })
+        });
+        let results = time::timeout(Duration::from_secs(1), future::join_all(reads))
+            .await
+            .unwrap();
+        assert!(results.into_iter().all(|res| res.unwrap().is_ok()));
+        drop(guard);
+    }
+
+    #[tokio::test]
+    async fn writes_to_different_shards_dont_block() {
+        let global = Global::new();
+        let held = b"held".to_vec();
+        let other = (0..)
+            .map(|i| format!("other{}", i).into_bytes())
+            .find(|key| !std::ptr::eq(global.db.shard(key), global.db.shard(&held)))
+            .unwrap();
+
+        // Hold a write lock on one shard, a write to another still completes
+        let guard = global.db.shard(&held).write().await;
+        let pair = KeyValue {
+            key: other,
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        time::timeout(Duration::from_secs(1), global.push(Request::new(pair)))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // While a write to the held shard waits
+        let pair = KeyValue {
+            key: held,
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        assert!(
+            time::timeout(Duration::from_millis(100), global.push(Request::new(pair)))
+                .await
+                .is_err()
+        );
+        drop(guard);
+    }
+
+    #[tokio::test]
+    async fn capacity_eviction_invalidates_cluster() {
+        let global = Global {
+            max_entries: Some(1),
+            ..Global::new()
+        };
+        let uid = Byte {
+            body: b"127.0.0.1:50000".to_vec(),
+        };
+        global.join(Request::new(uid.clone())).await.unwrap();
+
+        for key in &["old", "new"] {
+            let pair = KeyValue {
+                key: key.as_bytes().to_vec(),
+                value: b"value".to_vec(),
+                ..Default::default()
+            };
+            global.push(Request::new(pair)).await.unwrap();
+        }
+        assert_eq!(global.db.len().await, 1);
+        assert!(global
+            .db
+            .shard(b"new")
+            .read()
+            .await
+            .contains_key(&b"new"[..]));
+
+        // Node that cached the evicted KEY is told to drop it
+        let invalidation = global.update(Request::new(uid)).await.unwrap().into_inner();
+        assert_eq!(invalidation.key, b"old".to_vec());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_writes_stay_within_max_entries() {
+        let global = Arc::new(Global {
+            max_entries: Some(4),
+            ..Global::new()
+        });
+        let mut writers = vec![];
+        for i in 0..64 {
+            let global = global.clone();
+            writers.push(tokio::spawn(async move {
+                let pair = KeyValue {
+                    key: format!("key{}", i).into_bytes(),
+                    value: b"value".to_vec(),
+                    ..Default::default()
+                };
+                global.push(Request::new(pair)).await.unwrap();
+            }));
+        }
+        for writer in writers {
+            writer.await.unwrap();
+        }
+        assert_eq!(global.db.len().await, 4);
+        assert_eq!(global.quotas.lock().unwrap().keys(), 4);
+    }
+
+    #[tokio::test]
+    async fn rejected_write_evicts_nothing() {
+        let global = Global {
+            max_entries: Some(2),
+            ..Global::new()
+        };
+        let quota = Quota {
+            max_keys: Some(1),
+            max_bytes: None,
+        };
+        global.quotas.lock().unwrap().set(b"tenant", quota);
+        for key in &["old", "tenant:a"] {
+            let pair = KeyValue {
+                key: key.as_bytes().to_vec(),
+                value: b"value".to_vec(),
+                ..Default::default()
+            };
+            global.push(Request::new(pair)).await.unwrap();
+        }
+
+        // Namespace is full, so KEY is refused rather than made room for
+        let pair = KeyValue {
+            key: b"tenant:b".to_vec(),
+            value: b"value".to_vec(),
+            ..Default::default()
+        };
+        let status = global.push(Request::new(pair)).await.unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert!(global
+            .db
+            .shard(b"old")
+            .read()
+            .await
+            .contains_key(&b"old"[..]));
+    }
+
+    #[tokio::test]
+    async fn overflowing_queue_signals_resync() {
+        let mut global = Global::new();
+        global.set_max_queue_len(10);
+        let uid = Byte {
+            body: b"127.0.0.1:50000".to_vec(),
+        };
+        global.join(Request::new(uid.clone())).await.unwrap();
+
+        for i in 0..25 {
+            let invalidation = Invalidation {
+                key: format!("key{}", i).into_bytes(),
+                ..Default::default()
+            };
+            Global::broadcast(&global.cluster, invalidation).await;
+        }
+        assert_eq!(
+            global.cluster.lock().await[&uid.body[..]]
+                .lock()
+                .await
+                .pending
+                .len(),
+            1
+        );
+
+        // Local is told to flush it's cache in place of the KEYs it fell behind on
+        let ack = UpdateAck {
+            uid: uid.body.clone(),
+            seq: 0,
+        };
+        let batch = global.update_batch(Request::new(ack)).await.unwrap();
+        let batch = batch.into_inner();
+        assert!(batch.flush);
+        let keys = compression::decode(batch.keys, batch.compressed).unwrap();
+        assert!(snapshot::decode_keys(Bytes::from(keys)).unwrap().is_empty());
+
+        // Once resynced, KEYs are queued individually again
+        let invalidation = Invalidation {
+            key: b"key".to_vec(),
+            ..Default::default()
+        };
+        Global::broadcast(&global.cluster, invalidation).await;
+        let invalidation = global.update(Request::new(uid)).await.unwrap().into_inner();
+        assert!(!invalidation.flush);
+        assert_eq!(invalidation.key, b"key".to_vec());
+    }
+
+    #[tokio::test]
+    async fn update_batch_drains_queue_in_one_call() {
+        let global = Global::new();
+        let uid = Byte {
+            body: b"127.0.0.1:50000".to_vec(),
+        };
+        global.join(Request::new(uid.clone())).await.unwrap();
+
+        let mut keys: Vec<Vec<u8>> = (0..5000)
+            .map(|i| format!("key{}", i).into_bytes())
+            .collect();
+        for key in &keys[..10] {
+            let invalidation = Invalidation {
+                key: key.clone(),
+                ..Default::default()
+            };
+            Global::broadcast(&global.cluster, invalidation).await;
+        }
+        global.clear_all(Request::new(Null {})).await.unwrap();
+        keys.drain(..10);
+        for key in &keys {
+            let invalidation = Invalidation {
+                key: key.clone(),
+                ..Default::default()
+            };
+            Global::broadcast(&global.cluster, invalidation).await;
+        }
+
+        let mut req = Request::new(UpdateAck {
+            uid: uid.body.clone(),
+            seq: 0,
+        });
+        req.metadata_mut()
+            .insert(COMPRESSION_HEADER, "1024".parse().unwrap());
+        let batch = global.update_batch(req).await.unwrap().into_inner();
+        let seq = batch.seq;
+        assert!(batch.flush && batch.compressed && !batch.draining);
+        let decoded = compression::decode(batch.keys, batch.compressed).unwrap();
+        let decoded = snapshot::decode_keys(Bytes::from(decoded)).unwrap();
+        assert_eq!(
+            decoded,
+            keys.into_iter().map(Bytes::from).collect::<Vec<_>>()
+        );
+
+        // Queue is left empty once the batch is acknowledged
+        let ack = UpdateAck { uid: uid.body, seq };
+        let batch = global
+            .update_batch(Request::new(ack))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!batch.flush && batch.keys.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_batch_is_resent() {
+        let global = Global::new();
+        let uid = b"127.0.0.1:50000".to_vec();
+        global
+            .join(Request::new(Byte { body: uid.clone() }))
+            .await
+            .unwrap();
+        let update = |seq| {
+            let ack = UpdateAck {
+                uid: uid.clone(),
+                seq,
+            };
+            async {
+                let batch = global
+                    .update_batch(Request::new(ack))
+                    .await
+                    .unwrap()
+                    .into_inner();
+                let keys = compression::decode(batch.keys, batch.compressed).unwrap();
+                (batch.seq, snapshot::decode_keys(Bytes::from(keys)).unwrap())
+            }
+        };
+        let invalidate =
+            |key: &'static str| Global::invalidate(&global.cluster, key.as_bytes().to_vec(), b"");
+
+        invalidate("lost").await;
+        let (lost, keys) = update(0).await;
+        assert_eq!(keys, [Bytes::from("lost")]);
+
+        // Reply never reached Local, which acknowledges the batch before
+        invalidate("next").await;
+        let (seq, keys) = update(0).await;
+        assert_eq!(keys, [Bytes::from("lost"), Bytes::from("next")]);
+        assert_ne!(seq, lost);
+
+        // Acknowledged invalidations aren't sent again
+        let (_, keys) = update(seq).await;
+        assert!(keys.is_empty());
+    }
+
+    #[tokio::test]
+    async fn pulled_frames_are_sized_as_negotiated() {
+        let mut global = Global::new();
+        global.set_chunk_size(4);
+        let pair = KeyValue {
+            key: b"key".to_vec(),
+            value: b"0123456789".to_vec(),
+            ..Default::default()
+        };
+        global.push(Request::new(pair)).await.unwrap();
+
+        async fn frame_sizes(global: &Global, req: Request<Byte>) -> Vec<usize> {
+            let stream = global.pull_file(req).await.unwrap().into_inner();
+            stream
+                .map(|frame| frame.unwrap().body.len())
+                .collect()
+                .await
+        }
+        let key = Byte {
+            body: b"key".to_vec(),
+        };
+        assert_eq!(
+            frame_sizes(&global, Request::new(key.clone())).await,
+            vec![4, 4, 2]
+        );
+
+        // Size asked for by the client takes precedence
+        let mut req = Request::new(key);
+        req.metadata_mut()
+            .insert(CHUNK_SIZE_HEADER, "3".parse().unwrap());
+        assert_eq!(frame_sizes(&global, req).await, vec![3, 3, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn corrupted_frame_fails_checksum() {
+        let mut client = serve().await;
+
+        let value = vec![7; 1000];
+        let push = |key: &[u8], corrupt: bool| {
+            let frames = vec![key.to_vec(), value[..600].to_vec(), value[600..].to_vec()];
+            // Flip a bit of a VALUE frame in transit
+            let frames =
+                futures::stream::iter(frames.into_iter().enumerate().map(move |(i, mut body)| {
+                    if corrupt && i == 2 {
+                        body[0] ^= 1;
+                    }
+                    Byte { body }
+                }));
+            let mut req = Request::new(frames);
+            req.metadata_mut().insert(
+                CHECKSUM_HEADER,
+                MetadataValue::from(checksum::checksum(&value) as u64),
+            );
+            req
+        };
+
+        let status = client.push_file(push(b"corrupt", true)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::DataLoss);
+        let req = Request::new(Byte {
+            body: b"corrupt".to_vec(),
+        });
+        assert!(client.pull_file(req).await.is_err());
+
+        client.push_file(push(b"intact", false)).await.unwrap();
+        let mut req = Request::new(Byte {
+            body: b"intact".to_vec(),
+        });
+        req.metadata_mut()
+            .insert(CHECKSUM_HEADER, checksum::REQUESTED.parse().unwrap());
+        let res = client.pull_file(req).await.unwrap();
+        assert_eq!(
+            checksum::declared(res.metadata()),
+            Some(checksum::checksum(&value))
+        );
+    }
+
+    #[tokio::test]
+    async fn streamed_push_doesnt_overwrite() {
+        let mut client = serve().await;
+
+        let push = |value: &[u8]| {
+            let frames = vec![b"key".to_vec(), value.to_vec()];
+            Request::new(futures::stream::iter(
+                frames.into_iter().map(|body| Byte { body }),
+            ))
+        };
+        let pushed = client.push_file(push(b"first")).await.unwrap().into_inner();
+        let status = client.push_file(push(b"second")).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::AlreadyExists);
+
+        let req = Request::new(Byte {
+            body: b"key".to_vec(),
+        });
+        let value = client.pull(req).await.unwrap().into_inner();
+        assert_eq!(value.body, b"first");
+        assert_eq!(value.version, pushed.version);
+    }
+
+    #[tokio::test]
+    async fn malformed_address_is_an_error() {
+        assert!(Global::start_server("not-an-address").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn oversized_frame_is_rejected() {
+        // Frame VALUE with a larger size than Global accepts
+        let mut client = serve().await;
+        let frames = vec![
+            Byte {
+                body: b"oversized".to_vec(),
+            },
+            Byte {
+                body: vec![0; MAX_BYTE_SIZE + 1],
+            },
+        ];
+        let status = client
+            .push_file(Request::new(futures::stream::iter(frames)))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            format!(
+                "Frame of {} bytes exceeds limit of {} bytes.",
+                MAX_BYTE_SIZE + 1,
+                MAX_BYTE_SIZE
+            )
+        );
     }
 }
@@ -1,26 +1,239 @@
-use std::{error::Error, fmt};
+use bytes::Bytes;
+use std::{error::Error, fmt, io, string::FromUtf8Error};
+use tonic::{Code, Status};
 
-/// Error type for Dstore, contains message as string
+/// Error type for Dstore, distinguishes the conditions under which an operation fails
 #[derive(Debug)]
-pub struct DstoreError(pub String);
+pub enum DstoreError {
+    /// KEY is already mapped to a VALUE
+    KeyOccupied(Bytes),
+    /// KEY isn't mapped to any VALUE
+    NotFound(Bytes),
+    /// Couldn't connect to or join Global
+    Connection(String),
+    /// Global responded with an error status
+    Transport(Box<Status>),
+    /// Failure in reading or writing data
+    Io(io::Error),
+    /// A frame to be sent exceeds the size Global accepts
+    FrameTooLarge { size: usize, limit: usize },
+    /// Global didn't respond within the configured timeout, on every attempt made
+    Timeout,
+    /// VALUE streamed for KEY doesn't match it's checksum, as when corrupted in transit
+    ChecksumMismatch(Bytes),
+    /// Couldn't serialize a typed KEY or VALUE, or deserialize a VALUE, see `TypedStore`
+    Serialization(bincode::Error),
+    /// VALUE read as a string isn't valid UTF-8
+    Utf8(FromUtf8Error),
+    /// Queue named KEY is at capacity, see `Queue::create()`
+    QueueFull(Bytes),
+    /// KEY is at version `current` rather than the one a write expected, 0 if it isn't mapped, see
+    /// `Local::put_if_version()`
+    VersionConflict { key: Bytes, current: u64 },
+    /// Range read of KEY starts beyond the end of it's VALUE, see `Local::get_range()`
+    OutOfRange(Bytes),
+}
+
+impl DstoreError {
+    /// Classify an error status returned by Global for an operation on KEY
+    pub(crate) fn from_status(key: &[u8], status: Status) -> Self {
+        match status.code() {
+            Code::NotFound => Self::NotFound(Bytes::copy_from_slice(key)),
+            Code::AlreadyExists => Self::KeyOccupied(Bytes::copy_from_slice(key)),
+            Code::DeadlineExceeded => Self::Timeout,
+            Code::DataLoss => Self::ChecksumMismatch(Bytes::copy_from_slice(key)),
+            Code::OutOfRange => Self::OutOfRange(Bytes::copy_from_slice(key)),
+            _ => Self::Transport(Box::new(status)),
+        }
+    }
+}
 
 impl fmt::Display for DstoreError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "dstore error: {}", self.0)
+        match self {
+            Self::KeyOccupied(key) => {
+                write!(
+                    f,
+                    "dstore error: Key occupied: {}",
+                    String::from_utf8_lossy(key)
+                )
+            }
+            Self::NotFound(key) => write!(
+                f,
+                "dstore error: Key-Value mapping doesn't exist: {}",
+                String::from_utf8_lossy(key)
+            ),
+            Self::Connection(msg) => write!(f, "dstore error: Couldn't connect: {}", msg),
+            Self::Transport(status) => write!(f, "dstore error: Global: {}", status.message()),
+            Self::Io(e) => write!(f, "dstore error: {}", e),
+            Self::FrameTooLarge { size, limit } => write!(
+                f,
+                "dstore error: Frame of {} bytes exceeds limit of {} bytes",
+                size, limit
+            ),
+            Self::Timeout => write!(f, "dstore error: Global didn't respond in time"),
+            Self::ChecksumMismatch(key) => write!(
+                f,
+                "dstore error: Checksum mismatch for VALUE of key: {}",
+                String::from_utf8_lossy(key)
+            ),
+            Self::Serialization(e) => write!(f, "dstore error: Couldn't serialize: {}", e),
+            Self::Utf8(e) => write!(f, "dstore error: VALUE isn't valid UTF-8: {}", e),
+            Self::QueueFull(key) => write!(
+                f,
+                "dstore error: Queue is full: {}",
+                String::from_utf8_lossy(key)
+            ),
+            Self::VersionConflict { key, current } => write!(
+                f,
+                "dstore error: Key is at version {}: {}",
+                current,
+                String::from_utf8_lossy(key)
+            ),
+            Self::OutOfRange(key) => write!(
+                f,
+                "dstore error: Range starts beyond end of VALUE of key: {}",
+                String::from_utf8_lossy(key)
+            ),
+        }
+    }
+}
+
+impl Error for DstoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Transport(status) => Some(status.as_ref()),
+            Self::Io(e) => Some(e),
+            Self::Serialization(e) => Some(e.as_ref()),
+            Self::Utf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Status> for DstoreError {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            Code::DeadlineExceeded => Self::Timeout,
+            _ => Self::Transport(Box::new(status)),
+        }
+    }
+}
+
+impl From<tonic::transport::Error> for DstoreError {
+    fn from(e: tonic::transport::Error) -> Self {
+        Self::Connection(e.to_string())
+    }
+}
+
+impl From<io::Error> for DstoreError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for DstoreError {
+    fn from(e: FromUtf8Error) -> Self {
+        Self::Utf8(e)
     }
 }
 
-impl Error for DstoreError {}
+impl From<bincode::Error> for DstoreError {
+    fn from(e: bincode::Error) -> Self {
+        Self::Serialization(e)
+    }
+}
+
+/// Emit a tracing event with the given fields and message. Compiled out, along with it's fields,
+/// unless the `tracing` feature is enabled.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+/// Record a metric by evaluating the given statement. Compiled out unless the `metrics` feature is enabled.
+macro_rules! metric {
+    ($stmt:expr) => {
+        #[cfg(feature = "metrics")]
+        $stmt;
+    };
+}
 
 mod dstore_proto {
     tonic::include_proto!("dstore");
 }
 
+/// gRPC metadata header used by Locals to identify themselves to Global
+pub(crate) const UID_HEADER: &str = "dstore-uid";
+
+/// gRPC metadata header carrying the fencing token a writer was issued, see `Local::promote()`
+pub(crate) const FENCE_HEADER: &str = "dstore-fence";
+
+/// gRPC metadata header carrying the shared secret Global may require of every request
+pub(crate) const TOKEN_HEADER: &str = "dstore-token";
+
+/// gRPC metadata header marking a pushed stream as ending in an empty frame. Global applies such a stream
+/// only once it's terminating frame arrives, so that one cut short by it's sender isn't taken as complete.
+pub(crate) const TERMINATED_HEADER: &str = "dstore-terminated";
+
+/// gRPC metadata header carrying the size of VALUE frames in a streamed transfer, so both ends frame it alike
+pub(crate) const CHUNK_SIZE_HEADER: &str = "dstore-chunk-size";
+
+/// gRPC metadata header carrying the version KEY is at, sent along when a write expecting another is rejected
+/// and with a VALUE streamed from Global
+pub(crate) const VERSION_HEADER: &str = "dstore-version";
+
+/// gRPC metadata header carrying the size of a VALUE streamed from Global, for reporting progress
+pub(crate) const SIZE_HEADER: &str = "dstore-size";
+
+/// gRPC metadata header carrying the largest VALUE a Local takes in a single packet when pulling. Global turns
+/// away a pull of a larger VALUE, setting `SIZE_HEADER` on the error, for Local to stream it instead.
+pub(crate) const MAX_VALUE_HEADER: &str = "dstore-max-value";
+
+/// gRPC metadata header set on a VALUE streamed from Global if it's TTL slides, so that it isn't cached
+pub(crate) const SLIDING_HEADER: &str = "dstore-sliding";
+
 /// Maximum size of contents in a gRPC packet as per standard
 pub const MAX_BYTE_SIZE: usize = 4_194_304;
 
+mod access;
+mod admin;
+mod bloom;
+mod checksum;
+mod compression;
+mod feed;
 mod global;
 mod local;
+mod lru;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod queue;
+mod quota;
+mod shard;
+mod sharded_db;
+mod snapshot;
+mod tasks;
+/// In-process Global for tests, enabled by the `testing` feature
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
+mod typed;
+mod wal;
+mod watch;
 
-pub use global::Global;
-pub use local::Local;
+pub use admin::{Admin, AdminBuilder, GlobalStats, NodeInfo};
+pub use bloom::BloomFilter;
+pub use dstore_proto::dstore_server::DstoreServer;
+pub use global::{Global, GlobalBuilder};
+pub use local::{
+    BatchOp, BatchSummary, CacheStats, Consistency, KeyAccess, KeyInfo, Local, LocalBuilder,
+    LocalConfig, Precondition, TlsConfig, Upsert,
+};
+pub use queue::{Queue, QueueBuilder, QueueOptions};
+pub use quota::{Quota, Usage};
+pub use shard::ShardedLocal;
+pub use typed::TypedStore;
+pub use wal::SyncPolicy;
+pub use watch::{Mutation, Watch, WatchEvent};
@@ -1,277 +1,2734 @@
 use bytes::Bytes;
-use futures::{stream, StreamExt};
-use std::{collections::HashMap, error::Error, sync::Arc};
+use futures::{stream, Future, Stream, StreamExt};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tokio::{
-    sync::Mutex,
-    time::{self, Duration},
+    fs,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    runtime::Handle,
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+    time::{self, Duration, Instant},
 };
-use tonic::{transport::Channel, Request};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{metadata::MetadataValue, transport::Channel, Code, Request, Response, Status};
 
 use crate::{
-    dstore_proto::{dstore_client::DstoreClient, Byte, KeyValue},
-    DstoreError, MAX_BYTE_SIZE,
+    bloom::BloomFilter,
+    checksum::{self, Verifier, CHECKSUM_HEADER},
+    compression::{self, Sampling, COMPRESSION_HEADER},
+    dstore_proto::{
+        dstore_client::DstoreClient, operation::Kind, AccessList, Batch, BatchResult, Byte,
+        ByteRange, CachedKeys, Condition, ConditionalBatch, Events, Expiry, Fence, FieldDelta,
+        Filter, Invalidations, KeyField, KeyValue, KeyValueTtl, KeyVersion, Keys, LogRange, Lookup,
+        Null, Offset, Operation, Reconciliation, Size, Swap, UpdateAck, Upserted, Value,
+        VersionedValue,
+    },
+    lru::Lru,
+    snapshot,
+    transport::{self, Client, Tagging},
+    watch::{Mutation, Watch},
+    DstoreError, CHUNK_SIZE_HEADER, MAX_BYTE_SIZE, MAX_VALUE_HEADER, SIZE_HEADER, SLIDING_HEADER,
+    TERMINATED_HEADER, VERSION_HEADER,
 };
 
+/// Bytes reserved within a packet for framing a mapping, such as proto field tags and length prefixes
+const PACKET_OVERHEAD: usize = 64;
+
+/// Check if a mapping can be transported in a single packet of upto `limit` bytes, else it must be streamed
+/// in frames. Shared by every read and write path, so that both agree on how a VALUE travels.
+fn fits_single_packet(key_len: usize, value_len: usize, limit: usize) -> bool {
+    key_len + value_len + PACKET_OVERHEAD <= limit
+}
+
+/// Check if Global rejected a request for being compressed, as Globals not accepting compression do
+fn rejects_compression(status: &Status) -> bool {
+    matches!(status.code(), Code::Unimplemented | Code::Internal)
+        && status.message().contains("compress")
+}
+
+/// TTL as whole milliseconds for Global, rounded up so that a sub-millisecond TTL doesn't expire
+/// the mapping on Global before it does in cache
+fn millis(ttl: Duration) -> u64 {
+    ttl.as_nanos().div_ceil(1_000_000).min(u64::MAX as u128) as u64
+}
+
+/// Pause before retrying a request to Global, doubled after each retry
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Await a request to Global, giving up with a deadline exceeded status once `timeout` elapses
+async fn attempt<T>(
+    timeout: Option<Duration>,
+    request: impl Future<Output = Result<T, Status>>,
+) -> Result<T, Status> {
+    match timeout {
+        Some(timeout) => match time::timeout(timeout, request).await {
+            Ok(result) => result,
+            Err(_) => Err(Status::deadline_exceeded("Global didn't respond in time.")),
+        },
+        None => request.await,
+    }
+}
+
+/// Make a request to Global through `rpc` over `global`, which is invoked afresh with a copy of `req` on each
+/// attempt. Attempts that find Global unreachable or that time out are retried upto `retries` times with
+/// exponential backoff.
+async fn retrying<M, T, F, Fut>(
+    global: &Client,
+    timeout: Option<Duration>,
+    retries: usize,
+    req: Request<M>,
+    rpc: F,
+) -> Result<Response<T>, Status>
+where
+    M: Clone,
+    F: Fn(Client, Request<M>) -> Fut,
+    Fut: Future<Output = Result<Response<T>, Status>>,
+{
+    let mut backoff = RETRY_BACKOFF;
+    for _ in 0..retries {
+        match attempt(timeout, rpc(global.clone(), copy_request(&req))).await {
+            Err(status) if is_transient(&status) => {
+                time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+
+    attempt(timeout, rpc(global.clone(), req)).await
+}
+
+/// Copy of a request, message and metadata alike, to be sent again
+fn copy_request<M: Clone>(req: &Request<M>) -> Request<M> {
+    let mut copy = Request::new(req.get_ref().clone());
+    *copy.metadata_mut() = req.metadata().clone();
+    copy
+}
+
+/// Whether a failed request may succeed if repeated, as when Global couldn't be reached or didn't respond
+fn is_transient(status: &Status) -> bool {
+    match status.code() {
+        Code::Unavailable | Code::DeadlineExceeded => true,
+        // Channel reports failures to connect as unknown errors
+        Code::Unknown => status.message().contains("transport error"),
+        _ => false,
+    }
+}
+
+/// Consistency level of a read, chosen per request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Consistency {
+    /// Serve from cache if KEY is cached, which may be stale until the next `update()`
+    Eventual,
+    /// Bypass cache and read the current VALUE from Global, refreshing cache
+    Strong,
+}
+
+/// Everything Global knows about a KEY, as reported by `Local::inspect()`
+#[derive(Clone, Debug)]
+pub struct KeyInfo {
+    /// Size of VALUE in bytes
+    pub size: usize,
+    /// Grows each time VALUE is written, never repeating for KEY even once it's removed and mapped anew
+    pub version: u64,
+    /// Time remaining until mapping expires, if inserted with a TTL
+    pub ttl: Option<Duration>,
+    /// UID of Local that inserted the mapping
+    pub owner: Bytes,
+    /// Time VALUE was last written
+    pub modified: SystemTime,
+    /// UIDs of Locals that may hold VALUE in cache
+    pub holders: Vec<Bytes>,
+    /// Tags attached to KEY. Tagging isn't supported yet, so this is always empty.
+    pub tags: Vec<Bytes>,
+}
+
+/// Approximate number of times Global served or wrote a KEY, as reported by `Local::hot_keys()`
+/// and `Admin::hot_keys()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyAccess {
+    pub key: Bytes,
+    pub reads: u64,
+    pub writes: u64,
+}
+
+impl From<crate::dstore_proto::KeyAccess> for KeyAccess {
+    fn from(access: crate::dstore_proto::KeyAccess) -> Self {
+        Self {
+            key: Bytes::from(access.key),
+            reads: access.reads,
+            writes: access.writes,
+        }
+    }
+}
+
+/// Outcome of `Local::upsert()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Upsert {
+    /// KEY wasn't mapped, VALUE was inserted as `version`
+    Created { version: u64 },
+    /// KEY was mapped, VALUE was replaced as `version`
+    Updated { version: u64 },
+}
+
+/// Outcome of `Local::insert_batch()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchSummary {
+    /// Number of mappings inserted
+    pub accepted: usize,
+    /// Number of KEYs skipped as they were already mapped on Global
+    pub present: usize,
+}
+
+/// Operation within a batch applied atomically on Global, see `Local::apply_batch()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchOp {
+    /// Map KEY to VALUE, replacing any existing VALUE
+    Put { key: Bytes, value: Bytes },
+    /// Remove KEY's mapping, which must exist
+    Remove(Bytes),
+    /// Map `to` to the VALUE of `from`, which must exist
+    Copy { from: Bytes, to: Bytes },
+    /// Move the VALUE of `from`, which must exist, to `to`
+    Rename { from: Bytes, to: Bytes },
+}
+
+impl BatchOp {
+    /// Every KEY written by `ops`
+    fn keys(ops: &[BatchOp]) -> Vec<Bytes> {
+        let mut keys = vec![];
+        for op in ops {
+            match op {
+                BatchOp::Put { key, .. } | BatchOp::Remove(key) => keys.push(key.clone()),
+                BatchOp::Copy { from, to } | BatchOp::Rename { from, to } => {
+                    keys.push(from.clone());
+                    keys.push(to.clone());
+                }
+            }
+        }
+        keys
+    }
+}
+
+impl From<BatchOp> for Operation {
+    fn from(op: BatchOp) -> Self {
+        let (kind, key, value, target) = match op {
+            BatchOp::Put { key, value } => (Kind::Put, key, value, Bytes::new()),
+            BatchOp::Remove(key) => (Kind::Remove, key, Bytes::new(), Bytes::new()),
+            BatchOp::Copy { from, to } => (Kind::Copy, from, Bytes::new(), to),
+            BatchOp::Rename { from, to } => (Kind::Rename, from, Bytes::new(), to),
+        };
+
+        Self {
+            kind: kind as i32,
+            key: key.to_vec(),
+            value: value.to_vec(),
+            target: target.to_vec(),
+        }
+    }
+}
+
+/// Condition a transaction is applied under, see `Local::transaction()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Precondition {
+    /// KEY is mapped to VALUE
+    Equals { key: Bytes, value: Bytes },
+    /// KEY isn't mapped
+    Absent(Bytes),
+}
+
+impl From<Precondition> for Condition {
+    fn from(precondition: Precondition) -> Self {
+        match precondition {
+            Precondition::Equals { key, value } => Self {
+                key: key.to_vec(),
+                expected: value.to_vec(),
+                absent: false,
+            },
+            Precondition::Absent(key) => Self {
+                key: key.to_vec(),
+                absent: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Counts of how `get()`, `get_single()` and `get_file()` calls were served, see `Local::stats()`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Calls served from cache
+    pub hits: u64,
+    /// Calls that found KEY absent from cache
+    pub misses: u64,
+    /// VALUEs fetched from Global on a miss
+    pub remote_fetches: u64,
+}
+
+/// Certificate authority and domain name to verify Global's certificate against, when connecting over TLS
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// PEM encoded certificate of the authority that signed Global's certificate
+    pub ca_cert: Vec<u8>,
+    /// Name Global's certificate is issued for
+    pub domain: String,
+}
+
+/// Options a Local is constructed with, see `Local::with_config()`
+#[derive(Clone, Debug)]
+pub struct LocalConfig {
+    /// Interval between polls of Global for cache invalidations, 5 seconds by default
+    pub update_interval: Duration,
+    /// Maximum number of mappings held in cache, unbounded by default
+    pub max_entries: Option<usize>,
+    /// Connect to Global over TLS if set, else in plaintext as by default
+    pub tls: Option<TlsConfig>,
+    /// Shared secret sent with every request, for Globals started with a token
+    pub token: Option<String>,
+    /// Time an attempt at a request to Global is given to complete, 10 seconds by default. Unbounded if `None`.
+    pub timeout: Option<Duration>,
+    /// Number of times a request is retried, with exponential backoff, if Global couldn't be reached
+    /// or didn't respond in time, 2 by default. Writes that aren't safe to repeat aren't retried.
+    pub retries: usize,
+    /// Warm cache on joining with every mapping on Global whose VALUE is small, off by default
+    pub preload: bool,
+    /// VALUEs larger than this many bytes aren't cached, but fetched from Global on each read. Unbounded by
+    /// default, though VALUEs streamed from a reader are cached only if within a limit, see `insert_from_reader()`.
+    pub max_cached_value: Option<usize>,
+    /// Size of VALUE frames in streamed transfers, `MAX_BYTE_SIZE` by default. Smaller frames may suit
+    /// high latency links better, sizes beyond `max_message_size` are capped to it.
+    pub chunk_size: usize,
+    /// Largest gRPC message sent to Global, `MAX_BYTE_SIZE` by default. Mappings that fit are sent in a
+    /// single packet, larger ones are streamed in frames. Raise it along with `chunk_size`, to send large
+    /// VALUEs whole over fast links, so long as Global's is raised alike, see `Global::set_max_message_size()`.
+    pub max_message_size: usize,
+    /// Verify VALUEs streamed to and from Global against a CRC32 of them, on by default. VALUEs streamed from
+    /// a reader aren't verified, as their checksum is only known once they've been sent.
+    pub checksum: bool,
+    /// Compress requests to Global with gzip, off by default. Worth it for large, compressible VALUEs such
+    /// as text, though it costs CPU for small ones. Falls back to uncompressed requests should Global not
+    /// accept compressed ones. Responses are compressed if Global is configured to, regardless.
+    pub gzip: bool,
+    /// Poll Global for cache invalidations and heartbeat in the background every `update_interval`, on by
+    /// default. Without it, cache is never invalidated unless `update()` is called, nor is Global sent
+    /// heartbeats, so Local may be dropped from cluster after Global's node timeout. Suits short-lived clients.
+    pub background_updates: bool,
+}
+
+impl Default for LocalConfig {
+    fn default() -> Self {
+        Self {
+            update_interval: Duration::from_secs(5),
+            max_entries: None,
+            tls: None,
+            token: None,
+            timeout: Some(Duration::from_secs(10)),
+            retries: 2,
+            preload: false,
+            max_cached_value: None,
+            chunk_size: MAX_BYTE_SIZE,
+            max_message_size: MAX_BYTE_SIZE,
+            checksum: true,
+            gzip: false,
+            background_updates: true,
+        }
+    }
+}
+
+/// Builds a Local with chained setters, as an alternative to filling in a `LocalConfig`.
+/// Options not set are left at their defaults, see `LocalConfig`.
+#[derive(Clone, Debug)]
+pub struct LocalBuilder {
+    global_addr: String,
+    local_addr: String,
+    config: LocalConfig,
+}
+
+impl LocalBuilder {
+    /// Start building a Local with UID `local_addr`, to connect to Global on `global_addr`
+    pub fn new(global_addr: &str, local_addr: &str) -> Self {
+        Self {
+            global_addr: global_addr.to_string(),
+            local_addr: local_addr.to_string(),
+            config: LocalConfig::default(),
+        }
+    }
+
+    /// Replace every option with those in `config`
+    pub fn config(mut self, config: LocalConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Interval between polls of Global for cache invalidations and heartbeats
+    pub fn update_interval(mut self, interval: Duration) -> Self {
+        self.config.update_interval = interval;
+        self
+    }
+
+    /// Hold atmost `max_entries` mappings in cache, evicting least recently used ones to fit
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.config.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Connect to Global over TLS
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.config.tls = Some(tls);
+        self
+    }
+
+    /// Send shared secret `token` with every request, for Globals started with one
+    pub fn token(mut self, token: &str) -> Self {
+        self.config.token = Some(token.to_string());
+        self
+    }
+
+    /// Time an attempt at a request to Global is given to complete, unbounded if `None`
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    /// Number of times a request is retried if Global couldn't be reached or didn't respond in time
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.config.retries = retries;
+        self
+    }
+
+    /// Warm cache with every small mapping on Global on joining
+    pub fn preload(mut self, preload: bool) -> Self {
+        self.config.preload = preload;
+        self
+    }
+
+    /// Don't cache VALUEs larger than `size` bytes
+    pub fn max_cached_value(mut self, size: usize) -> Self {
+        self.config.max_cached_value = Some(size);
+        self
+    }
+
+    /// Size of VALUE frames in streamed transfers
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.config.chunk_size = size;
+        self
+    }
+
+    /// Verify VALUEs streamed to and from Global against a CRC32 of them
+    pub fn checksum(mut self, checksum: bool) -> Self {
+        self.config.checksum = checksum;
+        self
+    }
+
+    /// Compress requests to Global with gzip
+    pub fn gzip(mut self, gzip: bool) -> Self {
+        self.config.gzip = gzip;
+        self
+    }
+
+    /// Largest gRPC message sent to Global, larger mappings are streamed
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.config.max_message_size = size;
+        self
+    }
+
+    /// Poll Global for cache invalidations and heartbeat in the background
+    pub fn background_updates(mut self, background_updates: bool) -> Self {
+        self.config.background_updates = background_updates;
+        self
+    }
+
+    /// Connect to Global and join it's cluster, as configured
+    pub async fn connect(self) -> Result<Arc<Mutex<Local>>, DstoreError> {
+        Local::with_config(&self.global_addr, &self.local_addr, self.config).await
+    }
+}
+
+/// VALUE being streamed from Global, see `Local::pull()`
+struct Pulled {
+    stream: tonic::Streaming<Byte>,
+    verifier: Verifier,
+    /// Size of VALUE, if declared
+    size: Option<u64>,
+    /// Version KEY is at, 0 if not declared
+    version: u64,
+    /// Whether KEY's TTL slides, in which case VALUE isn't cached
+    sliding: bool,
+}
+
+/// Heartbeat and update requests of a single updater tick, made over a clone of Local's client so that
+/// Local isn't locked while awaiting Global's replies
+struct Poller {
+    global: Client,
+    timeout: Option<Duration>,
+    retries: usize,
+    heartbeat: Request<Byte>,
+    update: Request<UpdateAck>,
+}
+
+impl Poller {
+    /// Send a heartbeat, then drain invalidation queue, as `Local::heartbeat()` and `Local::update()` do
+    async fn poll(self) -> (Result<(), Status>, Result<Invalidations, Status>) {
+        let mut global = self.global.clone();
+        let heartbeat = attempt(self.timeout, global.heartbeat(self.heartbeat))
+            .await
+            .map(drop);
+        let update = retrying(
+            &self.global,
+            self.timeout,
+            self.retries,
+            self.update,
+            |mut global, req| async move { global.update_batch(req).await },
+        )
+        .await
+        .map(Response::into_inner);
+
+        (heartbeat, update)
+    }
+}
+
 /// Maintain state of Local cache
 pub struct Local {
     /// Local, cached in-memory database
     db: HashMap<Bytes, Bytes>,
+    /// Order in which KEYs were last used
+    lru: Lru,
+    /// Maximum number of mappings held in cache, unbounded if `None`
+    max_entries: Option<usize>,
+    /// KEYs preloaded from a snapshot, yet to be validated against Global
+    pending: HashSet<Bytes>,
+    /// Instants at which cached mappings inserted with a TTL expire
+    expiries: HashMap<Bytes, Instant>,
+    /// Versions of cached VALUEs, where known
+    versions: HashMap<Bytes, u64>,
     /// Stores client connection with Global
-    global: DstoreClient<Channel>,
+    global: Client,
     /// Using an address as UID
     pub addr: String,
+    /// Whether Local is still a member of Global's cluster
+    joined: bool,
+    /// Sequence number of the last batch of invalidations recieved, acknowledged on next update
+    update_seq: u64,
+    /// Whether Global has signalled that it is shutting down
+    draining: bool,
+    /// VALUEs of atleast this many bytes are compressed in transit, uncompressed if `None`
+    compress_above: Option<usize>,
+    /// Decides which VALUEs over `compress_above` compress well enough to be worth it
+    sampling: Sampling,
+    /// Background task polling Global for cache invalidations, until `shutdown()`
+    updater: Option<JoinHandle<()>>,
+    /// Number of reads served from cache
+    hits: AtomicU64,
+    /// Number of reads that missed cache
+    misses: AtomicU64,
+    /// Number of VALUEs fetched from Global
+    remote_fetches: AtomicU64,
+    /// Fencing token attached to requests, none if 0
+    fence: Arc<AtomicU64>,
+    /// Time an attempt at a request to Global is given to complete, unbounded if `None`
+    timeout: Option<Duration>,
+    /// Number of times a request that failed transiently is retried
+    retries: usize,
+    /// VALUEs larger than this many bytes aren't cached, unbounded if `None`
+    max_cached_value: Option<usize>,
+    /// Size of VALUE frames in streamed transfers, sent along so Global frames them alike
+    chunk_size: usize,
+    /// Largest message sent to Global, mappings larger are streamed
+    max_message_size: usize,
+    /// Whether VALUEs streamed to and from Global are verified against their checksum
+    checksum: bool,
 }
 
 impl Local {
-    /// Generate reference counted pointer to datastructure maintaining Local state
-    pub async fn new(
+    /// Generate reference counted pointer to datastructure maintaining Local state. `global_addr` is either a
+    /// TCP address or, on Unix, the path of Global's socket prefixed `unix:`, as in `unix:/tmp/dstore.sock`.
+    pub async fn new(global_addr: &str, local_addr: &str) -> Result<Arc<Mutex<Self>>, DstoreError> {
+        Self::builder(global_addr, local_addr).connect().await
+    }
+
+    /// Start building a Local with non-default options, see `LocalBuilder`
+    pub fn builder(global_addr: &str, local_addr: &str) -> LocalBuilder {
+        LocalBuilder::new(global_addr, local_addr)
+    }
+
+    /// Generate Local as with `new()`, configured by `config`
+    pub async fn with_config(
         global_addr: &str,
         local_addr: &str,
-    ) -> Result<Arc<Mutex<Self>>, Box<dyn Error>> {
-        // Client connection to Global server
-        let mut global = DstoreClient::connect(format!("http://{}", global_addr)).await?;
+        config: LocalConfig,
+    ) -> Result<Arc<Mutex<Self>>, DstoreError> {
+        let endpoint = transport::endpoint(global_addr, config.tls.as_ref())?;
+        let channel = match config.timeout {
+            Some(timeout) => time::timeout(timeout, transport::connect(endpoint, global_addr))
+                .await
+                .map_err(|_| DstoreError::Timeout)??,
+            None => transport::connect(endpoint, global_addr).await?,
+        };
+
+        Self::with_channel(channel, local_addr, config).await
+    }
+
+    /// Generate Local as with `with_config()`, talking to Global over `channel` rather than connecting
+    /// to it by address, as over the in-memory transport of `testing::MemoryGlobal`
+    pub(crate) async fn with_channel(
+        channel: Channel,
+        local_addr: &str,
+        config: LocalConfig,
+    ) -> Result<Arc<Mutex<Self>>, DstoreError> {
+        // Client connection to Global server, each request is tagged with Local's UID
+        let fence = Arc::new(AtomicU64::new(0));
+        let tagging = Tagging::new(Some(local_addr), config.token.as_deref(), fence.clone())?;
+        let mut global =
+            DstoreClient::with_interceptor(channel.clone(), tagging.clone()).accept_gzip();
+        if config.gzip {
+            global = global.send_gzip();
+        }
 
         // Check if Local is allowed to join Global's cluster
-        match global
-            .join(Request::new(Byte {
-                body: local_addr.as_bytes().to_vec(),
-            }))
-            .await
-        {
+        let req = Request::new(Byte {
+            body: local_addr.as_bytes().to_vec(),
+        });
+        let mut joined = attempt(config.timeout, global.join(copy_request(&req))).await;
+        // Fall back to uncompressed requests, should Global not accept compressed ones
+        if matches!(&joined, Err(status) if config.gzip && rejects_compression(status)) {
+            global = DstoreClient::with_interceptor(channel, tagging).accept_gzip();
+            joined = attempt(config.timeout, global.join(req)).await;
+        }
+        match joined {
             Ok(_) => {
                 // If able to join, create reference counted pointer to Local state
                 let node = Arc::new(Mutex::new(Self {
                     db: HashMap::new(),
+                    lru: Lru::default(),
+                    max_entries: config.max_entries,
+                    pending: HashSet::new(),
+                    expiries: HashMap::new(),
+                    versions: HashMap::new(),
                     global,
                     addr: local_addr.to_string(),
+                    joined: true,
+                    update_seq: 0,
+                    draining: false,
+                    compress_above: None,
+                    sampling: Sampling::default(),
+                    updater: None,
+                    hits: AtomicU64::new(0),
+                    misses: AtomicU64::new(0),
+                    remote_fetches: AtomicU64::new(0),
+                    fence,
+                    timeout: config.timeout,
+                    retries: config.retries,
+                    max_cached_value: config.max_cached_value,
+                    chunk_size: config.chunk_size.clamp(1, config.max_message_size),
+                    max_message_size: config.max_message_size,
+                    checksum: config.checksum,
                 }));
 
-                // Start a timer at intervals of 5 seconds, create clone of Local pointer
-                let mut timer = time::interval(Duration::from_secs(5));
-                let updater = node.clone();
+                if config.background_updates {
+                    // Start a timer at the configured interval, with a weak pointer to Local so that
+                    // the updater doesn't keep Local alive once every other reference is dropped
+                    let mut timer = time::interval(config.update_interval);
+                    let updater = Arc::downgrade(&node);
+
+                    // Start thread to concurrently update cache by refering Global invalidation queue
+                    let handle = tokio::spawn(async move {
+                        loop {
+                            timer.tick().await;
+                            let Some(local) = updater.upgrade() else {
+                                break;
+                            };
+                            // Requests are made without holding Local's lock, so that calls on Local
+                            // aren't held up while Global is slow to respond
+                            let poller = local.lock().await.poller();
+                            drop(local);
+                            let Some(poller) = poller else {
+                                continue;
+                            };
+                            let (heartbeat, update) = poller.poll().await;
+                            let Some(local) = updater.upgrade() else {
+                                break;
+                            };
+                            let mut local = local.lock().await;
+                            // Rejoining supersedes invalidations queued before
+                            if !local.apply_heartbeat(heartbeat).await {
+                                local.apply_update(update).await;
+                            }
+                        }
+                    });
+                    node.lock().await.updater = Some(handle);
+                }
+                if config.preload {
+                    node.lock().await.bootstrap().await?;
+                }
+
+                Ok(node)
+            }
+            Err(e) if e.code() == Code::DeadlineExceeded => Err(DstoreError::Timeout),
+            Err(e) => Err(DstoreError::Connection(format!(
+                "Couldn't join cluster: {}",
+                e.message()
+            ))),
+        }
+    }
+
+    /// Generate Local as with `new()`, holding atmost `max_entries` mappings in cache.
+    /// Least recently used mappings are evicted from cache to fit, they remain on Global.
+    pub async fn with_max_entries(
+        global_addr: &str,
+        local_addr: &str,
+        max_entries: usize,
+    ) -> Result<Arc<Mutex<Self>>, DstoreError> {
+        let config = LocalConfig {
+            max_entries: Some(max_entries),
+            ..LocalConfig::default()
+        };
+        Self::with_config(global_addr, local_addr, config).await
+    }
+
+    /// Limit the number of mappings held in cache, evicting least recently used ones to fit
+    pub fn set_max_entries(&mut self, max_entries: Option<usize>) {
+        self.max_entries = max_entries;
+        self.evict();
+    }
+
+    /// Check if Global has signalled that it is shutting down, cache may soon stop being invalidated
+    pub fn is_draining(&self) -> bool {
+        self.draining
+    }
+
+    /// Compress single packet VALUEs of atleast `threshold` bytes in transit to and from Global,
+    /// smaller VALUEs aren't worth the CPU spent compressing them. Disabled if `None`
+    pub fn set_compression_threshold(&mut self, threshold: Option<usize>) {
+        self.compress_above = threshold;
+    }
+
+    /// Before compressing a VALUE, deflate it's first `sample_size` bytes and skip compression
+    /// unless they shrink to atmost `max_ratio` of their size, as with already compressed blobs
+    pub fn set_compression_sampling(&mut self, sample_size: usize, max_ratio: f64) {
+        self.sampling = Sampling {
+            sample_size,
+            max_ratio,
+        };
+    }
+
+    /// Become the writer Global recognises, by being issued a fencing token newer than any before it.
+    /// Writes made thereafter carry the token, Global rejects those from writers issued older tokens.
+    pub async fn promote(&mut self) -> Result<u64, DstoreError> {
+        let Fence { token } = self
+            .call(Request::new(Null {}), |mut global, req| async move {
+                global.promote(req).await
+            })
+            .await?
+            .into_inner();
+        self.fence.store(token, Ordering::SeqCst);
+
+        Ok(token)
+    }
+
+    /// Fencing token writes currently carry, `None` if writes aren't fenced
+    pub fn fencing_token(&self) -> Option<u64> {
+        match self.fence.load(Ordering::SeqCst) {
+            0 => None,
+            token => Some(token),
+        }
+    }
+
+    /// How often reads were served from cache versus fetched from Global, since Local was created
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            remote_fetches: self.remote_fetches.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Look KEY up in cache as `lookup()` does, counting the outcome towards `stats()`
+    async fn lookup_counted(&mut self, key: &Bytes) -> Option<Bytes> {
+        let value = self.lookup(key).await;
+        match value {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        value
+    }
+
+    /// Check if a KEY is currently held in cache
+    pub fn is_cached(&self, key: &Bytes) -> bool {
+        self.db.contains_key(key)
+    }
+
+    /// Drop every mapping from cache, leaving Global untouched. Following reads fetch VALUEs afresh from
+    /// Global, as when cached copies are suspected to be stale.
+    pub fn clear_cache(&mut self) {
+        self.flush();
+    }
+
+    /// Drop mapping of KEY from cache, leaving Global untouched, returns `false` if not cached.
+    /// The next read of KEY fetches it's VALUE afresh from Global.
+    pub fn invalidate(&mut self, key: &Bytes) -> bool {
+        let cached = self.is_cached(key);
+        self.uncache(key);
+        cached
+    }
+
+    /// Mark a cached KEY as most recently used without reading it's VALUE, returns `false` if not cached
+    pub fn touch(&mut self, key: &Bytes) -> bool {
+        self.lru.touch(key)
+    }
+
+    /// Serialize cached mappings along with their versions, for use with `preload_snapshot()` on another node
+    /// or a later run. Mappings of unknown version are exported as version 0, never found valid on preloading.
+    pub fn export_snapshot(&self) -> Bytes {
+        snapshot::encode_versioned(self.db.iter().map(|(key, value)| {
+            let version = self.versions.get(key).copied().unwrap_or_default();
+            (key, version, value)
+        }))
+    }
+
+    /// Warm cache with mappings from a snapshot, each is validated against Global's version of it on first access
+    pub fn preload_snapshot(&mut self, snapshot: Bytes) -> Result<usize, DstoreError> {
+        let entries = snapshot::decode_versioned(snapshot)?;
+        let count = entries.len();
+        for (key, version, value) in entries {
+            self.pending.insert(key.clone());
+            self.cache(key.clone(), value);
+            if self.db.contains_key(&key) {
+                self.versions.insert(key, version);
+            }
+        }
+
+        Ok(count)
+    }
 
-                // Start thread to concurrently update cache by refering Global invalidation queue
-                tokio::spawn(async move {
-                    loop {
-                        timer.tick().await;
-                        updater.lock().await.update().await;
+    /// Read VALUE from cache, validating mappings preloaded from a snapshot with Global
+    async fn lookup(&mut self, key: &Bytes) -> Option<Bytes> {
+        let value = self.db.get(key).cloned()?;
+        if matches!(self.expiries.get(key), Some(expiry) if *expiry <= Instant::now()) {
+            self.uncache(key);
+            return None;
+        }
+        if self.pending.remove(key) {
+            // Drop preloaded mapping if Global has since moved on to another version of it
+            let version = self.versions.get(key).copied().unwrap_or_default();
+            let req = Request::new(Byte { body: key.to_vec() });
+            match self
+                .call(
+                    req,
+                    |mut global, req| async move { global.contains(req).await },
+                )
+                .await
+            {
+                Ok(presence) if version != 0 && presence.get_ref().version == version => {}
+                _ => {
+                    self.uncache(key);
+                    return None;
+                }
+            }
+        }
+        self.touch(key);
+
+        Some(value)
+    }
+
+    /// Add mapping to cache as most recently used, evicting least recently used mappings if full
+    fn cache(&mut self, key: Bytes, value: Bytes) {
+        // Drop rather than keep an older copy of a VALUE too large to cache
+        if self.max_cached_value.is_some_and(|max| value.len() > max) {
+            self.uncache(&key);
+            return;
+        }
+        self.db.insert(key.clone(), value);
+        self.lru.insert(key);
+        self.evict();
+    }
+
+    /// Remove mapping from cache, VALUE remains on Global
+    fn uncache(&mut self, key: &[u8]) {
+        if self.db.remove(key).is_some() {
+            self.lru.remove(key);
+            self.pending.remove(key);
+            self.expiries.remove(key);
+            self.versions.remove(key);
+        }
+    }
+
+    /// Drop all mappings from cache
+    fn flush(&mut self) {
+        self.db.clear();
+        self.lru.clear();
+        self.pending.clear();
+        self.expiries.clear();
+        self.versions.clear();
+    }
+
+    /// Drop least recently used mappings until cache fits within `max_entries`
+    fn evict(&mut self) {
+        if let Some(max) = self.max_entries {
+            while self.db.len() > max {
+                match self.lru.oldest() {
+                    Some(key) => self.uncache(&key),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Make a request to Global through `rpc`, which is invoked afresh with a copy of `req` on each attempt.
+    /// Attempts that find Global unreachable or that time out are retried with exponential backoff.
+    async fn call<M, T, F, Fut>(&self, req: Request<M>, rpc: F) -> Result<Response<T>, Status>
+    where
+        M: Clone,
+        F: Fn(Client, Request<M>) -> Fut,
+        Fut: Future<Output = Result<Response<T>, Status>>,
+    {
+        retrying(&self.global, self.timeout, self.retries, req, rpc).await
+    }
+
+    /// Make a request to Global as with `call()`, but in a single attempt. For writes that aren't safe to
+    /// repeat, as an attempt that timed out may still have been applied.
+    async fn call_once<M, T, F, Fut>(&self, req: Request<M>, rpc: F) -> Result<Response<T>, Status>
+    where
+        F: Fn(Client, Request<M>) -> Fut,
+        Fut: Future<Output = Result<Response<T>, Status>>,
+    {
+        attempt(self.timeout, rpc(self.global.clone(), req)).await
+    }
+
+    /// Leave Global's cluster, cache no longer recieves invalidations from Global
+    pub async fn leave(&mut self) -> Result<(), DstoreError> {
+        let req = Request::new(Byte {
+            body: self.addr.as_bytes().to_vec(),
+        });
+        match self
+            .call(
+                req,
+                |mut global, req| async move { global.leave(req).await },
+            )
+            .await
+        {
+            Ok(_) => {
+                self.joined = false;
+                Ok(())
+            }
+            Err(e) => Err(DstoreError::from(e)),
+        }
+    }
+
+    /// Check connection with Global, returning the round-trip latency of a request
+    pub async fn ping(&mut self) -> Result<Duration, DstoreError> {
+        let start = Instant::now();
+        self.call(Request::new(Null {}), |mut global, req| async move {
+            global.ping(req).await
+        })
+        .await?;
+
+        Ok(start.elapsed())
+    }
+
+    /// Check if Local polls Global for cache invalidations in the background, as until `shutdown()`
+    /// unless constructed without `LocalConfig::background_updates`
+    pub fn is_updating(&self) -> bool {
+        self.updater.is_some()
+    }
+
+    /// Stop polling Global for cache invalidations, leaving cluster too if `leave` is set.
+    /// Cache may turn stale thereafter.
+    pub async fn shutdown(&mut self, leave: bool) -> Result<(), DstoreError> {
+        if let Some(updater) = self.updater.take() {
+            updater.abort();
+        }
+        if leave && self.joined {
+            self.leave().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove cached mappings as per directions from Global Invalidation queue
+    pub async fn update(&mut self) {
+        // Invalidation queue is dropped from Global when leaving cluster
+        if !self.joined {
+            return;
+        }
+
+        let req = self.update_request();
+        let res = self
+            .call(req, |mut global, req| async move {
+                global.update_batch(req).await
+            })
+            .await;
+        self.apply_update(res.map(Response::into_inner)).await;
+    }
+
+    /// Request draining invalidation queue in a single reply, asking for KEYs to be compressed if many. Global
+    /// holds on to the last batch until acknowledged, so that a failed request loses no invalidations.
+    fn update_request(&self) -> Request<UpdateAck> {
+        let mut req = Request::new(UpdateAck {
+            uid: self.addr.as_bytes().to_vec(),
+            seq: self.update_seq,
+        });
+        if let Some(threshold) = self.compress_above {
+            req.metadata_mut().insert(
+                COMPRESSION_HEADER,
+                MetadataValue::from_str(&threshold.to_string()).unwrap(),
+            );
+        }
+
+        req
+    }
+
+    /// Invalidate cache as per Global's reply to `update_request()`
+    async fn apply_update(&mut self, res: Result<Invalidations, Status>) {
+        if !self.joined {
+            return;
+        }
+        let Invalidations {
+            keys,
+            compressed,
+            flush,
+            draining,
+            seq,
+        } = match res {
+            Ok(invalidations) => invalidations,
+            // Global restarted and lost track of cluster, along with any invalidations pending
+            Err(e) if e.code() == Code::FailedPrecondition => {
+                let _ = self.rejoin().await;
+                return;
+            }
+            Err(_) => return,
+        };
+        self.update_seq = seq;
+
+        if draining {
+            self.draining = true;
+        }
+        // Too many invalidations to keep track of, bring cache in line with Global instead
+        if flush {
+            if self.reconcile().await.is_err() {
+                self.flush();
+            }
+            return;
+        }
+        let keys = compression::decode(keys, compressed)
+            .map_err(DstoreError::from)
+            .and_then(|keys| snapshot::decode_keys(Bytes::from(keys)));
+        match keys {
+            Ok(keys) => {
+                for key in keys {
+                    self.uncache(&key);
+                }
+            }
+            // Can't tell which KEYs are stale, so drop them all
+            Err(_) => self.flush(),
+        }
+    }
+
+    /// Let Global know Local is alive, so that it isn't dropped from cluster. Sent every `update_interval`,
+    /// rejoining if Global has already dropped Local.
+    pub async fn heartbeat(&mut self) {
+        if !self.joined {
+            return;
+        }
+        let req = Request::new(Byte {
+            body: self.addr.as_bytes().to_vec(),
+        });
+        let res = self
+            .call_once(
+                req,
+                |mut global, req| async move { global.heartbeat(req).await },
+            )
+            .await;
+        self.apply_heartbeat(res.map(drop)).await;
+    }
+
+    /// Rejoin if Global's reply to a heartbeat shows it has dropped Local, returns whether it did
+    async fn apply_heartbeat(&mut self, res: Result<(), Status>) -> bool {
+        match res {
+            Err(e) if self.joined && e.code() == Code::FailedPrecondition => {
+                let _ = self.rejoin().await;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Requests the updater makes on each tick, to be sent without holding Local's lock. `None` once Local
+    /// has left cluster.
+    fn poller(&self) -> Option<Poller> {
+        if !self.joined {
+            return None;
+        }
+
+        Some(Poller {
+            global: self.global.clone(),
+            timeout: self.timeout,
+            retries: self.retries,
+            heartbeat: Request::new(Byte {
+                body: self.addr.as_bytes().to_vec(),
+            }),
+            update: self.update_request(),
+        })
+    }
+
+    /// Cache every mapping Global streams for warming a freshly joined Local, upto `max_entries`
+    async fn bootstrap(&mut self) -> Result<(), DstoreError> {
+        let req = Request::new(Byte {
+            body: self.addr.as_bytes().to_vec(),
+        });
+        let mut stream = self
+            .call(
+                req,
+                |mut global, req| async move { global.bootstrap(req).await },
+            )
+            .await?
+            .into_inner();
+        while let Some(pair) = stream.next().await {
+            let KeyValue {
+                key,
+                value,
+                sliding,
+                version,
+                ..
+            } = pair?;
+            // Mappings with a sliding TTL aren't cached, so that every read reaches Global to extend it
+            if !sliding {
+                let key = Bytes::from(key);
+                self.versions.insert(key.clone(), version);
+                self.cache(key, Bytes::from(value));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rejoin Global's cluster after it restarted, reconciling cache as invalidations made before then are
+    /// lost. Cache is dropped should reconciling fail.
+    async fn rejoin(&mut self) -> Result<(), DstoreError> {
+        if self.reconcile().await.is_ok() {
+            return Ok(());
+        }
+        let req = Request::new(Byte {
+            body: self.addr.as_bytes().to_vec(),
+        });
+        self.call(req, |mut global, req| async move { global.join(req).await })
+            .await?;
+        self.update_seq = 0;
+        self.flush();
+
+        Ok(())
+    }
+
+    /// Rejoin Global's cluster and bring cache in line with Global in a single round trip, for use on
+    /// reconnecting after the invalidation queue may have been missed. Cached KEYs that no longer exist
+    /// are evicted, those with a changed version are fetched afresh.
+    pub async fn reconcile(&mut self) -> Result<(), DstoreError> {
+        let keys = self
+            .db
+            .keys()
+            .map(|key| KeyVersion {
+                key: key.to_vec(),
+                version: self.versions.get(key).copied().unwrap_or_default(),
+            })
+            .collect();
+        let req = Request::new(CachedKeys {
+            uid: self.addr.as_bytes().to_vec(),
+            keys,
+        });
+        let Reconciliation { evict, refresh } = self
+            .call(
+                req,
+                |mut global, req| async move { global.reconcile(req).await },
+            )
+            .await?
+            .into_inner();
+        // Global starts a fresh invalidation queue for Local
+        self.joined = true;
+        self.update_seq = 0;
+
+        for key in evict {
+            self.uncache(&key);
+        }
+        for key in refresh {
+            let key = Bytes::from(key);
+            self.uncache(&key);
+            // KEY may have been removed since reconciling, leave it uncached if so
+            let _ = self.get(&key).await;
+        }
+
+        Ok(())
+    }
+
+    /// Remove all mappings from Global and cache, other Locals flush their caches on next update
+    pub async fn clear_all(&mut self) -> Result<usize, DstoreError> {
+        match self
+            .call_once(Request::new(Null {}), |mut global, req| async move {
+                global.clear_all(req).await
+            })
+            .await
+        {
+            Ok(res) => {
+                self.flush();
+                Ok(res.into_inner().size as usize)
+            }
+            Err(e) => Err(DstoreError::from(e)),
+        }
+    }
+
+    /// Insert VALUEs onto Global in either a single packet or as a stream as per it's size
+    pub async fn insert(&mut self, key: Bytes, value: Bytes) -> Result<(), DstoreError> {
+        let (key_len, size) = (key.len(), value.len());
+        let streaming = !fits_single_packet(key_len, size, self.max_message_size);
+        let res = if streaming {
+            self.insert_file(key, value).await
+        } else {
+            self.insert_single(key, value).await
+        };
+        trace_event!(key_len, size, streaming, ok = res.is_ok(), "insert");
+        res
+    }
+
+    /// Insert a single packet sized KEY->VALUE mapping onto Global and store in cache
+    pub async fn insert_single(&mut self, key: Bytes, value: Bytes) -> Result<(), DstoreError> {
+        // Check if LOCAL already contains KEY
+        if self.db.contains_key(&key) {
+            Err(DstoreError::KeyOccupied(key))
+        } else {
+            // If not, consult Global
+            let req = Byte { body: key.to_vec() };
+            match self
+                .call(Request::new(req), |mut global, req| async move {
+                    global.contains(req).await
+                })
+                .await
+            {
+                Ok(size) => {
+                    // If Global contains KEY, update LOCAL cache
+                    if fits_single_packet(
+                        key.len(),
+                        size.into_inner().size as usize,
+                        self.max_message_size,
+                    ) {
+                        self.get_single(&key).await?;
+                    } else {
+                        self.get_file(&key).await?;
                     }
-                });
+                    Err(DstoreError::KeyOccupied(key))
+                }
+                Err(_) => {
+                    // Else push a single packet KEY -> VALUE to update GLOBAL
+                    let (value_bytes, compressed) =
+                        compression::encode_with(&value, self.compress_above, &self.sampling);
+                    let req = Request::new(KeyValue {
+                        key: key.to_vec(),
+                        value: value_bytes,
+                        compressed,
+                        ..Default::default()
+                    });
+                    let res = self
+                        .call_once(req, |mut global, req| async move { global.push(req).await })
+                        .await;
+
+                    match res {
+                        Err(e) => Err(DstoreError::from_status(&key, e)),
+                        Ok(res) => {
+                            // If Global updated successfully, add mapping to cache
+                            self.versions.insert(key.clone(), res.into_inner().version);
+                            self.cache(key, value);
+                            eprintln!("Database updated");
+                            Ok(())
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Insert a single packet sized KEY -> VALUE mapping that expires after `ttl`, on Global and in cache
+    pub async fn insert_with_ttl(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        ttl: Duration,
+    ) -> Result<(), DstoreError> {
+        self.push_with_ttl(key, value, ttl, false).await
+    }
+
+    /// Insert a single packet sized KEY -> VALUE mapping on Global that expires once it goes unread for `ttl`,
+    /// as for sessions. The mapping isn't cached, so that every read reaches Global to extend it's TTL.
+    pub async fn insert_with_sliding_ttl(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        ttl: Duration,
+    ) -> Result<(), DstoreError> {
+        self.push_with_ttl(key, value, ttl, true).await
+    }
+
+    /// Insert a mapping that expires after `ttl`, extended on every read if `sliding`
+    async fn push_with_ttl(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        ttl: Duration,
+        sliding: bool,
+    ) -> Result<(), DstoreError> {
+        if self.db.contains_key(&key) {
+            return Err(DstoreError::KeyOccupied(key));
+        }
+
+        // Global rejects the mapping if KEY is already in use
+        let req = Request::new(KeyValueTtl {
+            key: key.to_vec(),
+            value: value.to_vec(),
+            ttl_millis: millis(ttl),
+            sliding,
+        });
+        match self
+            .call_once(req, |mut global, req| async move {
+                global.push_with_ttl(req).await
+            })
+            .await
+        {
+            Ok(res) => {
+                if !sliding {
+                    self.expiries.insert(key.clone(), Instant::now() + ttl);
+                    self.versions.insert(key.clone(), res.into_inner().version);
+                    self.cache(key, value);
+                }
+                eprintln!("Database updated");
+                Ok(())
+            }
+            Err(e) => Err(DstoreError::from_status(&key, e)),
+        }
+    }
+
+    /// Set or update the TTL of a KEY already mapped on Global, so that it expires after `ttl`.
+    /// Other Locals drop their copies on next update.
+    pub async fn expire(&mut self, key: &Bytes, ttl: Duration) -> Result<(), DstoreError> {
+        let req = Request::new(Expiry {
+            key: key.to_vec(),
+            ttl_millis: millis(ttl),
+        });
+        match self
+            .call(
+                req,
+                |mut global, req| async move { global.expire(req).await },
+            )
+            .await
+        {
+            Ok(_) => {
+                if self.is_cached(key) {
+                    self.expiries.insert(key.clone(), Instant::now() + ttl);
+                }
+                Ok(())
+            }
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Remove the TTL of a KEY mapped on Global, so that it no longer expires. Returns `false` if KEY had no TTL.
+    pub async fn persist(&mut self, key: &Bytes) -> Result<bool, DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        match self
+            .call_once(
+                req,
+                |mut global, req| async move { global.persist(req).await },
+            )
+            .await
+        {
+            Ok(res) => {
+                self.expiries.remove(key);
+                Ok(res.into_inner().value)
+            }
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Seconds left before KEY expires on Global. As with Redis, `-1` if KEY has no TTL and `-2` if it isn't mapped.
+    pub async fn ttl(&mut self, key: &Bytes) -> Result<i64, DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        let res = self
+            .call(req, |mut global, req| async move { global.ttl(req).await })
+            .await?;
+
+        Ok(res.into_inner().secs)
+    }
+
+    /// Replace VALUE of a KEY already mapped on Global, other Locals drop stale copies on next update
+    pub async fn update_value(&mut self, key: Bytes, value: Bytes) -> Result<(), DstoreError> {
+        let (value_bytes, compressed) =
+            compression::encode_with(&value, self.compress_above, &self.sampling);
+        let req = Request::new(KeyValue {
+            key: key.to_vec(),
+            value: value_bytes,
+            compressed,
+            ..Default::default()
+        });
+        // Each overwrite bumps KEY's version and invalidates peers anew, so it isn't repeated
+        match self
+            .call_once(
+                req,
+                |mut global, req| async move { global.overwrite(req).await },
+            )
+            .await
+        {
+            Ok(res) => {
+                self.versions.insert(key.clone(), res.into_inner().version);
+                self.cache(key, value);
+                eprintln!("Database updated");
+                Ok(())
+            }
+            Err(e) => Err(DstoreError::from_status(&key, e)),
+        }
+    }
+
+    /// Insert a single packet sized KEY -> VALUE mapping, or replace VALUE if KEY is already mapped
+    /// on Global, other Locals drop stale copies on next update
+    pub async fn upsert(&mut self, key: Bytes, value: Bytes) -> Result<Upsert, DstoreError> {
+        let (value_bytes, compressed) =
+            compression::encode_with(&value, self.compress_above, &self.sampling);
+        let req = Request::new(KeyValue {
+            key: key.to_vec(),
+            value: value_bytes,
+            compressed,
+            ..Default::default()
+        });
+        match self
+            .call_once(
+                req,
+                |mut global, req| async move { global.upsert(req).await },
+            )
+            .await
+        {
+            Ok(res) => {
+                let Upserted { created, version } = res.into_inner();
+                self.versions.insert(key.clone(), version);
+                self.cache(key, value);
+                eprintln!("Database updated");
+                Ok(match created {
+                    true => Upsert::Created { version },
+                    false => Upsert::Updated { version },
+                })
+            }
+            Err(e) => Err(DstoreError::from_status(&key, e)),
+        }
+    }
 
-                Ok(node)
+    /// Atomically replace VALUE of KEY on Global with `value`, only if it currently equals `expected`
+    pub async fn compare_and_swap(
+        &mut self,
+        key: Bytes,
+        expected: Bytes,
+        value: Bytes,
+    ) -> Result<bool, DstoreError> {
+        let req = Request::new(Swap {
+            key: key.to_vec(),
+            expected: expected.to_vec(),
+            value: value.to_vec(),
+        });
+        match self
+            .call_once(req, |mut global, req| async move {
+                global.compare_and_swap(req).await
+            })
+            .await
+        {
+            Ok(res) if res.get_ref().swapped => {
+                self.versions.insert(key.clone(), res.into_inner().version);
+                self.cache(key, value);
+                Ok(true)
+            }
+            Ok(_) => {
+                // Cached copy, if any, may not reflect VALUE on Global
+                self.uncache(&key);
+                Ok(false)
             }
-            Err(_) => Err(Box::new(DstoreError("Couldn't join cluster".to_string()))),
+            Err(e) => Err(DstoreError::from_status(&key, e)),
         }
     }
 
-    /// Remove cached mappings as per directions from Global Invalidation queue
-    pub async fn update(&mut self) {
-        while let Ok(key) = self
-            .global
-            .update(Request::new(Byte {
-                body: self.addr.as_bytes().to_vec(),
-            }))
+    /// Get VALUE associated with KEY from Global along with it's version, to be passed to `put_if_version()`.
+    /// Always consults Global, refreshing the cached copy. VALUE must fit in a single packet.
+    pub async fn get_with_version(&mut self, key: &Bytes) -> Result<(Bytes, u64), DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        match self
+            .call(req, |mut global, req| async move { global.pull(req).await })
             .await
         {
-            self.db.remove(&key.into_inner().body[..]);
+            Ok(res) => {
+                self.remote_fetches.fetch_add(1, Ordering::Relaxed);
+                let Value {
+                    body,
+                    compressed,
+                    version,
+                    sliding,
+                } = res.into_inner();
+                let value = Bytes::from(compression::decode(body, compressed)?);
+                if !sliding {
+                    self.versions.insert(key.clone(), version);
+                    self.cache(key.clone(), value.clone());
+                }
+                Ok((value, version))
+            }
+            Err(e) => Err(DstoreError::from_status(key, e)),
         }
     }
 
-    /// Insert VALUEs onto Global in either a single packet or as a stream as per it's size
-    pub async fn insert(&mut self, key: Bytes, value: Bytes) -> Result<(), Box<dyn Error>> {
-        if value.len() < MAX_BYTE_SIZE {
-            self.insert_single(key, value).await
-        } else {
-            self.insert_file(key, value).await
+    /// Current version of KEY on Global, without fetching VALUE. Versions only ever grow, even should KEY be
+    /// removed and mapped anew.
+    pub async fn version(&mut self, key: &Bytes) -> Result<u64, DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        match self
+            .call(
+                req,
+                |mut global, req| async move { global.contains(req).await },
+            )
+            .await
+        {
+            Ok(res) => Ok(res.into_inner().version),
+            Err(e) => Err(DstoreError::from_status(key, e)),
         }
     }
 
-    /// Insert a single packet sized KEY->VALUE mapping onto Global and store in cache
-    pub async fn insert_single(&mut self, key: Bytes, value: Bytes) -> Result<(), Box<dyn Error>> {
-        // Check if LOCAL already contains KEY
-        if self.db.contains_key(&key) {
-            return Err(Box::new(DstoreError("Key occupied!".to_string())));
-        } else {
-            // If not, consult Global
-            let req = Byte { body: key.to_vec() };
-            match self.global.contains(Request::new(req)).await {
-                Ok(size) => {
-                    // If Global contains KEY, update LOCAL cache
-                    if size.into_inner().size as usize > MAX_BYTE_SIZE {
-                        self.get_file(&key).await?;
-                    } else {
-                        self.get_single(&key).await?;
-                    }
-                    Err(Box::new(DstoreError(
-                        "Local updated, Key occupied!".to_string(),
-                    )))
-                }
-                Err(_) => {
-                    // Else push a single packet KEY -> VALUE to update GLOBAL
-                    let req = Request::new(KeyValue {
-                        key: key.to_vec(),
-                        value: value.to_vec(),
-                    });
-                    let res = self.global.push(req).await;
-
-                    if let Err(e) = res {
-                        Err(Box::new(DstoreError(format!(
-                            "Couldn't update Global: {}",
-                            e
-                        ))))
-                    } else {
-                        // If Global updated successfully, add mapping to cache
-                        self.db.insert(key, value);
-                        Ok(eprintln!("Database updated"))
+    /// Map KEY to VALUE on Global only if KEY is still at `expected_version`, as read by `get_with_version()`,
+    /// or isn't mapped if it's 0. Returns the new version, else errs with `VersionConflict` should another
+    /// write have come between, so that no update is lost. Not retried, as an attempt may have been applied.
+    pub async fn put_if_version(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        expected_version: u64,
+    ) -> Result<u64, DstoreError> {
+        let req = Request::new(VersionedValue {
+            key: key.to_vec(),
+            value: value.to_vec(),
+            expected_version,
+        });
+        match self
+            .call_once(req, |mut global, req| async move {
+                global.push_if_version(req).await
+            })
+            .await
+        {
+            Ok(res) => {
+                let version = res.into_inner().version;
+                self.versions.insert(key.clone(), version);
+                self.cache(key, value);
+                Ok(version)
+            }
+            Err(e) => {
+                // Cached copy, if any, may not reflect VALUE on Global
+                self.uncache(&key);
+                match e.metadata().get(VERSION_HEADER) {
+                    Some(current) if e.code() == Code::Aborted => {
+                        let current = current.to_str().ok().and_then(|v| v.parse().ok());
+                        Err(DstoreError::VersionConflict {
+                            key,
+                            current: current.unwrap_or_default(),
+                        })
                     }
+                    _ => Err(DstoreError::from_status(&key, e)),
                 }
             }
         }
     }
 
     /// Insert large KEY -> VALUE mappings on Global and store in cache
-    pub async fn insert_file(&mut self, key: Bytes, value: Bytes) -> Result<(), Box<dyn Error>> {
+    pub async fn insert_file(&mut self, key: Bytes, value: Bytes) -> Result<(), DstoreError> {
+        self.push_file(key, value, None).await
+    }
+
+    /// Insert large KEY -> VALUE mappings on Global as `insert_file()` does, calling `progress` with the bytes
+    /// sent so far and the size of VALUE after each frame.
+    pub async fn insert_file_with_progress(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        mut progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<(), DstoreError> {
+        self.push_file(key, value, Some(&mut progress)).await
+    }
+
+    /// Stream VALUE to Global in frames, reporting `progress` after each frame if any
+    async fn push_file(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        progress: Option<&mut (dyn FnMut(u64, Option<u64>) + Send)>,
+    ) -> Result<(), DstoreError> {
         // Check if LOCAL already contains KEY
         if self.db.contains_key(&key) {
-            return Err(Box::new(DstoreError("Key occupied!".to_string())));
+            Err(DstoreError::KeyOccupied(key))
         } else {
             // If not, consult Global
             let req = Byte { body: key.to_vec() };
-            match self.global.contains(Request::new(req.clone())).await {
+            match self
+                .call(Request::new(req.clone()), |mut global, req| async move {
+                    global.contains(req).await
+                })
+                .await
+            {
                 Ok(size) => {
                     // If Global contains KEY, update LOCAL cache
-                    if size.into_inner().size as usize > MAX_BYTE_SIZE {
-                        self.get_file(&key).await?;
-                    } else {
+                    if fits_single_packet(
+                        key.len(),
+                        size.into_inner().size as usize,
+                        self.max_message_size,
+                    ) {
                         self.get_single(&key).await?;
+                    } else {
+                        self.get_file(&key).await?;
                     }
-                    Err(Box::new(DstoreError(
-                        "Local updated, Key occupied!".to_string(),
-                    )))
+                    Err(DstoreError::KeyOccupied(key))
                 }
                 Err(_) => {
-                    // Else push steam of packets ordered as `KEY, VALUE(1), VALUE(2)..` frames, to update GLOBAL
-                    let mut frames = vec![Byte { body: key.to_vec() }];
-                    // Size each frame upto MAX_BYTE_SIZE
-                    for i in 0..value.len() / MAX_BYTE_SIZE {
-                        frames.push(Byte {
-                            body: value[i * MAX_BYTE_SIZE..(i + 1) * MAX_BYTE_SIZE].to_vec(),
-                        })
+                    // Else push steam of packets ordered as `KEY, VALUE(1), VALUE(2)..` frames, to update GLOBAL.
+                    // VALUE frames are sized within limit, but KEY must fit in a single frame
+                    if key.len() > self.max_message_size {
+                        return Err(DstoreError::FrameTooLarge {
+                            size: key.len(),
+                            limit: self.max_message_size,
+                        });
                     }
+                    let checksum = MetadataValue::from(checksum::checksum(&value) as u64);
+                    let res = match progress {
+                        None => {
+                            let mut frames = vec![Byte { body: key.to_vec() }];
+                            // Size each frame upto chunk size, including a final frame of trailing bytes
+                            for frame in value.chunks(self.chunk_size) {
+                                frames.push(Byte {
+                                    body: frame.to_vec(),
+                                })
+                            }
+
+                            let mut req = self.chunked(stream::iter(frames));
+                            if self.checksum {
+                                req.metadata_mut().insert(CHECKSUM_HEADER, checksum);
+                            }
+                            self.call_once(req, |mut global, req| async move {
+                                global.push_file(req).await
+                            })
+                            .await
+                        }
+                        Some(progress) => {
+                            // Frames are handed over one at a time, so as to report each once sent
+                            let (tx, rx) = mpsc::channel(2);
+                            let (frames, chunk_size) = (value.clone(), self.chunk_size);
+                            let first = Byte { body: key.to_vec() };
+                            let sending = async move {
+                                let _ = tx.send(first).await;
+                                let (total, mut sent) = (frames.len() as u64, 0);
+                                for frame in frames.chunks(chunk_size) {
+                                    let body = frame.to_vec();
+                                    // Global may have ended the stream early, it's reply says why
+                                    if tx.send(Byte { body }).await.is_err() {
+                                        break;
+                                    }
+                                    sent += frame.len() as u64;
+                                    progress(sent, Some(total));
+                                }
+                                Ok(())
+                            };
+
+                            let mut req = self.chunked(ReceiverStream::new(rx));
+                            if self.checksum {
+                                req.metadata_mut().insert(CHECKSUM_HEADER, checksum);
+                            }
+                            let pushing = self.call_once(req, |mut global, req| async move {
+                                global.push_file(req).await
+                            });
+                            tokio::try_join!(sending, pushing).map(|(_, res)| res)
+                        }
+                    };
 
                     // If global accepts stream, update cache, else fail task
-                    match self
-                        .global
-                        .push_file(Request::new(stream::iter(frames)))
-                        .await
-                    {
-                        Ok(_) => {
-                            self.db.insert(key, value);
-                            Ok(eprintln!("Database updated"))
+                    match res {
+                        Ok(res) => {
+                            self.versions.insert(key.clone(), res.into_inner().version);
+                            self.cache(key, value);
+                            eprintln!("Database updated");
+                            Ok(())
                         }
-                        Err(e) => Err(Box::new(DstoreError(format!(
-                            "Couldn't update Global: {}",
-                            e
-                        )))),
+                        Err(e) => Err(DstoreError::from_status(&key, e)),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Insert a KEY -> VALUE mapping on Global, streaming VALUE from `reader` in frames as it's read rather
+    /// than holding it all in memory. VALUE is cached only if within `max_cached_value`. Should reading fail
+    /// midway, Global discards what was streamed, leaving KEY unmapped.
+    pub async fn insert_from_reader<R: AsyncRead + Unpin>(
+        &mut self,
+        key: Bytes,
+        reader: R,
+    ) -> Result<(), DstoreError> {
+        self.insert_from_reader_with_progress(key, reader, |_, _| {})
+            .await
+    }
+
+    /// Insert a KEY -> VALUE mapping on Global as `insert_from_reader()` does, calling `progress` with the
+    /// bytes sent so far after each frame. The size of VALUE isn't known upfront, so is never reported.
+    pub async fn insert_from_reader_with_progress<R: AsyncRead + Unpin>(
+        &mut self,
+        key: Bytes,
+        mut reader: R,
+        mut progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<(), DstoreError> {
+        // KEY must fit in a single frame, ahead of VALUE frames
+        if key.len() > self.max_message_size {
+            return Err(DstoreError::FrameTooLarge {
+                size: key.len(),
+                limit: self.max_message_size,
+            });
+        }
+        if self.db.contains_key(&key) {
+            return Err(DstoreError::KeyOccupied(key));
+        }
+        let req = Request::new(Byte { body: key.to_vec() });
+        if self
+            .call(
+                req,
+                |mut global, req| async move { global.contains(req).await },
+            )
+            .await
+            .is_ok()
+        {
+            return Err(DstoreError::KeyOccupied(key));
+        }
+
+        let (tx, rx) = mpsc::channel(2);
+        let (limit, chunk_size) = (self.max_cached_value, self.chunk_size);
+        let reading = async move {
+            let _ = tx.send(Byte { body: key.to_vec() }).await;
+            // Keep a copy of VALUE for cache only so long as it's within limit
+            let mut copy = limit.map(|_| vec![]);
+            let mut sent = 0;
+            // Frames are only sent once read, so a failed read leaves the stream without it's terminating frame
+            loop {
+                let mut frame = Vec::with_capacity(chunk_size);
+                (&mut reader)
+                    .take(chunk_size as u64)
+                    .read_to_end(&mut frame)
+                    .await?;
+                let last = frame.is_empty();
+                let len = frame.len() as u64;
+                if let (Some(buf), Some(limit)) = (&mut copy, limit) {
+                    buf.extend_from_slice(&frame);
+                    if buf.len() > limit {
+                        copy = None;
                     }
                 }
+                // Global may have ended the stream early, it's reply says why
+                if tx.send(Byte { body: frame }).await.is_err() || last {
+                    break;
+                }
+                sent += len;
+                progress(sent, None);
+            }
+
+            Ok::<_, DstoreError>((key, copy))
+        };
+        let mut req = self.chunked(ReceiverStream::new(rx));
+        req.metadata_mut()
+            .insert(TERMINATED_HEADER, MetadataValue::from_static("true"));
+        let pushing = async {
+            self.call_once(
+                req,
+                |mut global, req| async move { global.push_file(req).await },
+            )
+            .await
+            .map_err(DstoreError::from)
+        };
+        let ((key, copy), res) = tokio::try_join!(reading, pushing)?;
+
+        self.versions.insert(key.clone(), res.into_inner().version);
+        if let Some(value) = copy {
+            self.cache(key, Bytes::from(value));
+        }
+        eprintln!("Database updated");
+
+        Ok(())
+    }
+
+    /// Insert a KEY -> VALUE mapping on Global, streaming VALUE from the file at `path`, see `insert_from_reader()`
+    pub async fn insert_from_path(
+        &mut self,
+        key: Bytes,
+        path: impl AsRef<Path>,
+    ) -> Result<(), DstoreError> {
+        let file = fs::File::open(path).await?;
+        self.insert_from_reader(key, file).await
+    }
+
+    /// Concatenate `chunk` to the end of the VALUE mapped to KEY on Global, mapping KEY to `chunk` if it
+    /// isn't mapped. Chunks must fit in a single packet, though VALUE may grow beyond one.
+    pub async fn append(&mut self, key: &Bytes, chunk: Bytes) -> Result<(), DstoreError> {
+        if !fits_single_packet(key.len(), chunk.len(), self.max_message_size) {
+            return Err(DstoreError::FrameTooLarge {
+                size: key.len() + chunk.len() + PACKET_OVERHEAD,
+                limit: self.max_message_size,
+            });
+        }
+        let (value, compressed) =
+            compression::encode_with(&chunk, self.compress_above, &self.sampling);
+        let req = Request::new(KeyValue {
+            key: key.to_vec(),
+            value,
+            compressed,
+            ..Default::default()
+        });
+        if let Err(e) = self
+            .call_once(
+                req,
+                |mut global, req| async move { global.append(req).await },
+            )
+            .await
+        {
+            return Err(DstoreError::from_status(key, e));
+        }
+        // Cached copy is stale, as in every other Local that's sent an invalidation
+        self.uncache(key);
+
+        Ok(())
+    }
+
+    /// Apply `ops` on Global atomically and in order, either all take effect or none do, as when the
+    /// source KEY of a remove, copy or rename isn't mapped by the time it's reached
+    pub async fn apply_batch(&mut self, ops: Vec<BatchOp>) -> Result<(), DstoreError> {
+        let touched = BatchOp::keys(&ops);
+        let ops = ops.into_iter().map(Operation::from).collect();
+        self.call_once(Request::new(Batch { ops }), |mut global, req| async move {
+            global.apply_batch(req).await
+        })
+        .await?;
+        // Cached copies are stale, as in every other Local that's sent an invalidation
+        for key in touched {
+            self.uncache(&key);
+        }
+
+        Ok(())
+    }
+
+    /// Apply `ops` on Global atomically and in order as `apply_batch()` does, provided every one of `conditions`
+    /// holds at the time. Returns `false`, having applied none, if any doesn't. Not retried, as a transaction
+    /// applied by an attempt that timed out would have it's conditions fail on the next.
+    pub async fn transaction(
+        &mut self,
+        ops: Vec<BatchOp>,
+        conditions: Vec<Precondition>,
+    ) -> Result<bool, DstoreError> {
+        let touched = BatchOp::keys(&ops);
+        let req = Request::new(ConditionalBatch {
+            ops: ops.into_iter().map(Operation::from).collect(),
+            conditions: conditions.into_iter().map(Condition::from).collect(),
+        });
+        let applied = self
+            .call_once(req, |mut global, req| async move {
+                global.transaction(req).await
+            })
+            .await?
+            .into_inner()
+            .value;
+        if applied {
+            for key in touched {
+                self.uncache(&key);
+            }
+        }
+
+        Ok(applied)
+    }
+
+    /// Insert many single packet sized KEY -> VALUE mappings over a single stream to Global,
+    /// KEYs already mapped on Global are skipped and left uncached. Errs should any other mapping
+    /// be rejected, as for exceeding a quota, leaving those inserted before it uncached.
+    pub async fn insert_batch(
+        &mut self,
+        pairs: Vec<(Bytes, Bytes)>,
+    ) -> Result<BatchSummary, DstoreError> {
+        let mut values = HashMap::new();
+        let mut frames = vec![];
+        for (key, value) in pairs {
+            if !fits_single_packet(key.len(), value.len(), self.max_message_size) {
+                return Err(DstoreError::FrameTooLarge {
+                    size: key.len() + value.len() + PACKET_OVERHEAD,
+                    limit: self.max_message_size,
+                });
             }
+            let (value_bytes, compressed) =
+                compression::encode_with(&value, self.compress_above, &self.sampling);
+            frames.push(KeyValue {
+                key: key.to_vec(),
+                value: value_bytes,
+                compressed,
+                ..Default::default()
+            });
+            values.insert(key, value);
         }
+
+        let BatchResult {
+            accepted,
+            present,
+            versions,
+        } = self
+            .call_once(
+                Request::new(stream::iter(frames)),
+                |mut global, req| async move { global.push_batch(req).await },
+            )
+            .await?
+            .into_inner();
+        // Cache only those mappings Global accepted
+        let summary = BatchSummary {
+            accepted: accepted.len(),
+            present: present as usize,
+        };
+        for (key, version) in accepted.into_iter().zip(versions) {
+            if let Some((key, value)) = values.remove_entry(&key[..]) {
+                self.versions.insert(key.clone(), version);
+                self.cache(key, value);
+            }
+        }
+
+        Ok(summary)
     }
 
     /// Get VALUE associated with KEY from system
-    pub async fn get(&mut self, key: &Bytes) -> Result<Bytes, Box<dyn Error>> {
+    pub async fn get(&mut self, key: &Bytes) -> Result<Bytes, DstoreError> {
         // Check cache for KEY, if it exists, return associated VALUE
-        match self.db.get(key) {
-            Some(value) => Ok(value.clone()),
+        match self.lookup_counted(key).await {
+            Some(value) => {
+                trace_event!(
+                    key_len = key.len(),
+                    size = value.len(),
+                    cached = true,
+                    "get"
+                );
+                Ok(value)
+            }
             None => {
-                // If KEY in Global, extract VALUE byte size
-                let size = match self
-                    .global
-                    .contains(Request::new(Byte { body: key.to_vec() }))
-                    .await
-                {
-                    Ok(res) => res.into_inner().size,
-                    Err(e) => return Err(Box::new(DstoreError(format!("Global: {}", e)))),
-                } as usize;
-                // If VALUE sized larger than single packet transportable, use get_file(), else use get_single()
-                if size < MAX_BYTE_SIZE {
-                    self.get_single(key).await
-                } else {
-                    self.get_file(key).await
-                }
+                // Mappings larger than a single packet can transport are streamed instead
+                let res = self.fetch_single(key).await;
+                trace_event!(
+                    key_len = key.len(),
+                    size = res.as_ref().map_or(0, |value| value.len()),
+                    cached = false,
+                    streaming = res.as_ref().is_ok_and(|value| !fits_single_packet(
+                        key.len(),
+                        value.len(),
+                        self.max_message_size
+                    )),
+                    ok = res.is_ok(),
+                    "get"
+                );
+                res
+            }
+        }
+    }
+
+    /// Insert a KEY -> VALUE mapping of strings, as with `insert()`
+    pub async fn insert_str(&mut self, key: &str, value: &str) -> Result<(), DstoreError> {
+        self.insert(
+            Bytes::copy_from_slice(key.as_bytes()),
+            Bytes::copy_from_slice(value.as_bytes()),
+        )
+        .await
+    }
+
+    /// Get VALUE associated with KEY as a string, as with `try_get()`. Errs if VALUE isn't valid UTF-8.
+    pub async fn get_str(&mut self, key: &str) -> Result<Option<String>, DstoreError> {
+        match self
+            .try_get(&Bytes::copy_from_slice(key.as_bytes()))
+            .await?
+        {
+            Some(value) => Ok(Some(String::from_utf8(value.to_vec())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get VALUE associated with KEY as with `get()`, `None` if KEY isn't mapped either in cache or
+    /// on Global. Errs only on failures such as Global being unreachable.
+    pub async fn try_get(&mut self, key: &Bytes) -> Result<Option<Bytes>, DstoreError> {
+        match self.get(key).await {
+            Ok(value) => Ok(Some(value)),
+            Err(DstoreError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get VALUE associated with KEY at the chosen consistency level
+    pub async fn get_with_consistency(
+        &mut self,
+        key: &Bytes,
+        level: Consistency,
+    ) -> Result<Bytes, DstoreError> {
+        if level == Consistency::Strong {
+            // Drop cached copy so that VALUE is fetched afresh from Global
+            self.uncache(key);
+        }
+
+        self.get(key).await
+    }
+
+    /// Get the current VALUE of KEY from Global regardless of cache, refreshing the cached copy. For reads
+    /// that must see writes made by other Locals, shorthand for `get_with_consistency()` at `Consistency::Strong`.
+    pub async fn get_consistent(&mut self, key: &Bytes) -> Result<Bytes, DstoreError> {
+        self.get_with_consistency(key, Consistency::Strong).await
+    }
+
+    /// Get everything Global knows about KEY, including which Locals may hold it in cache
+    pub async fn inspect(&mut self, key: &Bytes) -> Result<KeyInfo, DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        let info = match self
+            .call(
+                req,
+                |mut global, req| async move { global.inspect(req).await },
+            )
+            .await
+        {
+            Ok(res) => res.into_inner(),
+            Err(e) => return Err(DstoreError::from_status(key, e)),
+        };
+
+        Ok(KeyInfo {
+            size: info.size as usize,
+            version: info.version,
+            ttl: match info.ttl_millis {
+                0 => None,
+                millis => Some(Duration::from_millis(millis)),
+            },
+            owner: Bytes::from(info.owner),
+            modified: UNIX_EPOCH + Duration::from_millis(info.modified_millis),
+            holders: info.holders.into_iter().map(Bytes::from).collect(),
+            tags: info.tags.into_iter().map(Bytes::from).collect(),
+        })
+    }
+
+    /// List every KEY mapped on Global
+    pub async fn keys(&mut self) -> Result<Vec<Bytes>, DstoreError> {
+        let mut stream = self
+            .call(Request::new(Null {}), |mut global, req| async move {
+                global.list_keys(req).await
+            })
+            .await?
+            .into_inner();
+        let mut keys = vec![];
+        while let Some(frame) = stream.next().await {
+            keys.push(Bytes::from(frame?.body));
+        }
+
+        Ok(keys)
+    }
+
+    /// Get every mapping on Global whose KEY starts with `prefix`, in order of KEY, caching each of them.
+    /// Global visits each of it's mappings to find these, so the cost grows with all KEYs, not just matches.
+    pub async fn scan_prefix(
+        &mut self,
+        prefix: &Bytes,
+    ) -> Result<Vec<(Bytes, Bytes)>, DstoreError> {
+        let mut req = Request::new(Byte {
+            body: prefix.to_vec(),
+        });
+        if let Some(threshold) = self.compress_above {
+            req.metadata_mut().insert(
+                COMPRESSION_HEADER,
+                MetadataValue::from_str(&threshold.to_string()).unwrap(),
+            );
+        }
+        let mut stream = self
+            .call(req, |mut global, req| async move {
+                global.scan_prefix(req).await
+            })
+            .await?
+            .into_inner();
+        let mut pairs = vec![];
+        while let Some(pair) = stream.next().await {
+            let KeyValue {
+                key,
+                value,
+                compressed,
+                sliding,
+                version,
+            } = pair?;
+            let (key, value) = (
+                Bytes::from(key),
+                Bytes::from(compression::decode(value, compressed)?),
+            );
+            if !sliding {
+                self.versions.insert(key.clone(), version);
+                self.cache(key.clone(), value.clone());
+            }
+            pairs.push((key, value));
+        }
+
+        Ok(pairs)
+    }
+
+    /// Stream of notifications, each time KEY is written, overwritten or removed on Global by any Local.
+    /// Notifications are pushed as changes happen, rather than awaiting the next poll by `update()`.
+    pub async fn watch(
+        &mut self,
+        key: &Bytes,
+    ) -> Result<impl Stream<Item = Result<Bytes, DstoreError>>, DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        let stream = self
+            .call(
+                req,
+                |mut global, req| async move { global.watch(req).await },
+            )
+            .await?
+            .into_inner();
+
+        // Global notifies of every KEY starting with the one watched, keep only those that match
+        let key = key.clone();
+        Ok(tokio_stream::StreamExt::filter_map(
+            stream,
+            move |changed| match changed {
+                Ok(Byte { body }) if body == key => Some(Ok(key.clone())),
+                Ok(_) => None,
+                Err(e) => Some(Err(DstoreError::from(e))),
+            },
+        ))
+    }
+
+    /// Stream every mutation made on Global from sequence number `from` onwards, in order, followed by
+    /// each new mutation as it's made. Consumers resume by passing one more than the last sequence seen,
+    /// or 0 to start from the oldest retained. Errs if the mutations asked for are no longer retained.
+    pub async fn changes(
+        &mut self,
+        from: u64,
+    ) -> Result<impl Stream<Item = Result<Mutation, DstoreError>>, DstoreError> {
+        let req = Request::new(Offset { offset: from });
+        let stream = self
+            .call(
+                req,
+                |mut global, req| async move { global.changes(req).await },
+            )
+            .await?
+            .into_inner();
+
+        Ok(stream.map(|change| Ok(Mutation::from(change?))))
+    }
+
+    /// Replicate every mapping on Global whose KEY starts with `prefix`, first as a snapshot and
+    /// then kept up to date as mappings are written or removed, with each such change reported
+    pub async fn watch_prefix(&mut self, prefix: &[u8]) -> Result<Watch, DstoreError> {
+        let req = Request::new(Byte {
+            body: prefix.to_vec(),
+        });
+        let stream = self
+            .call(req, |mut global, req| async move {
+                global.watch_prefix(req).await
+            })
+            .await?
+            .into_inner();
+
+        Watch::start(stream).await
+    }
+
+    /// Bloom filter of KEYs mapped on Global, to rule out absent KEYs before requesting them.
+    /// Filter is rebuilt every few seconds, so KEYs mapped since it was built may test negative.
+    pub async fn bloom_filter(&mut self) -> Result<BloomFilter, DstoreError> {
+        let Filter { bits, hashes } = self
+            .call(Request::new(Null {}), |mut global, req| async move {
+                global.bloom_filter(req).await
+            })
+            .await?
+            .into_inner();
+
+        BloomFilter::from_bytes(&bits, hashes)
+            .ok_or_else(|| DstoreError::Connection("Malformed bloom filter".to_string()))
+    }
+
+    /// Upto `n` KEYs most accessed on Global, most accessed first. Reads served from cache aren't counted.
+    pub async fn hot_keys(&mut self, n: usize) -> Result<Vec<KeyAccess>, DstoreError> {
+        let req = Request::new(Size {
+            size: n.min(i32::MAX as usize) as i32,
+        });
+        let AccessList { keys } = self
+            .call(
+                req,
+                |mut global, req| async move { global.hot_keys(req).await },
+            )
+            .await?
+            .into_inner();
+
+        Ok(keys.into_iter().map(KeyAccess::from).collect())
+    }
+
+    /// Append an event onto log named KEY on Global, returning the event's offset within log
+    pub async fn log_append(&mut self, key: Bytes, event: Bytes) -> Result<u64, DstoreError> {
+        let req = Request::new(KeyValue {
+            key: key.to_vec(),
+            value: event.to_vec(),
+            ..Default::default()
+        });
+        match self
+            .call_once(req, |mut global, req| async move {
+                global.log_append(req).await
+            })
+            .await
+        {
+            Ok(res) => Ok(res.into_inner().offset),
+            Err(e) => Err(DstoreError::from_status(&key, e)),
+        }
+    }
+
+    /// Read upto `max` events from log named KEY, starting at `from_offset`, paired with their offsets
+    pub async fn log_read(
+        &mut self,
+        key: &Bytes,
+        from_offset: u64,
+        max: u64,
+    ) -> Result<Vec<(u64, Bytes)>, DstoreError> {
+        let req = Request::new(LogRange {
+            key: key.to_vec(),
+            from_offset,
+            max,
+        });
+        match self
+            .call(
+                req,
+                |mut global, req| async move { global.log_read(req).await },
+            )
+            .await
+        {
+            Ok(res) => {
+                let Events {
+                    from_offset,
+                    events,
+                } = res.into_inner();
+                Ok((from_offset..)
+                    .zip(events.into_iter().map(Bytes::from))
+                    .collect())
+            }
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Atomically add `delta` to FIELD of hash named KEY on Global, returning the resulting value.
+    /// Absent hashes and fields are initialized to `delta`.
+    pub async fn hincrby(
+        &mut self,
+        key: &Bytes,
+        field: &Bytes,
+        delta: i64,
+    ) -> Result<i64, DstoreError> {
+        let req = Request::new(FieldDelta {
+            key: key.to_vec(),
+            field: field.to_vec(),
+            delta,
+        });
+        match self
+            .call_once(
+                req,
+                |mut global, req| async move { global.h_incr_by(req).await },
+            )
+            .await
+        {
+            Ok(res) => {
+                // Cached copy is stale, as in every other Local that's sent an invalidation
+                self.uncache(key);
+                Ok(res.into_inner().value)
             }
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Get FIELD of hash mapped to KEY from Global, as adjusted with `hincrby()`
+    pub async fn hget(&mut self, key: &Bytes, field: &Bytes) -> Result<i64, DstoreError> {
+        let req = Request::new(KeyField {
+            key: key.to_vec(),
+            field: field.to_vec(),
+        });
+        match self
+            .call(
+                req,
+                |mut global, req| async move { global.h_get(req).await },
+            )
+            .await
+        {
+            Ok(res) => Ok(res.into_inner().value),
+            Err(e) => Err(DstoreError::from_status(key, e)),
         }
     }
 
+    /// Atomically add `delta` to the counter mapped to KEY on Global, returning the result. Counters
+    /// are VALUEs of 8 byte big-endian integers, an unmapped KEY counts from 0.
+    pub async fn increment(&mut self, key: &Bytes, delta: i64) -> Result<i64, DstoreError> {
+        let req = Request::new(KeyValue {
+            key: key.to_vec(),
+            value: delta.to_be_bytes().to_vec(),
+            ..Default::default()
+        });
+        let value = match self
+            .call_once(
+                req,
+                |mut global, req| async move { global.increment(req).await },
+            )
+            .await
+        {
+            Ok(res) => res.into_inner().value,
+            Err(e) => return Err(DstoreError::from_status(key, e)),
+        };
+        // Cached copy is stale, as in every other Local that's sent an invalidation
+        self.uncache(key);
+
+        Ok(value)
+    }
+
     /// Get VALUES that can fit in a single packet
-    pub async fn get_single(&mut self, key: &Bytes) -> Result<Bytes, Box<dyn Error>> {
+    pub async fn get_single(&mut self, key: &Bytes) -> Result<Bytes, DstoreError> {
         // Check if KEY is present in cache, else consult Global
-        match self.db.get(key) {
-            Some(value) => Ok(value.clone()),
-            None => {
-                // Send pull request to Global, update cache if successful
-                let req = Request::new(Byte { body: key.to_vec() });
-                match self.global.pull(req).await {
-                    Ok(res) => {
-                        let res = res.into_inner();
-                        eprintln!("Updating Local");
-                        self.db.insert(key.clone(), Bytes::from(res.body.clone()));
-                        Ok(Bytes::from(res.body))
-                    }
-                    Err(_) => Err(Box::new(DstoreError(
-                        "Key-Value mapping doesn't exist".to_string(),
-                    ))),
+        match self.lookup_counted(key).await {
+            Some(value) => Ok(value),
+            None => self.fetch_single(key).await,
+        }
+    }
+
+    /// Pull VALUE from Global in a single packet and cache it. A VALUE too large for a single packet is
+    /// streamed instead, as Global turns such a pull away rather than sending VALUE whole.
+    async fn fetch_single(&mut self, key: &Bytes) -> Result<Bytes, DstoreError> {
+        // Send pull request to Global, asking for VALUE to be compressed if large enough
+        let mut req = Request::new(Byte { body: key.to_vec() });
+        if let Some(threshold) = self.compress_above {
+            req.metadata_mut().insert(
+                COMPRESSION_HEADER,
+                MetadataValue::from_str(&threshold.to_string()).unwrap(),
+            );
+        }
+        let max_value = self
+            .max_message_size
+            .saturating_sub(key.len() + PACKET_OVERHEAD);
+        req.metadata_mut()
+            .insert(MAX_VALUE_HEADER, MetadataValue::from(max_value as u64));
+        // Update cache if successful
+        let res = self
+            .call(req, |mut global, req| async move { global.pull(req).await })
+            .await;
+        if matches!(&res, Err(e) if e.code() == Code::OutOfRange && e.metadata().contains_key(SIZE_HEADER))
+        {
+            return self.fetch_file(key, &mut |_, _| {}).await;
+        }
+        self.remote_fetches.fetch_add(1, Ordering::Relaxed);
+        match res {
+            Ok(res) => {
+                let Value {
+                    body,
+                    compressed,
+                    version,
+                    sliding,
+                } = res.into_inner();
+                let value = Bytes::from(compression::decode(body, compressed)?);
+                // Mappings with a sliding TTL aren't cached, so that every read reaches Global to extend it
+                if !sliding {
+                    eprintln!("Updating Local");
+                    self.versions.insert(key.clone(), version);
+                    self.cache(key.clone(), value.clone());
+                }
+                Ok(value)
+            }
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Get upto `length` bytes of VALUE associated with KEY, starting at `offset`. Served from cache if VALUE is
+    /// cached whole, else only the range is fetched from Global and nothing is cached. Ranges running past the
+    /// end of VALUE are cut short, those starting past it err with `OutOfRange`.
+    pub async fn get_range(
+        &mut self,
+        key: &Bytes,
+        offset: u64,
+        length: u64,
+    ) -> Result<Bytes, DstoreError> {
+        if let Some(value) = self.lookup_counted(key).await {
+            let len = value.len() as u64;
+            if offset > len {
+                return Err(DstoreError::OutOfRange(key.clone()));
+            }
+            let end = offset.saturating_add(length).min(len);
+            return Ok(value.slice(offset as usize..end as usize));
+        }
+
+        self.remote_fetches.fetch_add(1, Ordering::Relaxed);
+        let req = Request::new(ByteRange {
+            key: key.to_vec(),
+            offset,
+            length,
+        });
+        match self
+            .call(req, |mut global, req| async move {
+                global.pull_range(req).await
+            })
+            .await
+        {
+            Ok(res) => Ok(Bytes::from(res.into_inner().body)),
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Get VALUEs associated with many KEYs, those not in cache are requested from Global in a single call.
+    /// KEYs that aren't mapped are left out of the result.
+    pub async fn get_many(&mut self, keys: &[Bytes]) -> Result<HashMap<Bytes, Bytes>, DstoreError> {
+        let mut values = HashMap::new();
+        let mut misses = vec![];
+        for key in keys {
+            match self.lookup(key).await {
+                Some(value) => {
+                    values.insert(key.clone(), value);
                 }
+                None => misses.push(key.to_vec()),
+            }
+        }
+        if misses.is_empty() {
+            return Ok(values);
+        }
+
+        // Request cache misses from Global, asking for VALUEs to be compressed if large enough
+        let mut req = Request::new(Keys { keys: misses });
+        if let Some(threshold) = self.compress_above {
+            req.metadata_mut().insert(
+                COMPRESSION_HEADER,
+                MetadataValue::from_str(&threshold.to_string()).unwrap(),
+            );
+        }
+        let mut stream = self
+            .call(req, |mut global, req| async move {
+                global.pull_batch(req).await
+            })
+            .await?
+            .into_inner();
+        eprintln!("Updating Local");
+        while let Some(pair) = stream.next().await {
+            let KeyValue {
+                key,
+                value,
+                compressed,
+                sliding,
+                version,
+            } = pair?;
+            let (key, value) = (
+                Bytes::from(key),
+                Bytes::from(compression::decode(value, compressed)?),
+            );
+            if !sliding {
+                self.versions.insert(key.clone(), version);
+                self.cache(key.clone(), value.clone());
+            }
+            values.insert(key, value);
+        }
+
+        Ok(values)
+    }
+
+    /// Get VALUEs associated with many KEYs, calling `on_result` with each KEY and it's VALUE, or `None` if
+    /// KEY isn't mapped. Cached KEYs are reported first, the rest as soon as Global retrieves them,
+    /// caching each on arrival.
+    pub async fn get_each(
+        &mut self,
+        keys: &[Bytes],
+        mut on_result: impl FnMut(&Bytes, Option<&Bytes>),
+    ) -> Result<(), DstoreError> {
+        let mut misses = vec![];
+        for key in keys {
+            match self.lookup(key).await {
+                Some(value) => on_result(key, Some(&value)),
+                None => misses.push(key.to_vec()),
+            }
+        }
+        if misses.is_empty() {
+            return Ok(());
+        }
+
+        // Request cache misses from Global, asking for VALUEs to be compressed if large enough
+        let mut req = Request::new(Keys { keys: misses });
+        if let Some(threshold) = self.compress_above {
+            req.metadata_mut().insert(
+                COMPRESSION_HEADER,
+                MetadataValue::from_str(&threshold.to_string()).unwrap(),
+            );
+        }
+        let mut stream = self
+            .call(req, |mut global, req| async move {
+                global.multi_pull_stream(req).await
+            })
+            .await?
+            .into_inner();
+        while let Some(lookup) = stream.next().await {
+            let Lookup {
+                key,
+                value,
+                compressed,
+                found,
+                sliding,
+                version,
+            } = lookup?;
+            let key = Bytes::from(key);
+            if !found {
+                on_result(&key, None);
+                continue;
             }
+            let value = Bytes::from(compression::decode(value, compressed)?);
+            if !sliding {
+                self.versions.insert(key.clone(), version);
+                self.cache(key.clone(), value.clone());
+            }
+            on_result(&key, Some(&value));
         }
+
+        Ok(())
     }
 
     /// Get VALUES that don't fit in a single packet
-    pub async fn get_file(&mut self, key: &Bytes) -> Result<Bytes, Box<dyn Error>> {
+    pub async fn get_file(&mut self, key: &Bytes) -> Result<Bytes, DstoreError> {
+        self.get_file_with_progress(key, |_, _| {}).await
+    }
+
+    /// Get VALUES that don't fit in a single packet as `get_file()` does, calling `progress` with the bytes
+    /// recieved so far and the size of VALUE after each frame streamed from Global. Called just once if cached.
+    pub async fn get_file_with_progress(
+        &mut self,
+        key: &Bytes,
+        mut progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<Bytes, DstoreError> {
         // Check if KEY is present in cache, else consult Global
-        match self.db.get(key) {
-            Some(value) => Ok(value.clone()),
-            None => {
-                // Send pull_file request to Global, update cache with streamed response
-                let req = Request::new(Byte { body: key.to_vec() });
-                let mut stream = self.global.pull_file(req).await.unwrap().into_inner();
-                eprintln!("Updating Local");
-                let mut value = vec![];
-                while let Some(frame) = stream.next().await {
-                    let mut frame = frame?;
-                    value.append(&mut frame.body);
+        match self.lookup_counted(key).await {
+            Some(value) => {
+                let len = value.len() as u64;
+                progress(len, Some(len));
+                Ok(value)
+            }
+            None => self.fetch_file(key, &mut progress).await,
+        }
+    }
+
+    /// Request for a streamed transfer, declaring the size of VALUE frames in it
+    fn chunked<T>(&self, message: T) -> Request<T> {
+        let mut req = Request::new(message);
+        req.metadata_mut().insert(
+            CHUNK_SIZE_HEADER,
+            MetadataValue::from(self.chunk_size as u64),
+        );
+        req
+    }
+
+    /// Request VALUE mapped to KEY be streamed from Global, along with a verifier of it's checksum if enabled,
+    /// the size of VALUE if declared and the version it's at
+    async fn pull(&self, key: &Bytes) -> Result<Pulled, DstoreError> {
+        let mut req = self.chunked(Byte { body: key.to_vec() });
+        if self.checksum {
+            req.metadata_mut().insert(
+                CHECKSUM_HEADER,
+                MetadataValue::from_static(checksum::REQUESTED),
+            );
+        }
+        match self
+            .call(
+                req,
+                |mut global, req| async move { global.pull_file(req).await },
+            )
+            .await
+        {
+            Ok(res) => {
+                let expected = checksum::declared(res.metadata()).filter(|_| self.checksum);
+                let size = res
+                    .metadata()
+                    .get(SIZE_HEADER)
+                    .and_then(|size| size.to_str().ok()?.parse().ok());
+                let version = res
+                    .metadata()
+                    .get(VERSION_HEADER)
+                    .and_then(|version| version.to_str().ok()?.parse().ok())
+                    .unwrap_or_default();
+                let sliding = res.metadata().contains_key(SLIDING_HEADER);
+                Ok(Pulled {
+                    stream: res.into_inner(),
+                    verifier: Verifier::new(expected),
+                    size,
+                    version,
+                    sliding,
+                })
+            }
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Pull VALUE streamed from Global in frames and cache it, reporting `progress` after each frame
+    async fn fetch_file(
+        &mut self,
+        key: &Bytes,
+        progress: &mut (dyn FnMut(u64, Option<u64>) + Send),
+    ) -> Result<Bytes, DstoreError> {
+        self.remote_fetches.fetch_add(1, Ordering::Relaxed);
+        // Send pull_file request to Global, update cache with streamed response
+        let Pulled {
+            mut stream,
+            mut verifier,
+            size,
+            version,
+            sliding,
+        } = self.pull(key).await?;
+        eprintln!("Updating Local");
+        let mut value = vec![];
+        while let Some(frame) = stream.next().await {
+            let mut frame = frame.map_err(|e| DstoreError::from_status(key, e))?;
+            verifier.update(&frame.body);
+            value.append(&mut frame.body);
+            progress(value.len() as u64, size);
+        }
+        verifier
+            .verify()
+            .map_err(|e| DstoreError::from_status(key, e))?;
+        // Mappings with a sliding TTL aren't cached, so that every read reaches Global to extend it
+        if !sliding {
+            self.versions.insert(key.clone(), version);
+            self.cache(key.clone(), Bytes::from(value.clone()));
+        }
+        Ok(Bytes::from(value))
+    }
+
+    /// Write VALUE mapped to KEY into `writer`, each frame as it's streamed from Global rather than after
+    /// reassembling VALUE in memory. VALUE is cached only if `cache` is set and it's within `max_cached_value`,
+    /// returns the number of bytes written.
+    pub async fn get_to_writer<W: AsyncWrite + Unpin>(
+        &mut self,
+        key: &Bytes,
+        writer: W,
+        cache: bool,
+    ) -> Result<u64, DstoreError> {
+        self.get_to_writer_with_progress(key, writer, cache, |_, _| {})
+            .await
+    }
+
+    /// Write VALUE mapped to KEY into `writer` as `get_to_writer()` does, calling `progress` with the bytes
+    /// written so far and the size of VALUE after each frame. Called just once if cached.
+    pub async fn get_to_writer_with_progress<W: AsyncWrite + Unpin>(
+        &mut self,
+        key: &Bytes,
+        mut writer: W,
+        cache: bool,
+        mut progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<u64, DstoreError> {
+        if let Some(value) = self.lookup_counted(key).await {
+            writer.write_all(&value).await?;
+            writer.flush().await?;
+            let len = value.len() as u64;
+            progress(len, Some(len));
+            return Ok(len);
+        }
+
+        self.remote_fetches.fetch_add(1, Ordering::Relaxed);
+        let Pulled {
+            mut stream,
+            mut verifier,
+            size,
+            version,
+            sliding,
+        } = self.pull(key).await?;
+        // Keep a copy of VALUE for cache only so long as it's within limit
+        let mut copy = if cache { Some(vec![]) } else { None };
+        let mut written = 0;
+        while let Some(frame) = stream.next().await {
+            let frame = frame.map_err(|e| DstoreError::from_status(key, e))?;
+            verifier.update(&frame.body);
+            writer.write_all(&frame.body).await?;
+            written += frame.body.len() as u64;
+            progress(written, size);
+            if let Some(buf) = &mut copy {
+                buf.extend_from_slice(&frame.body);
+                if self.max_cached_value.is_some_and(|limit| buf.len() > limit) {
+                    copy = None;
                 }
-                self.db.insert(key.clone(), Bytes::from(value.clone()));
-                Ok(Bytes::from(value))
             }
         }
+        writer.flush().await?;
+        verifier
+            .verify()
+            .map_err(|e| DstoreError::from_status(key, e))?;
+        if let Some(value) = copy.filter(|_| !sliding) {
+            self.versions.insert(key.clone(), version);
+            self.cache(key.clone(), Bytes::from(value));
+        }
+
+        Ok(written)
+    }
+
+    /// Write VALUE mapped to KEY into the file at `path`, creating or truncating it, see `get_to_writer()`
+    pub async fn get_to_path(
+        &mut self,
+        key: &Bytes,
+        path: impl AsRef<Path>,
+        cache: bool,
+    ) -> Result<u64, DstoreError> {
+        let file = fs::File::create(path).await?;
+        self.get_to_writer(key, file, cache).await
     }
 
     /// Remove a KEY from the system
-    pub async fn remove(&mut self, key: &Bytes) -> Result<(), Box<dyn Error>> {
-        // Store erroring locations to write error message
-        let mut err = vec![];
+    pub async fn remove(&mut self, key: &Bytes) -> Result<(), DstoreError> {
         // Send remove request to Global
         let req = Request::new(Byte { body: key.to_vec() });
-        match self.global.remove(req).await {
-            Ok(_) => eprintln!("Global mapping removed!"),
-            Err(_) => err.push("global"),
+        if let Err(e) = self
+            .call_once(
+                req,
+                |mut global, req| async move { global.remove(req).await },
+            )
+            .await
+        {
+            trace_event!(key_len = key.len(), ok = false, "remove");
+            return Err(DstoreError::from_status(key, e));
         }
+        eprintln!("Global mapping removed!");
+        trace_event!(key_len = key.len(), ok = true, "remove");
+
+        // Evict cached copy right away, other Locals drop theirs on next update
+        self.uncache(key);
+
+        Ok(())
+    }
 
-        // Check if mapping exists locally, let `update()` remove KEY if it does
-        match self.db.contains_key(key) {
-            true => eprintln!("Local mapping will soon be removed!"),
-            false => err.push("local"),
+    /// Remove every KEY starting with `prefix` from the system, returning number of KEYs removed
+    pub async fn remove_prefix(&mut self, prefix: &Bytes) -> Result<usize, DstoreError> {
+        let req = Request::new(Byte {
+            body: prefix.to_vec(),
+        });
+        let Size { size } = self
+            .call_once(req, |mut global, req| async move {
+                global.remove_prefix(req).await
+            })
+            .await?
+            .into_inner();
+
+        // Evict cached copies right away, other Locals drop theirs on next update
+        let cached: Vec<Bytes> = self
+            .db
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in cached {
+            self.uncache(&key);
         }
 
-        // Return error message based on contents of err
-        match err.len() {
-            0 => Ok(()),
-            _ => Err(Box::new(DstoreError(format!(
-                "Key missing from {}!",
-                err.join(" and ")
-            )))),
+        Ok(size as usize)
+    }
+}
+
+impl Drop for Local {
+    /// Best-effort notify Global that Local is leaving cluster, if still within a runtime
+    fn drop(&mut self) {
+        if let (true, Ok(handle)) = (self.joined, Handle::try_current()) {
+            let mut global = self.global.clone();
+            let body = self.addr.as_bytes().to_vec();
+            handle.spawn(async move { global.leave(Request::new(Byte { body })).await });
         }
     }
 }
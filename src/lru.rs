@@ -0,0 +1,102 @@
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+
+/// Order in which KEYs were last used, with constant time updates. Each use of a KEY stamps it with a
+/// fresh generation and queues it at the back, leaving any earlier queued stamp of it stale. Stale stamps
+/// are skipped on reaching the front, and dropped all at once should they come to outnumber live ones.
+#[derive(Default)]
+pub(crate) struct Lru {
+    /// Generation each KEY was last used in
+    generations: HashMap<Bytes, u64>,
+    /// KEYs stamped with the generation they were used in, least recent at the front
+    order: VecDeque<(u64, Bytes)>,
+    /// Generation of the next use
+    next: u64,
+}
+
+impl Lru {
+    /// Mark KEY as most recently used, adding it if not already present
+    pub(crate) fn insert(&mut self, key: Bytes) {
+        let generation = self.next;
+        self.next += 1;
+        self.generations.insert(key.clone(), generation);
+        self.order.push_back((generation, key));
+        if self.order.len() > 2 * self.generations.len().max(16) {
+            self.compact();
+        }
+    }
+
+    /// Mark KEY as most recently used, returns `false` if not present
+    pub(crate) fn touch(&mut self, key: &[u8]) -> bool {
+        match self.generations.get_key_value(key) {
+            Some((key, _)) => {
+                let key = key.clone();
+                self.insert(key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop KEY, returns `false` if not present
+    pub(crate) fn remove(&mut self, key: &[u8]) -> bool {
+        self.generations.remove(key).is_some()
+    }
+
+    /// Least recently used KEY
+    pub(crate) fn oldest(&mut self) -> Option<Bytes> {
+        while let Some((generation, key)) = self.order.front() {
+            if self.generations.get(key) == Some(generation) {
+                return Some(key.clone());
+            }
+            self.order.pop_front();
+        }
+
+        None
+    }
+
+    /// Number of KEYs present
+    pub(crate) fn len(&self) -> usize {
+        self.generations.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.generations.clear();
+        self.order.clear();
+    }
+
+    /// Drop every stale stamp from queue
+    fn compact(&mut self) {
+        let generations = &self.generations;
+        self.order
+            .retain(|(generation, key)| generations.get(key) == Some(generation));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_comes_first() {
+        let mut lru = Lru::default();
+        for key in ["a", "b", "c"] {
+            lru.insert(Bytes::from(key));
+        }
+        assert!(lru.touch(b"a"));
+        assert!(!lru.touch(b"missing"));
+        assert_eq!(lru.oldest(), Some(Bytes::from("b")));
+        assert!(lru.remove(b"b"));
+        assert_eq!(lru.oldest(), Some(Bytes::from("c")));
+        assert!(lru.remove(b"c"));
+        assert_eq!(lru.oldest(), Some(Bytes::from("a")));
+
+        // Stale stamps of a KEY used over and over don't pile up
+        for _ in 0..1000 {
+            lru.touch(b"a");
+        }
+        assert!(lru.order.len() <= 32);
+        assert!(lru.remove(b"a"));
+        assert_eq!(lru.oldest(), None);
+    }
+}
@@ -0,0 +1,147 @@
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Response, Server, StatusCode,
+};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::{convert::Infallible, future::Future, net::SocketAddr, sync::Arc};
+
+/// Buckets of VALUE sizes in bytes, from a KB to beyond a single packet
+const SIZE_BUCKETS: &[f64] = &[
+    1024.0,
+    16_384.0,
+    262_144.0,
+    1_048_576.0,
+    4_194_304.0,
+    16_777_216.0,
+];
+
+/// Counters and gauges of Global's activity, exported in the Prometheus text format
+pub(crate) struct Metrics {
+    registry: Registry,
+    /// Requests served, by RPC
+    requests: IntCounterVec,
+    /// Bytes of KEYs and VALUEs transferred, `in` as pushed to Global or `out` as pulled from it
+    bytes: IntCounterVec,
+    /// Sizes of VALUEs pushed onto Global
+    value_sizes: Histogram,
+    /// Mappings held by Global, as of the last scrape
+    pub(crate) keys: IntGauge,
+    /// Locals in cluster, as of the last scrape
+    pub(crate) nodes: IntGauge,
+    /// Items awaiting delivery, by `invalidations` queued for Locals or `tasks` on task queues
+    pub(crate) queued: IntGaugeVec,
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Self {
+        let requests = IntCounterVec::new(
+            Opts::new("dstore_requests_total", "Requests served, by RPC"),
+            &["rpc"],
+        )
+        .unwrap();
+        let bytes = IntCounterVec::new(
+            Opts::new("dstore_bytes_total", "Bytes of KEYs and VALUEs transferred"),
+            &["direction"],
+        )
+        .unwrap();
+        let value_sizes = Histogram::with_opts(
+            HistogramOpts::new("dstore_value_size_bytes", "Sizes of VALUEs pushed")
+                .buckets(SIZE_BUCKETS.to_vec()),
+        )
+        .unwrap();
+        let keys = IntGauge::new("dstore_keys", "Mappings held").unwrap();
+        let nodes = IntGauge::new("dstore_nodes", "Locals in cluster").unwrap();
+        let queued = IntGaugeVec::new(
+            Opts::new("dstore_queued", "Items awaiting delivery, by queue kind"),
+            &["kind"],
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry.register(Box::new(bytes.clone())).unwrap();
+        registry.register(Box::new(value_sizes.clone())).unwrap();
+        registry.register(Box::new(keys.clone())).unwrap();
+        registry.register(Box::new(nodes.clone())).unwrap();
+        registry.register(Box::new(queued.clone())).unwrap();
+
+        Self {
+            registry,
+            requests,
+            bytes,
+            value_sizes,
+            keys,
+            nodes,
+            queued,
+        }
+    }
+
+    /// Count a request served by RPC `rpc`
+    pub(crate) fn request(&self, rpc: &str) {
+        self.requests.with_label_values(&[rpc]).inc();
+    }
+
+    /// Count a mapping of `key_len` and `value_len` bytes pushed onto Global
+    pub(crate) fn pushed(&self, key_len: usize, value_len: usize) {
+        self.bytes
+            .with_label_values(&["in"])
+            .inc_by((key_len + value_len) as u64);
+        self.value_sizes.observe(value_len as f64);
+    }
+
+    /// Count a mapping of `key_len` and `value_len` bytes pulled from Global
+    pub(crate) fn pulled(&self, key_len: usize, value_len: usize) {
+        self.bytes
+            .with_label_values(&["out"])
+            .inc_by((key_len + value_len) as u64);
+    }
+
+    /// Render every metric in the Prometheus text format
+    fn render(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .unwrap();
+        buf
+    }
+}
+
+/// Bind to `addr`, returning a server of `metrics` over HTTP at `/metrics` to be spawned. `refresh` is run
+/// to bring gauges up to date before each scrape.
+pub(crate) fn serve<F, Fut>(
+    addr: SocketAddr,
+    metrics: Arc<Metrics>,
+    refresh: F,
+) -> Result<impl Future<Output = Result<(), hyper::Error>>, hyper::Error>
+where
+    F: Fn() -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let make_service = make_service_fn(move |_| {
+        let (metrics, refresh) = (metrics.clone(), refresh.clone());
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let (metrics, refresh) = (metrics.clone(), refresh.clone());
+                async move {
+                    let res = match (req.method(), req.uri().path()) {
+                        (&Method::GET, "/metrics") => {
+                            refresh().await;
+                            Response::builder()
+                                .header("content-type", TextEncoder::new().format_type())
+                                .body(Body::from(metrics.render()))
+                        }
+                        _ => Response::builder()
+                            .status(StatusCode::NOT_FOUND)
+                            .body(Body::empty()),
+                    };
+                    Ok::<_, Infallible>(res.unwrap())
+                }
+            }))
+        }
+    });
+
+    Ok(Server::try_bind(&addr)?.serve(make_service))
+}
@@ -0,0 +1,194 @@
+use bytes::Bytes;
+use std::time::Duration;
+use tonic::{Code, Request};
+
+use crate::{
+    dstore_proto::{Byte, KeyValue, QueueConfig, QueueMessage, QueueWait},
+    transport::{Client, ConnectOptions},
+    DstoreError, TlsConfig,
+};
+
+/// Options a queue is created with, see `Queue::create()`
+#[derive(Clone, Debug)]
+pub struct QueueOptions {
+    /// Maximum number of VALUEs held, unbounded by default
+    pub capacity: Option<usize>,
+    /// Time a popped VALUE is given to be acknowledged before it's redelivered, 30 seconds by default
+    pub visibility_timeout: Duration,
+    /// Number of times a VALUE is delivered without being acknowledged before it's moved to the queue's
+    /// dead-letter queue, see `dead_letters()`. Redelivered indefinitely by default.
+    pub max_deliveries: Option<u32>,
+}
+
+impl Default for QueueOptions {
+    fn default() -> Self {
+        Self {
+            capacity: None,
+            visibility_timeout: Duration::from_secs(30),
+            max_deliveries: None,
+        }
+    }
+}
+
+/// Client to FIFO queues maintained on Global, each identified by a KEY
+pub struct Queue {
+    /// Stores client connection with Global
+    global: Client,
+}
+
+/// Builds a Queue client with chained setters, for Globals served over TLS or with a token. Options not
+/// set are left at their defaults, as with `Queue::connect()`.
+pub struct QueueBuilder {
+    options: ConnectOptions,
+}
+
+impl QueueBuilder {
+    /// Start building a Queue client to connect to Global on `global_addr`
+    pub fn new(global_addr: &str) -> Self {
+        Self {
+            options: ConnectOptions::new(global_addr),
+        }
+    }
+
+    /// Connect to Global over TLS
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.options.tls(tls);
+        self
+    }
+
+    /// Send shared secret `token` with every request, for Globals started with one
+    pub fn token(mut self, token: &str) -> Self {
+        self.options.token(token);
+        self
+    }
+
+    /// Connect to Global with the options set
+    pub async fn connect(self) -> Result<Queue, DstoreError> {
+        let global = self.options.connect().await?;
+
+        Ok(Queue { global })
+    }
+}
+
+impl Queue {
+    /// Connect to Global server on `global_addr`, a TCP address or a `unix:` prefixed socket path
+    pub async fn connect(global_addr: &str) -> Result<Self, DstoreError> {
+        Self::builder(global_addr).connect().await
+    }
+
+    /// Start building a Queue client to connect to Global on `global_addr`, see `QueueBuilder`
+    pub fn builder(global_addr: &str) -> QueueBuilder {
+        QueueBuilder::new(global_addr)
+    }
+
+    /// Create queue named KEY configured by `options`, or reconfigure an existing one.
+    /// Queues are otherwise created with default options on first push.
+    pub async fn create(&mut self, key: Bytes, options: QueueOptions) -> Result<(), DstoreError> {
+        let req = Request::new(QueueConfig {
+            key: key.to_vec(),
+            capacity: options.capacity.unwrap_or(0) as u64,
+            // Sub-millisecond timeouts would be taken for the default
+            visibility_timeout_millis: options.visibility_timeout.as_millis().max(1) as u64,
+            max_deliveries: options.max_deliveries.unwrap_or(0),
+        });
+        match self.global.create_queue(req).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DstoreError::from(e)),
+        }
+    }
+
+    /// Push VALUE onto the back of queue named KEY, errs with `QueueFull` if it's at capacity
+    pub async fn push_back(&mut self, key: Bytes, value: Bytes) -> Result<(), DstoreError> {
+        let req = Request::new(KeyValue {
+            key: key.to_vec(),
+            value: value.to_vec(),
+            ..Default::default()
+        });
+        match self.global.en_queue(req).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.code() == Code::ResourceExhausted => Err(DstoreError::QueueFull(key)),
+            Err(e) => Err(DstoreError::from(e)),
+        }
+    }
+
+    /// Pop VALUE from the front of queue named KEY, errors if queue is empty. Returns the id VALUE's
+    /// delivery is to be acknowledged by with `ack()`, else VALUE is redelivered after a visibility timeout.
+    pub async fn pop_front(&mut self, key: &Bytes) -> Result<(u64, Bytes), DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        match self.global.de_queue(req).await {
+            Ok(res) => {
+                let QueueMessage { id, value, .. } = res.into_inner();
+                Ok((id, Bytes::from(value)))
+            }
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Acknowledge a popped VALUE as processed, so that it isn't redelivered. Errs if delivery `id` isn't
+    /// awaiting acknowledgement, as when it's already been acknowledged or redelivered.
+    pub async fn ack(&mut self, id: u64) -> Result<(), DstoreError> {
+        let req = Request::new(QueueMessage {
+            id,
+            ..Default::default()
+        });
+        match self.global.ack(req).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DstoreError::from(e)),
+        }
+    }
+
+    /// VALUEs of queue named KEY that ran out of deliveries, oldest first. They're held in a queue of
+    /// their own, named KEY suffixed with `:dlq`, to be consumed as any other queue.
+    pub async fn dead_letters(&mut self, key: &Bytes) -> Result<Vec<Bytes>, DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        match self.global.dead_letters(req).await {
+            Ok(res) => Ok(res
+                .into_inner()
+                .values
+                .into_iter()
+                .map(Bytes::from)
+                .collect()),
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Number of VALUEs in queue named KEY, 0 if there's no such queue
+    pub async fn len(&mut self, key: &Bytes) -> Result<usize, DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        match self.global.queue_len(req).await {
+            Ok(res) => Ok(res.into_inner().size as usize),
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// VALUE at the front of queue named KEY, left in place. `None` if queue is empty.
+    pub async fn peek(&mut self, key: &Bytes) -> Result<Option<Bytes>, DstoreError> {
+        let req = Request::new(Byte { body: key.to_vec() });
+        match self.global.queue_peek(req).await {
+            Ok(res) => Ok(Some(Bytes::from(res.into_inner().body))),
+            Err(e) if e.code() == Code::NotFound => Ok(None),
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+
+    /// Pop VALUE from the front of queue named KEY as with `pop_front()`, waiting upto `timeout` for one to
+    /// be pushed if queue is empty. `None` if none was pushed in time.
+    pub async fn pop_front_wait(
+        &mut self,
+        key: &Bytes,
+        timeout: Duration,
+    ) -> Result<Option<(u64, Bytes)>, DstoreError> {
+        let req = Request::new(QueueWait {
+            key: key.to_vec(),
+            timeout_millis: timeout.as_millis() as u64,
+        });
+        match self.global.de_queue_blocking(req).await {
+            Ok(res) => {
+                let QueueMessage { id, value, .. } = res.into_inner();
+                Ok(Some((id, Bytes::from(value))))
+            }
+            Err(e) if e.code() == Code::NotFound => Ok(None),
+            Err(e) => Err(DstoreError::from_status(key, e)),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use tonic::Status;
+
+use crate::lru::Lru;
+
+/// Byte separating a KEY's namespace from the rest of it, as in `tenant:key`
+pub(crate) const NAMESPACE_SEPARATOR: u8 = b':';
+
+/// Limits on mappings within a namespace, unlimited where `None`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quota {
+    /// Maximum number of KEYs
+    pub max_keys: Option<usize>,
+    /// Maximum sum of KEY and VALUE lengths
+    pub max_bytes: Option<usize>,
+}
+
+/// Mappings currently held within a namespace
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Number of KEYs
+    pub keys: usize,
+    /// Sum of KEY and VALUE lengths
+    pub bytes: usize,
+}
+
+/// Namespace of KEY, the part before it's first separator. KEYs without one aren't in a namespace.
+pub(crate) fn namespace(key: &[u8]) -> Option<&[u8]> {
+    key.iter()
+        .position(|byte| *byte == NAMESPACE_SEPARATOR)
+        .map(|end| &key[..end])
+}
+
+/// Quotas configured and usage of every namespace, kept up to date on each write to Global
+#[derive(Default)]
+pub(crate) struct Quotas {
+    limits: HashMap<Bytes, Quota>,
+    usage: HashMap<Bytes, Usage>,
+    /// Every KEY charged for, namespaced or not, in order last written
+    written: Lru,
+}
+
+impl Quotas {
+    pub(crate) fn set(&mut self, namespace: &[u8], quota: Quota) {
+        self.limits.insert(Bytes::copy_from_slice(namespace), quota);
+    }
+
+    pub(crate) fn usage(&self, namespace: &[u8]) -> Usage {
+        self.usage.get(namespace).copied().unwrap_or_default()
+    }
+
+    /// Number of KEYs charged for across all namespaces, including those of writes yet to be applied
+    pub(crate) fn keys(&self) -> usize {
+        self.written.len()
+    }
+
+    /// KEY charged for least recently
+    pub(crate) fn least_recently_written(&mut self) -> Option<Bytes> {
+        self.written.oldest()
+    }
+
+    /// Account for KEY's mapping changing from `old` bytes to `new` bytes, either `None` if KEY is
+    /// absent, rejecting the change if it takes namespace beyond it's quota
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn charge(
+        &mut self,
+        key: &[u8],
+        old: Option<usize>,
+        new: Option<usize>,
+    ) -> Result<(), Status> {
+        let namespace = match namespace(key) {
+            Some(namespace) => namespace,
+            None => {
+                self.write(key, new);
+                return Ok(());
+            }
+        };
+        let before = self.usage(namespace);
+        let after = apply(before, old, new);
+        let quota = self.limits.get(namespace).copied().unwrap_or_default();
+        // Changes that don't grow usage are let through, even if namespace is already over quota
+        let over_keys =
+            after.keys > before.keys && quota.max_keys.is_some_and(|max| after.keys > max);
+        let over_bytes =
+            after.bytes > before.bytes && quota.max_bytes.is_some_and(|max| after.bytes > max);
+        if over_keys || over_bytes {
+            return Err(Status::resource_exhausted(format!(
+                "Namespace {} is over quota.",
+                String::from_utf8_lossy(namespace)
+            )));
+        }
+        self.usage.insert(Bytes::copy_from_slice(namespace), after);
+        self.write(key, new);
+
+        Ok(())
+    }
+
+    /// Account for KEY's mapping changing as with `charge()`, regardless of quota.
+    /// Used when restoring or evicting mappings, which aren't writes a tenant can be refused.
+    pub(crate) fn record(&mut self, key: &[u8], old: Option<usize>, new: Option<usize>) {
+        if let Some(namespace) = namespace(key) {
+            let usage = apply(self.usage(namespace), old, new);
+            self.usage.insert(Bytes::copy_from_slice(namespace), usage);
+        }
+        self.write(key, new);
+    }
+
+    /// Mark KEY as most recently written, or drop it if it's mapping is now absent
+    fn write(&mut self, key: &[u8], new: Option<usize>) {
+        match new {
+            Some(_) => {
+                if !self.written.touch(key) {
+                    self.written.insert(Bytes::copy_from_slice(key));
+                }
+            }
+            None => {
+                self.written.remove(key);
+            }
+        }
+    }
+}
+
+/// Usage after a mapping changes from `old` bytes to `new` bytes
+fn apply(mut usage: Usage, old: Option<usize>, new: Option<usize>) -> Usage {
+    if let Some(old) = old {
+        usage.keys -= 1;
+        usage.bytes -= old;
+    }
+    if let Some(new) = new {
+        usage.keys += 1;
+        usage.bytes += new;
+    }
+
+    usage
+}
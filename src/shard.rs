@@ -0,0 +1,123 @@
+use bytes::Bytes;
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::sync::Mutex;
+
+use crate::{bloom::fnv1a, DstoreError, Local};
+
+/// Number of virtual nodes placed on the ring per unit of weight
+const VNODES_PER_WEIGHT: u32 = 100;
+
+/// Consistent-hash ring mapping KEYs onto shards, each shard owns virtual nodes in proportion to it's weight
+struct Ring {
+    nodes: BTreeMap<u64, usize>,
+}
+
+impl Ring {
+    /// Place `weight * VNODES_PER_WEIGHT` virtual nodes on the ring for each shard
+    fn new(weights: &[u32]) -> Self {
+        let mut nodes = BTreeMap::new();
+        for (shard, weight) in weights.iter().enumerate() {
+            for vnode in 0..weight * VNODES_PER_WEIGHT {
+                let mut label = (shard as u64).to_le_bytes().to_vec();
+                label.extend_from_slice(&vnode.to_le_bytes());
+                nodes.insert(hash(&label), shard);
+            }
+        }
+
+        Self { nodes }
+    }
+
+    /// Find shard owning the first virtual node clockwise from KEY's position on the ring
+    fn shard(&self, key: &[u8]) -> usize {
+        let point = hash(key);
+        match self.nodes.range(point..).next() {
+            Some((_, shard)) => *shard,
+            None => *self.nodes.values().next().unwrap(),
+        }
+    }
+}
+
+/// Position of `bytes` on the ring, FNV-1a followed by MurmurHash3's 64-bit finalizer to spread
+/// short, similar inputs across the ring. Both are fixed algorithms, so every Local built with any
+/// toolchain routes a KEY to the same Global.
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hash = fnv1a(bytes, 0xcbf2_9ce4_8422_2325);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+/// Local cache spread across multiple Globals, each KEY is owned by a single Global on the ring
+pub struct ShardedLocal {
+    /// A Local connected to each Global, indexed by shard
+    shards: Vec<Arc<Mutex<Local>>>,
+    ring: Ring,
+}
+
+impl ShardedLocal {
+    /// Join the cluster of each Global in `globals`, a Global's share of KEYs is proportional to it's weight
+    pub async fn new(globals: &[(&str, u32)], local_addr: &str) -> Result<Self, DstoreError> {
+        if globals.iter().all(|(_, weight)| *weight == 0) {
+            return Err(DstoreError::Connection(
+                "No Global with a non-zero weight".to_string(),
+            ));
+        }
+
+        let mut shards = vec![];
+        for (global_addr, _) in globals {
+            shards.push(Local::new(global_addr, local_addr).await?);
+        }
+        let weights: Vec<u32> = globals.iter().map(|(_, weight)| *weight).collect();
+
+        Ok(Self {
+            shards,
+            ring: Ring::new(&weights),
+        })
+    }
+
+    /// Index of the Global that owns KEY, in the order Globals were passed to `new()`
+    pub fn shard_of(&self, key: &Bytes) -> usize {
+        self.ring.shard(key)
+    }
+
+    /// Local connected to the Global that owns KEY
+    pub fn shard(&self, key: &Bytes) -> Arc<Mutex<Local>> {
+        self.shards[self.shard_of(key)].clone()
+    }
+
+    /// Insert KEY -> VALUE mapping onto the owning Global
+    pub async fn insert(&self, key: Bytes, value: Bytes) -> Result<(), DstoreError> {
+        self.shard(&key).lock().await.insert(key, value).await
+    }
+
+    /// Get VALUE associated with KEY from the owning Global
+    pub async fn get(&self, key: &Bytes) -> Result<Bytes, DstoreError> {
+        self.shard(key).lock().await.get(key).await
+    }
+
+    /// Remove KEY from the owning Global
+    pub async fn remove(&self, key: &Bytes) -> Result<(), DstoreError> {
+        self.shard(key).lock().await.remove(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_placement_is_stable() {
+        // Changing these breaks routing between Locals of different versions
+        assert_eq!(hash(b""), 0xefd0_1f60_ba99_2926);
+        assert_eq!(hash(b"key"), 0xcf8c_7983_8f3b_3030);
+
+        let ring = Ring::new(&[1, 3]);
+        let shards: Vec<usize> = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta"]
+            .iter()
+            .map(|key| ring.shard(key.as_bytes()))
+            .collect();
+        assert_eq!(shards, [1, 0, 1, 1, 1, 1]);
+    }
+}
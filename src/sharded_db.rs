@@ -0,0 +1,66 @@
+use bytes::Bytes;
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap},
+    hash::{Hash, Hasher},
+};
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+/// Number of independently locked buckets Global's mappings are spread across
+const SHARDS: usize = 16;
+
+/// Map from KEY to `V` split into buckets chosen by a hash of KEY, each behind it's own lock,
+/// so that writes to KEYs in different buckets don't contend with each other
+pub(crate) struct ShardedDb<V> {
+    shards: Vec<RwLock<HashMap<Bytes, V>>>,
+}
+
+impl<V> ShardedDb<V> {
+    pub(crate) fn new() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| RwLock::new(HashMap::new())).collect(),
+        }
+    }
+
+    /// Position of the bucket holding KEY
+    pub(crate) fn index(&self, key: &[u8]) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % SHARDS
+    }
+
+    /// Bucket holding KEY
+    pub(crate) fn shard(&self, key: &[u8]) -> &RwLock<HashMap<Bytes, V>> {
+        &self.shards[self.index(key)]
+    }
+
+    /// Write locks on every bucket holding any of `keys`, keyed by `index()`. Buckets are locked in
+    /// order of position, so writers spanning several KEYs can't deadlock with each other.
+    pub(crate) async fn write_many<'a>(
+        &self,
+        keys: impl IntoIterator<Item = &'a [u8]>,
+    ) -> BTreeMap<usize, RwLockWriteGuard<'_, HashMap<Bytes, V>>> {
+        let indices: BTreeSet<usize> = keys.into_iter().map(|key| self.index(key)).collect();
+        let mut locked = BTreeMap::new();
+        for index in indices {
+            locked.insert(index, self.shards[index].write().await);
+        }
+
+        locked
+    }
+
+    /// Every bucket, for operations spanning all KEYs. Lock them one at a time, in order.
+    pub(crate) fn shards(&self) -> impl Iterator<Item = &RwLock<HashMap<Bytes, V>>> {
+        self.shards.iter()
+    }
+
+    /// Number of mappings across all buckets, as counted one bucket at a time
+    #[cfg(test)]
+    pub(crate) async fn len(&self) -> usize {
+        let mut len = 0;
+        for shard in self.shards() {
+            len += shard.read().await.len();
+        }
+
+        len
+    }
+}
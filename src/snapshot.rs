@@ -0,0 +1,103 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::io;
+
+use crate::DstoreError;
+
+/// Serialize KEY -> VALUE mappings as length-prefixed `KEY_LEN KEY VALUE_LEN VALUE` records
+pub(crate) fn encode<'a>(entries: impl Iterator<Item = (&'a Bytes, &'a Bytes)>) -> Bytes {
+    let mut buf = BytesMut::new();
+    for (key, value) in entries {
+        buf.put_u64(key.len() as u64);
+        buf.put_slice(key);
+        buf.put_u64(value.len() as u64);
+        buf.put_slice(value);
+    }
+
+    buf.freeze()
+}
+
+/// Deserialize KEY -> VALUE mappings, erroring on truncated records
+pub(crate) fn decode(mut buf: Bytes) -> Result<Vec<(Bytes, Bytes)>, DstoreError> {
+    let mut entries = vec![];
+    while buf.has_remaining() {
+        let key = take(&mut buf)?;
+        let value = take(&mut buf)?;
+        entries.push((key, value));
+    }
+
+    Ok(entries)
+}
+
+/// Serialize KEY -> VALUE mappings along with their versions, as length-prefixed
+/// `KEY_LEN KEY VERSION VALUE_LEN VALUE` records
+pub(crate) fn encode_versioned<'a>(
+    entries: impl Iterator<Item = (&'a Bytes, u64, &'a Bytes)>,
+) -> Bytes {
+    let mut buf = BytesMut::new();
+    for (key, version, value) in entries {
+        buf.put_u64(key.len() as u64);
+        buf.put_slice(key);
+        buf.put_u64(version);
+        buf.put_u64(value.len() as u64);
+        buf.put_slice(value);
+    }
+
+    buf.freeze()
+}
+
+/// Deserialize KEY -> VALUE mappings along with their versions, erroring on truncated records
+pub(crate) fn decode_versioned(mut buf: Bytes) -> Result<Vec<(Bytes, u64, Bytes)>, DstoreError> {
+    let mut entries = vec![];
+    while buf.has_remaining() {
+        let key = take(&mut buf)?;
+        if buf.remaining() < 8 {
+            return Err(truncated());
+        }
+        let version = buf.get_u64();
+        let value = take(&mut buf)?;
+        entries.push((key, version, value));
+    }
+
+    Ok(entries)
+}
+
+/// Serialize KEYs as a list of length-prefixed `KEY_LEN KEY` records
+pub(crate) fn encode_keys<'a>(keys: impl Iterator<Item = &'a [u8]>) -> Bytes {
+    let mut buf = BytesMut::new();
+    for key in keys {
+        buf.put_u64(key.len() as u64);
+        buf.put_slice(key);
+    }
+
+    buf.freeze()
+}
+
+/// Deserialize a list of KEYs, erroring on truncated records
+pub(crate) fn decode_keys(mut buf: Bytes) -> Result<Vec<Bytes>, DstoreError> {
+    let mut keys = vec![];
+    while buf.has_remaining() {
+        keys.push(take(&mut buf)?);
+    }
+
+    Ok(keys)
+}
+
+/// Split off a single length-prefixed record from the front of `buf`
+pub(crate) fn take(buf: &mut Bytes) -> Result<Bytes, DstoreError> {
+    if buf.remaining() < 8 {
+        return Err(truncated());
+    }
+    let len = buf.get_u64() as usize;
+    if buf.remaining() < len {
+        return Err(truncated());
+    }
+
+    Ok(buf.split_to(len))
+}
+
+fn truncated() -> DstoreError {
+    DstoreError::Io(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "Snapshot truncated",
+    ))
+}
@@ -0,0 +1,239 @@
+use bytes::Bytes;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use tokio::{
+    sync::Notify,
+    time::{Duration, Instant},
+};
+use tonic::Status;
+
+/// Time a popped VALUE is given to be acknowledged before it's redelivered, unless set for it's queue
+pub(crate) const VISIBILITY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Suffix to a queue's name naming it's dead-letter queue, as in `tasks:dlq`
+const DEAD_LETTER_SUFFIX: &[u8] = b":dlq";
+
+/// Name of the queue VALUEs of queue named KEY are moved to once delivered too many times
+fn dead_letter_queue(key: &[u8]) -> Bytes {
+    Bytes::from([key, DEAD_LETTER_SUFFIX].concat())
+}
+
+/// VALUE awaiting delivery, along with the number of times it's been delivered
+struct Message {
+    value: Bytes,
+    deliveries: u32,
+}
+
+/// VALUE delivered to a consumer, redelivered unless acknowledged before deadline
+struct InFlight {
+    queue: Bytes,
+    message: Message,
+    deadline: Instant,
+}
+
+/// FIFO queue of VALUEs, along with a notifier for consumers awaiting a VALUE while it's empty
+#[derive(Default)]
+struct TaskQueue {
+    items: VecDeque<Message>,
+    /// Wakes a single waiting consumer per VALUE pushed
+    pushed: Arc<Notify>,
+    /// Maximum number of VALUEs held, pushes beyond are rejected. Unbounded if `None`
+    capacity: Option<usize>,
+    /// Time a popped VALUE is given to be acknowledged, `VISIBILITY_TIMEOUT` if `None`
+    visibility_timeout: Option<Duration>,
+    /// Number of times a VALUE is delivered before it's moved to the dead-letter queue, rather than
+    /// redelivered. Redelivered indefinitely if `None`
+    max_deliveries: Option<u32>,
+}
+
+/// Task queues on Global by name, along with VALUEs popped from them that are yet to be acknowledged
+#[derive(Default)]
+pub(crate) struct Queues {
+    named: HashMap<Bytes, TaskQueue>,
+    /// Maps ids of deliveries to VALUEs delivered
+    in_flight: HashMap<u64, InFlight>,
+    /// Id of the next delivery, ids aren't reused so that a late acknowledgement can't acknowledge a redelivery
+    next_id: u64,
+}
+
+impl Queues {
+    /// Set capacity, visibility timeout and maximum deliveries of queue named KEY, creating it if it doesn't exist
+    pub(crate) fn configure(
+        &mut self,
+        key: &[u8],
+        capacity: Option<usize>,
+        visibility_timeout: Option<Duration>,
+        max_deliveries: Option<u32>,
+    ) {
+        let queue = self.named.entry(Bytes::copy_from_slice(key)).or_default();
+        queue.capacity = capacity;
+        queue.visibility_timeout = visibility_timeout;
+        queue.max_deliveries = max_deliveries;
+    }
+
+    /// Push VALUE onto the back of queue named KEY, creating it if it doesn't exist
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn push(&mut self, key: &[u8], value: Bytes) -> Result<(), Status> {
+        let queue = self.named.entry(Bytes::copy_from_slice(key)).or_default();
+        if let Some(capacity) = queue
+            .capacity
+            .filter(|capacity| queue.items.len() >= *capacity)
+        {
+            return Err(Status::resource_exhausted(format!(
+                "Queue {} is full, at capacity of {}.",
+                String::from_utf8_lossy(key),
+                capacity
+            )));
+        }
+        queue.items.push_back(Message {
+            value,
+            deliveries: 0,
+        });
+        queue.pushed.notify_one();
+
+        Ok(())
+    }
+
+    /// Pop VALUE from the front of queue named KEY, holding it in flight until acknowledged.
+    /// Returns the id of the delivery along with VALUE.
+    pub(crate) fn pop(&mut self, key: &[u8]) -> Option<(u64, Bytes)> {
+        self.reap(Instant::now());
+        let queue = self.named.get_mut(key)?;
+        let mut message = queue.items.pop_front()?;
+        // Pass the wakeup on, should more VALUEs be left for other waiting consumers
+        if !queue.items.is_empty() {
+            queue.pushed.notify_one();
+        }
+        message.deliveries += 1;
+        let value = message.value.clone();
+        let deadline = Instant::now() + queue.visibility_timeout.unwrap_or(VISIBILITY_TIMEOUT);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.in_flight.insert(
+            id,
+            InFlight {
+                queue: Bytes::copy_from_slice(key),
+                message,
+                deadline,
+            },
+        );
+
+        Some((id, value))
+    }
+
+    /// Acknowledge delivery `id` as processed, `false` if it isn't in flight, as when already redelivered
+    pub(crate) fn ack(&mut self, id: u64) -> bool {
+        self.in_flight.remove(&id).is_some()
+    }
+
+    /// Notifier of VALUEs pushed onto queue named KEY, creating it if it doesn't exist
+    pub(crate) fn pushed(&mut self, key: &[u8]) -> Arc<Notify> {
+        self.named
+            .entry(Bytes::copy_from_slice(key))
+            .or_default()
+            .pushed
+            .clone()
+    }
+
+    /// Number of VALUEs awaiting delivery from queue named KEY, those in flight aren't counted
+    pub(crate) fn len(&self, key: &[u8]) -> usize {
+        self.named.get(key).map_or(0, |queue| queue.items.len())
+    }
+
+    /// Number of VALUEs awaiting delivery across every queue
+    #[cfg(feature = "metrics")]
+    pub(crate) fn queued(&self) -> usize {
+        self.named.values().map(|queue| queue.items.len()).sum()
+    }
+
+    /// VALUE at the front of queue named KEY, if any
+    pub(crate) fn peek(&self, key: &[u8]) -> Option<&Bytes> {
+        self.named
+            .get(key)?
+            .items
+            .front()
+            .map(|message| &message.value)
+    }
+
+    /// VALUEs moved to the dead-letter queue of queue named KEY, oldest first
+    pub(crate) fn dead_letters(&self, key: &[u8]) -> Vec<Bytes> {
+        self.named
+            .get(&dead_letter_queue(key))
+            .map(|queue| {
+                queue
+                    .items
+                    .iter()
+                    .map(|message| message.value.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Return VALUEs unacknowledged past their deadline to the front of their queues, for redelivery.
+    /// VALUEs out of deliveries are instead moved to the back of their queue's dead-letter queue.
+    pub(crate) fn reap(&mut self, now: Instant) {
+        let mut expired: Vec<u64> = self
+            .in_flight
+            .iter()
+            .filter(|(_, in_flight)| in_flight.deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        // Handle VALUEs in the order they were delivered
+        expired.sort_unstable();
+        let mut redeliveries = vec![];
+        for id in expired {
+            let InFlight { queue, message, .. } = self.in_flight.remove(&id).unwrap();
+            let max_deliveries = self
+                .named
+                .get(&queue)
+                .and_then(|queue| queue.max_deliveries);
+            if max_deliveries.is_some_and(|max| message.deliveries >= max) {
+                let dead = self.named.entry(dead_letter_queue(&queue)).or_default();
+                // Dead letters are to be delivered afresh, should they be consumed
+                dead.items.push_back(Message {
+                    value: message.value,
+                    deliveries: 0,
+                });
+                dead.pushed.notify_one();
+            } else {
+                redeliveries.push((queue, message));
+            }
+        }
+        // Pushed onto the front in reverse, so that they're redelivered in the order first delivered
+        for (queue, message) in redeliveries.into_iter().rev() {
+            let queue = self.named.entry(queue).or_default();
+            queue.items.push_front(message);
+            queue.pushed.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unacknowledged_values_are_redelivered_in_order() {
+        let mut queues = Queues::default();
+        queues.configure(b"tasks", None, Some(Duration::from_secs(1)), None);
+        for value in ["one", "two", "three"] {
+            queues.push(b"tasks", Bytes::from(value)).unwrap();
+        }
+        let (one, _) = queues.pop(b"tasks").unwrap();
+        let (two, _) = queues.pop(b"tasks").unwrap();
+        assert!(queues.ack(one));
+        assert!(!queues.ack(one));
+
+        // Only the unacknowledged VALUE is redelivered, ahead of those never delivered
+        queues.reap(Instant::now() + Duration::from_secs(2));
+        assert!(!queues.ack(two));
+        assert_eq!(queues.len(b"tasks"), 2);
+        let (redelivery, value) = queues.pop(b"tasks").unwrap();
+        assert_ne!(redelivery, two);
+        assert_eq!(value, Bytes::from("two"));
+        assert_eq!(queues.peek(b"tasks"), Some(&Bytes::from("three")));
+    }
+}
@@ -0,0 +1,136 @@
+use futures::StreamExt;
+use std::{io, net::SocketAddr, sync::Arc};
+use tokio::{
+    io::DuplexStream,
+    net::TcpListener,
+    sync::{mpsc, oneshot, Mutex},
+    task::JoinHandle,
+};
+use tokio_stream::wrappers::{TcpListenerStream, UnboundedReceiverStream};
+use tonic::transport::{Endpoint, Server, Uri};
+
+use crate::{DstoreError, Global, Local, LocalConfig};
+
+/// Size of the buffer of each in-memory pipe to Global, writes beyond wait on Global to read
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Global served in-process on an ephemeral port, for tests that must not collide on ports when run
+/// concurrently. Stops serving once shut down or dropped.
+pub struct TestGlobal {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    server: Option<JoinHandle<Result<(), tonic::transport::Error>>>,
+}
+
+impl TestGlobal {
+    /// Serve a Global with default options on a port chosen by the OS
+    pub async fn spawn() -> Self {
+        Self::spawn_with(Global::new()).await
+    }
+
+    /// Serve `global`, as configured, on a port chosen by the OS. Global is bound by the time this
+    /// returns, so Locals may connect right away.
+    pub async fn spawn_with(global: Global) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Couldn't bind to an ephemeral port");
+        let addr = listener.local_addr().unwrap();
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let server = tokio::spawn(
+            Server::builder()
+                .add_service(global.into_service())
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                    signal.await.ok();
+                }),
+        );
+
+        Self {
+            addr,
+            shutdown: Some(shutdown),
+            server: Some(server),
+        }
+    }
+
+    /// Address Global is served on, as passed to `Local::new()`
+    pub fn addr(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// Connect a Local with UID `local_addr` to Global
+    pub async fn local(&self, local_addr: &str) -> Result<Arc<Mutex<Local>>, DstoreError> {
+        Local::new(&self.addr(), local_addr).await
+    }
+
+    /// Stop serving, returning once Global has shut down. Locals still connected fail their next request.
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        if let Some(server) = self.server.take() {
+            server.await.ok();
+        }
+    }
+}
+
+impl Drop for TestGlobal {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+/// Global served in-process over in-memory pipes rather than the network, for testing Local's logic, such
+/// as how it caches and chunks VALUEs, without binding any port. Stops accepting connections once dropped.
+pub struct MemoryGlobal {
+    /// Hands Global the far end of each pipe Locals connect over
+    connections: mpsc::UnboundedSender<DuplexStream>,
+}
+
+impl MemoryGlobal {
+    /// Serve a Global with default options in memory
+    pub fn spawn() -> Self {
+        Self::spawn_with(Global::new())
+    }
+
+    /// Serve `global`, as configured, in memory. Must be called from within a tokio runtime.
+    pub fn spawn_with(global: Global) -> Self {
+        let (connections, incoming) = mpsc::unbounded_channel();
+        tokio::spawn(
+            Server::builder()
+                .add_service(global.into_service())
+                .serve_with_incoming(
+                    UnboundedReceiverStream::new(incoming).map(Ok::<_, io::Error>),
+                ),
+        );
+
+        Self { connections }
+    }
+
+    /// Connect a Local with UID `local_addr` to Global
+    pub async fn local(&self, local_addr: &str) -> Result<Arc<Mutex<Local>>, DstoreError> {
+        self.local_with_config(local_addr, LocalConfig::default())
+            .await
+    }
+
+    /// Connect a Local with UID `local_addr` to Global, configured by `config`. Options for reaching
+    /// Global over the network, such as TLS, have no effect.
+    pub async fn local_with_config(
+        &self,
+        local_addr: &str,
+        config: LocalConfig,
+    ) -> Result<Arc<Mutex<Local>>, DstoreError> {
+        let connections = self.connections.clone();
+        let channel = Endpoint::from_static("http://global.in-memory")
+            .connect_with_connector(tower::service_fn(move |_: Uri| {
+                let (local, global) = tokio::io::duplex(PIPE_CAPACITY);
+                let sent = connections.send(global).map_err(|_| {
+                    io::Error::new(io::ErrorKind::ConnectionRefused, "Global stopped serving")
+                });
+                async move { sent.map(|_| local) }
+            }))
+            .await?;
+
+        Local::with_channel(channel, local_addr, config).await
+    }
+}
@@ -0,0 +1,231 @@
+#[cfg(unix)]
+use futures::{Stream, StreamExt};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+#[cfg(unix)]
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+#[cfg(unix)]
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{UnixListener, UnixStream},
+};
+#[cfg(unix)]
+use tokio_stream::wrappers::UnixListenerStream;
+#[cfg(unix)]
+use tonic::transport::server::Connected;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint},
+    Request, Status,
+};
+
+use crate::{
+    dstore_proto::dstore_client::DstoreClient, DstoreError, TlsConfig, FENCE_HEADER, TOKEN_HEADER,
+    UID_HEADER,
+};
+
+/// Client connection to Global, tagging each request as it's sender's
+pub(crate) type Client = DstoreClient<InterceptedService<Channel, Tagging>>;
+
+/// Tags each request with the sending Local's UID, along with the fencing token and shared secret if any.
+/// Clients outside cluster, such as `Admin`, carry no UID.
+#[derive(Clone)]
+pub(crate) struct Tagging {
+    uid: Option<MetadataValue<Ascii>>,
+    token: Option<MetadataValue<Ascii>>,
+    /// Fencing token the sender was issued, none if 0
+    fence: Arc<AtomicU64>,
+}
+
+impl Tagging {
+    /// Tag requests with `uid` and shared secret `token`, if any, along with the fencing token held by `fence`
+    pub(crate) fn new(
+        uid: Option<&str>,
+        token: Option<&str>,
+        fence: Arc<AtomicU64>,
+    ) -> Result<Self, DstoreError> {
+        let uid = match uid {
+            Some(uid) => Some(
+                MetadataValue::from_str(uid)
+                    .map_err(|_| DstoreError::Connection(format!("Invalid UID: {}", uid)))?,
+            ),
+            None => None,
+        };
+        let token = match token {
+            Some(token) => Some(
+                MetadataValue::from_str(token)
+                    .map_err(|_| DstoreError::Connection("Invalid token".to_string()))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self { uid, token, fence })
+    }
+}
+
+impl Interceptor for Tagging {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(uid) = &self.uid {
+            req.metadata_mut().insert(UID_HEADER, uid.clone());
+        }
+        // Requests carry the fencing token Local was issued if any, Global checks it on writes
+        match self.fence.load(Ordering::SeqCst) {
+            0 => {}
+            token => {
+                req.metadata_mut()
+                    .insert(FENCE_HEADER, MetadataValue::from(token));
+            }
+        }
+        if let Some(token) = &self.token {
+            req.metadata_mut().insert(TOKEN_HEADER, token.clone());
+        }
+
+        Ok(req)
+    }
+}
+
+/// Prefix of an address naming a Unix domain socket by it's path rather than a TCP address,
+/// as in `unix:/tmp/dstore.sock`
+pub(crate) const UNIX_PREFIX: &str = "unix:";
+
+/// Path of the Unix domain socket named by `addr`, if it isn't a TCP address
+pub(crate) fn unix_path(addr: &str) -> Option<&str> {
+    addr.strip_prefix(UNIX_PREFIX)
+}
+
+/// Endpoint to reach Global at `addr`, over TLS as configured by `tls` if set. Sockets aren't addressed
+/// by URI, so endpoints to them carry a placeholder authority, to be connected with `connect()`.
+pub(crate) fn endpoint(addr: &str, tls: Option<&TlsConfig>) -> Result<Endpoint, DstoreError> {
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    let authority = match unix_path(addr) {
+        Some(_) => "localhost",
+        None => addr,
+    };
+
+    let endpoint = Endpoint::from_shared(format!("{}://{}", scheme, authority))
+        .map_err(|e| DstoreError::Connection(e.to_string()))?;
+    match tls {
+        Some(TlsConfig { ca_cert, domain }) => Ok(endpoint.tls_config(
+            ClientTlsConfig::new()
+                .ca_certificate(Certificate::from_pem(ca_cert))
+                .domain_name(domain),
+        )?),
+        None => Ok(endpoint),
+    }
+}
+
+/// Connect `endpoint` to Global at `addr`, over TCP or the Unix domain socket it names
+pub(crate) async fn connect(endpoint: Endpoint, addr: &str) -> Result<Channel, DstoreError> {
+    match unix_path(addr) {
+        #[cfg(unix)]
+        Some(path) => {
+            let path = path.to_string();
+            let connector = tower::service_fn(move |_: tonic::transport::Uri| {
+                UnixStream::connect(path.clone())
+            });
+            Ok(endpoint.connect_with_connector(connector).await?)
+        }
+        #[cfg(not(unix))]
+        Some(_) => Err(DstoreError::Connection(
+            "Unix domain sockets are only supported on Unix".to_string(),
+        )),
+        None => Ok(endpoint.connect().await?),
+    }
+}
+
+/// Connection to Global over a Unix domain socket, as accepted by Global. Carries no peer address to
+/// serve along with requests, unlike a TCP connection.
+#[cfg(unix)]
+pub(crate) struct UnixConnection(UnixStream);
+
+#[cfg(unix)]
+impl Connected for UnixConnection {
+    type ConnectInfo = ();
+
+    fn connect_info(&self) -> Self::ConnectInfo {}
+}
+
+#[cfg(unix)]
+impl AsyncRead for UnixConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+#[cfg(unix)]
+impl AsyncWrite for UnixConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+/// Connections accepted on a Unix domain socket at `path`, replacing a socket left behind by an earlier Global
+#[cfg(unix)]
+pub(crate) fn incoming(path: &str) -> io::Result<impl Stream<Item = io::Result<UnixConnection>>> {
+    use std::os::unix::fs::FileTypeExt;
+    if std::fs::metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+
+    Ok(UnixListenerStream::new(listener).map(|conn| conn.map(UnixConnection)))
+}
+
+/// Options clients outside cluster, such as `Admin` and `Queue`, connect to Global with
+#[derive(Clone, Debug)]
+pub(crate) struct ConnectOptions {
+    global_addr: String,
+    tls: Option<TlsConfig>,
+    token: Option<String>,
+}
+
+impl ConnectOptions {
+    /// Connect to Global on `global_addr`, a TCP address or a `unix:` prefixed socket path, with no TLS or token
+    pub(crate) fn new(global_addr: &str) -> Self {
+        Self {
+            global_addr: global_addr.to_string(),
+            tls: None,
+            token: None,
+        }
+    }
+
+    pub(crate) fn tls(&mut self, tls: TlsConfig) {
+        self.tls = Some(tls);
+    }
+
+    pub(crate) fn token(&mut self, token: &str) {
+        self.token = Some(token.to_string());
+    }
+
+    /// Connect to Global, requests carry shared secret token if set
+    pub(crate) async fn connect(&self) -> Result<Client, DstoreError> {
+        let addr = &self.global_addr;
+        let channel = connect(endpoint(addr, self.tls.as_ref())?, addr).await?;
+        let tagging = Tagging::new(None, self.token.as_deref(), Arc::default())?;
+
+        Ok(DstoreClient::with_interceptor(channel, tagging).accept_gzip())
+    }
+}
@@ -0,0 +1,48 @@
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{marker::PhantomData, sync::Arc};
+use tokio::sync::Mutex;
+
+use crate::{DstoreError, Local};
+
+/// Typed view over a Local, KEYs and VALUEs are serialized with bincode on their way to and from Bytes
+pub struct TypedStore<K, V> {
+    local: Arc<Mutex<Local>>,
+    types: PhantomData<fn(K, V)>,
+}
+
+impl<K: Serialize, V: Serialize + DeserializeOwned> TypedStore<K, V> {
+    /// Store typed mappings through `local`
+    pub fn new(local: Arc<Mutex<Local>>) -> Self {
+        Self {
+            local,
+            types: PhantomData,
+        }
+    }
+
+    /// Local that typed mappings are stored through
+    pub fn local(&self) -> Arc<Mutex<Local>> {
+        self.local.clone()
+    }
+
+    /// Insert KEY -> VALUE mapping, as with `Local::insert()`
+    pub async fn insert(&self, key: &K, value: &V) -> Result<(), DstoreError> {
+        let (key, value) = (encode(key)?, encode(value)?);
+        self.local.lock().await.insert(key, value).await
+    }
+
+    /// Get VALUE associated with KEY, as with `Local::get()`
+    pub async fn get(&self, key: &K) -> Result<V, DstoreError> {
+        let value = self.local.lock().await.get(&encode(key)?).await?;
+        Ok(bincode::deserialize(&value)?)
+    }
+
+    /// Remove KEY from the system, as with `Local::remove()`
+    pub async fn remove(&self, key: &K) -> Result<(), DstoreError> {
+        self.local.lock().await.remove(&encode(key)?).await
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Bytes, DstoreError> {
+    Ok(Bytes::from(bincode::serialize(value)?))
+}
@@ -0,0 +1,254 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    thread,
+};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{snapshot, DstoreError};
+
+/// When writes appended to a write-ahead log are flushed to disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Flush after every write, so that no acknowledged write is lost even on power failure
+    Always,
+    /// Flush after every `n` writes. Unflushed writes survive Global crashing, but not the machine.
+    Batched(usize),
+}
+
+/// Write to Global's mappings, as recorded in a write-ahead log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Record {
+    /// KEY was mapped to VALUE, either newly or replacing an older VALUE
+    Put { key: Bytes, value: Bytes },
+    /// KEY's mapping was removed
+    Remove(Bytes),
+}
+
+const PUT: u8 = 0;
+const REMOVE: u8 = 1;
+
+impl Record {
+    /// Serialize as a tag byte followed by length-prefixed KEY and, for puts, VALUE
+    fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        match self {
+            Self::Put { key, value } => {
+                buf.put_u8(PUT);
+                buf.put_u64(key.len() as u64);
+                buf.put_slice(key);
+                buf.put_u64(value.len() as u64);
+                buf.put_slice(value);
+            }
+            Self::Remove(key) => {
+                buf.put_u8(REMOVE);
+                buf.put_u64(key.len() as u64);
+                buf.put_slice(key);
+            }
+        }
+
+        buf.freeze()
+    }
+
+    /// Deserialize a single record from the front of `buf`, erroring if it's truncated or unknown
+    fn decode(buf: &mut Bytes) -> Result<Self, DstoreError> {
+        if !buf.has_remaining() {
+            return Err(DstoreError::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Record truncated",
+            )));
+        }
+        match buf.get_u8() {
+            PUT => Ok(Self::Put {
+                key: snapshot::take(buf)?,
+                value: snapshot::take(buf)?,
+            }),
+            REMOVE => Ok(Self::Remove(snapshot::take(buf)?)),
+            tag => Err(DstoreError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown record type {}", tag),
+            ))),
+        }
+    }
+}
+
+/// Request made of the thread writing to log
+enum Command {
+    /// Append encoded records, `usize` of them, replying once written and flushed as per sync policy
+    Append(Bytes, usize, oneshot::Sender<io::Result<()>>),
+    /// Reply with offset of the end of log, as of every record appended so far
+    End(oneshot::Sender<u64>),
+    /// Drop every record before offset, replying once done
+    Compact(u64, oneshot::Sender<io::Result<()>>),
+}
+
+/// Append-only log of writes to Global's mappings, replayed to recover writes made since the last snapshot.
+/// Writes are made on a dedicated thread, so that tasks awaiting them don't block the runtime. Handles to
+/// the log are cheap to clone, the thread stops once every one of them is dropped.
+#[derive(Clone)]
+pub(crate) struct Wal {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Wal {
+    /// Open log at `path` for appending, creating it if it doesn't exist, along with every record already
+    /// in it in the order they were appended. A record left incomplete by a crash midway through appending
+    /// it is cut off, along with anything after it, so that later records aren't appended after it.
+    pub(crate) fn open(
+        path: &Path,
+        policy: SyncPolicy,
+    ) -> Result<(Self, Vec<Record>), DstoreError> {
+        let mut buf = match std::fs::read(path) {
+            Ok(buf) => Bytes::from(buf),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Bytes::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let (mut records, mut valid) = (vec![], 0);
+        while buf.has_remaining() {
+            let remaining = buf.remaining();
+            match Record::decode(&mut buf) {
+                Ok(record) => records.push(record),
+                Err(_) => break,
+            }
+            valid += remaining - buf.remaining();
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.set_len(valid as u64)?;
+        let mut writer = Writer {
+            path: path.to_path_buf(),
+            file,
+            policy,
+            unsynced: 0,
+            start: 0,
+            len: valid as u64,
+        };
+        let (commands, mut rx) = mpsc::unbounded_channel();
+        thread::Builder::new()
+            .name("dstore-wal".to_string())
+            .spawn(move || {
+                while let Some(command) = rx.blocking_recv() {
+                    writer.run(command);
+                }
+            })?;
+
+        Ok((Self { commands }, records))
+    }
+
+    /// Append records as a single write, flushing to disk as per sync policy. Should the write fail, none of
+    /// them are left in log.
+    pub(crate) async fn append(&self, records: &[Record]) -> io::Result<()> {
+        let mut buf = BytesMut::new();
+        for record in records {
+            buf.put(record.encode());
+        }
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::Append(buf.freeze(), records.len(), tx))?;
+        rx.await.map_err(|_| closed())?
+    }
+
+    /// Offset of the end of log, to later compact it upto once the records before are saved elsewhere
+    pub(crate) async fn end(&self) -> io::Result<u64> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::End(tx))?;
+        rx.await.map_err(|_| closed())
+    }
+
+    /// Drop every record before `offset`, as returned by `end()`, keeping those appended since
+    pub(crate) async fn compact(&self, offset: u64) -> io::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.send(Command::Compact(offset, tx))?;
+        rx.await.map_err(|_| closed())?
+    }
+
+    fn send(&self, command: Command) -> io::Result<()> {
+        self.commands.send(command).map_err(|_| closed())
+    }
+}
+
+/// Error for requests made of a log whose thread has stopped
+fn closed() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "Write-ahead log closed")
+}
+
+/// State of the thread writing to log
+struct Writer {
+    path: PathBuf,
+    file: File,
+    policy: SyncPolicy,
+    /// Number of writes appended since last flush to disk
+    unsynced: usize,
+    /// Offset of the start of file, counting bytes compacted away since log was opened
+    start: u64,
+    /// Length of file
+    len: u64,
+}
+
+impl Writer {
+    fn run(&mut self, command: Command) {
+        // Requester may have given up waiting, the command is carried out regardless
+        match command {
+            Command::Append(buf, records, tx) => {
+                let _ = tx.send(self.append(&buf, records));
+            }
+            Command::End(tx) => {
+                let _ = tx.send(self.start + self.len);
+            }
+            Command::Compact(offset, tx) => {
+                let _ = tx.send(self.compact(offset));
+            }
+        }
+    }
+
+    /// Append encoded records, cutting them off again should writing or flushing them fail
+    fn append(&mut self, buf: &[u8], records: usize) -> io::Result<()> {
+        let res = self.write(buf, records);
+        if res.is_err() {
+            let _ = self.file.set_len(self.len);
+        } else {
+            self.len += buf.len() as u64;
+        }
+
+        res
+    }
+
+    fn write(&mut self, buf: &[u8], records: usize) -> io::Result<()> {
+        self.file.write_all(buf)?;
+        self.unsynced += records;
+        let limit = match self.policy {
+            SyncPolicy::Always => 1,
+            SyncPolicy::Batched(n) => n.max(1),
+        };
+        if self.unsynced >= limit {
+            self.file.sync_data()?;
+            self.unsynced = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite log with only what follows `offset`. Written to a temporary file first, so that a crash
+    /// midway leaves the log intact.
+    fn compact(&mut self, offset: u64) -> io::Result<()> {
+        let skip = offset.saturating_sub(self.start).min(self.len);
+        let mut rest = vec![];
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(skip))?;
+        file.read_to_end(&mut rest)?;
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".compact");
+        let mut file = File::create(&tmp)?;
+        file.write_all(&rest)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.start += skip;
+        self.len = rest.len() as u64;
+        self.unsynced = 0;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use bytes::Bytes;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tonic::Streaming;
+
+use crate::{dstore_proto::Change, DstoreError};
+
+/// Write or removal of a mapping under a watched prefix
+#[derive(Clone, Debug, PartialEq)]
+pub enum WatchEvent {
+    /// KEY was mapped to VALUE, either newly or replacing an older VALUE
+    Put { key: Bytes, value: Bytes },
+    /// KEY's mapping was removed, expired or evicted
+    Removed(Bytes),
+}
+
+/// Mutation as recorded in Global's change feed, see `Local::changes()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mutation {
+    /// Position of mutation in feed, each mutation's is one more than the last
+    pub sequence: u64,
+    pub event: WatchEvent,
+}
+
+impl From<Change> for Mutation {
+    fn from(change: Change) -> Self {
+        let key = Bytes::from(change.key);
+        let event = match change.removed {
+            true => WatchEvent::Removed(key),
+            false => WatchEvent::Put {
+                key,
+                value: Bytes::from(change.value),
+            },
+        };
+
+        Self {
+            sequence: change.sequence,
+            event,
+        }
+    }
+}
+
+/// Live replica of mappings on Global whose KEYs start with a prefix, see `Local::watch_prefix()`.
+/// Replica is kept up to date in the background until Watch is dropped.
+pub struct Watch {
+    /// Mappings under prefix, as of the latest change recieved
+    replica: Arc<Mutex<HashMap<Bytes, Bytes>>>,
+    /// Changes recieved after the initial snapshot, in order
+    events: mpsc::UnboundedReceiver<WatchEvent>,
+    /// Background task applying changes to replica
+    task: JoinHandle<()>,
+}
+
+impl Watch {
+    /// Build replica from the snapshot at the head of `stream`, then apply changes following it
+    pub(crate) async fn start(mut stream: Streaming<Change>) -> Result<Self, DstoreError> {
+        let mut replica = HashMap::new();
+        loop {
+            match stream.message().await? {
+                Some(Change { synced: true, .. }) => break,
+                Some(Change { key, value, .. }) => {
+                    replica.insert(Bytes::from(key), Bytes::from(value));
+                }
+                None => {
+                    return Err(DstoreError::Connection(
+                        "Watch ended before snapshot was complete".to_string(),
+                    ))
+                }
+            }
+        }
+
+        let replica = Arc::new(Mutex::new(replica));
+        let (tx, events) = mpsc::unbounded_channel();
+        let updater = replica.clone();
+        let task = tokio::spawn(async move {
+            while let Ok(Some(Change {
+                key,
+                value,
+                removed,
+                ..
+            })) = stream.message().await
+            {
+                let key = Bytes::from(key);
+                let event = match removed {
+                    true => {
+                        updater.lock().unwrap().remove(&key);
+                        WatchEvent::Removed(key)
+                    }
+                    false => {
+                        let value = Bytes::from(value);
+                        updater.lock().unwrap().insert(key.clone(), value.clone());
+                        WatchEvent::Put { key, value }
+                    }
+                };
+                // Replica is maintained even if events aren't being consumed
+                let _ = tx.send(event);
+            }
+        });
+
+        Ok(Self {
+            replica,
+            events,
+            task,
+        })
+    }
+
+    /// VALUE currently mapped to KEY in replica
+    pub fn get(&self, key: &[u8]) -> Option<Bytes> {
+        self.replica.lock().unwrap().get(key).cloned()
+    }
+
+    /// Copy of every mapping currently in replica
+    pub fn snapshot(&self) -> HashMap<Bytes, Bytes> {
+        self.replica.lock().unwrap().clone()
+    }
+
+    /// Wait for the next change after the initial snapshot, `None` once Global ends the watch
+    pub async fn next(&mut self) -> Option<WatchEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for Watch {
+    /// Stop recieving changes from Global
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -1,12 +1,149 @@
-use dstore::Local;  
 use bytes::Bytes;
+use dstore::{
+    testing::{MemoryGlobal, TestGlobal},
+    Admin, BatchOp, BatchSummary, BloomFilter, CacheStats, Consistency, DstoreError, DstoreServer,
+    Global, GlobalStats, KeyAccess, Local, LocalConfig, Mutation, NodeInfo, Precondition, Queue,
+    QueueOptions, Quota, ShardedLocal, TlsConfig, TypedStore, Upsert, Usage, WatchEvent,
+    MAX_BYTE_SIZE,
+};
+use futures::StreamExt;
+use std::{
+    convert::TryInto,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{oneshot, Mutex},
+    time::{self, Duration, Instant},
+};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    codegen::{http, BoxFuture, Context, Poll, Service},
+    transport::{Body, NamedService, Server},
+};
+
+/// Global whose reply to the RPC named in `lose` is held back until after Locals give up waiting, as
+/// though lost on it's way back, though the request itself is applied. Only a single reply is lost.
+#[derive(Clone)]
+struct LosingGlobal<S> {
+    inner: S,
+    lose: Arc<std::sync::Mutex<Option<&'static str>>>,
+}
+
+impl<S> Service<http::Request<Body>> for LosingGlobal<S>
+where
+    S: Service<http::Request<Body>>,
+    S::Response: Send + 'static,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<S::Response, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+        let rpc = req.uri().path().rsplit('/').next().unwrap_or_default();
+        let mut lose = self.lose.lock().unwrap();
+        let lost = *lose == Some(rpc);
+        if lost {
+            *lose = None;
+        }
+        let reply = self.inner.call(req);
+        Box::pin(async move {
+            let reply = reply.await;
+            if lost {
+                time::sleep(Duration::from_secs(1)).await;
+            }
+            reply
+        })
+    }
+}
+
+impl<S: NamedService> NamedService for LosingGlobal<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+/// Serve a Global on a port chosen by the OS, that loses the next reply to the RPC set in the returned slot
+async fn spawn_losing_global() -> (String, Arc<std::sync::Mutex<Option<&'static str>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let lose = Arc::new(std::sync::Mutex::new(None));
+    let service = LosingGlobal {
+        inner: Global::new().into_service(),
+        lose: lose.clone(),
+    };
+    tokio::spawn(
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    (addr, lose)
+}
+
+/// Forward connections on a port chosen by the OS to `target` in the background, returning it's address.
+/// Once the returned flag is set, traffic is swallowed without closing connections, as though the far end
+/// had hung.
+async fn spawn_proxy(target: &str) -> (String, Arc<AtomicBool>) {
+    let target = target.to_string();
+    let frozen = Arc::new(AtomicBool::new(false));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let freeze = frozen.clone();
+    tokio::spawn(async move {
+        while let Ok((client, _)) = listener.accept().await {
+            let server = TcpStream::connect(&target).await.unwrap();
+            let (client_rx, client_tx) = client.into_split();
+            let (server_rx, server_tx) = server.into_split();
+            for (mut rx, mut tx) in [(client_rx, server_tx), (server_rx, client_tx)] {
+                let freeze = freeze.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0; 8192];
+                    while let Ok(n) = rx.read(&mut buf).await {
+                        if n == 0 {
+                            return;
+                        }
+                        if !freeze.load(Ordering::SeqCst) {
+                            let _ = tx.write_all(&buf[..n]).await;
+                        }
+                    }
+                });
+            }
+        }
+    });
+
+    (addr, frozen)
+}
+
+/// Address of a port that was free when asked, for Globals served through an API that binds on it's own,
+/// as when restarted on the same address
+async fn free_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+/// Wait for Global to accept connections on `addr`
+async fn serving(addr: &str) {
+    while TcpStream::connect(addr).await.is_err() {
+        time::sleep(Duration::from_millis(10)).await;
+    }
+}
 
 #[tokio::test]
 async fn push_to_global_test() {
     // intialize global and local
-    let global_addr= "[::1]:50051";
-    let local_addr= "[::1]:50052";
-    
+    let local_addr = "127.0.0.1:50052";
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
     let local = Local::new(global_addr, local_addr).await.unwrap();
     let mut local = local.lock().await;
 
@@ -25,3 +162,3472 @@ async fn push_to_global_test() {
         }
     }
 }
+
+#[tokio::test]
+async fn pull_file_trailing_bytes_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    // 9 MB doesn't divide evenly into MAX_BYTE_SIZE frames
+    let key = Bytes::from("large");
+    let value: Bytes = (0..9_000_000).map(|i| (i % 251) as u8).collect();
+
+    // Store large VALUE on Global through one node
+    let writer = Local::new(global_addr, "127.0.0.1:50062").await.unwrap();
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), value.clone())
+        .await
+        .unwrap();
+
+    // Another node with an empty cache must reassemble VALUE from streamed frames
+    let reader = Local::new(global_addr, "127.0.0.1:50063").await.unwrap();
+    let pulled = reader.lock().await.get(&key).await.unwrap();
+    assert_eq!(pulled.len(), value.len());
+    assert_eq!(pulled, value);
+}
+
+#[tokio::test]
+async fn touch_prevents_lru_eviction_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50072").await.unwrap();
+    let mut local = local.lock().await;
+    local.set_max_entries(Some(2));
+
+    let (a, b, c) = (Bytes::from("a"), Bytes::from("b"), Bytes::from("c"));
+    local.insert(a.clone(), Bytes::from("1")).await.unwrap();
+    local.insert(b.clone(), Bytes::from("2")).await.unwrap();
+
+    // Keep `a` warm, leaving `b` as the least recently used mapping
+    assert!(local.touch(&a));
+    local.insert(c.clone(), Bytes::from("3")).await.unwrap();
+
+    assert!(local.is_cached(&a));
+    assert!(!local.is_cached(&b));
+    assert!(local.is_cached(&c));
+
+    // Touching an uncached KEY is a no-op
+    assert!(!local.touch(&Bytes::from("d")));
+}
+
+#[tokio::test]
+async fn preload_snapshot_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    // Export the cache of a node that has inserted KEYs onto Global
+    let (key, value) = (Bytes::from("warm"), Bytes::from("cache"));
+    let overwritten = Bytes::from("overwritten");
+    let exporter = Local::new(global_addr, "127.0.0.1:50082").await.unwrap();
+    let mut exporter = exporter.lock().await;
+    exporter.insert(key.clone(), value.clone()).await.unwrap();
+    exporter
+        .insert(overwritten.clone(), Bytes::from("old"))
+        .await
+        .unwrap();
+    let mut snapshot = exporter.export_snapshot().to_vec();
+
+    // Append a stale mapping that Global doesn't hold
+    let stale = Bytes::from("stale");
+    snapshot.extend_from_slice(&(stale.len() as u64).to_be_bytes());
+    snapshot.extend_from_slice(&stale);
+    snapshot.extend_from_slice(&1u64.to_be_bytes());
+    snapshot.extend_from_slice(&1u64.to_be_bytes());
+    snapshot.push(0);
+
+    // VALUE replaced on Global since export, by one of the same size
+    exporter
+        .update_value(overwritten.clone(), Bytes::from("new"))
+        .await
+        .unwrap();
+
+    let local = Local::new(global_addr, "127.0.0.1:50083").await.unwrap();
+    let mut local = local.lock().await;
+    assert_eq!(local.preload_snapshot(Bytes::from(snapshot)).unwrap(), 3);
+
+    // Entries aren't validated upfront, even stale ones are cached
+    assert!(local.is_cached(&key));
+    assert!(local.is_cached(&stale));
+    assert!(local.is_cached(&overwritten));
+
+    // Validation happens on first access, against the version Global holds
+    assert_eq!(local.get(&key).await.unwrap(), value);
+    assert!(local.get(&stale).await.is_err());
+    assert!(!local.is_cached(&stale));
+    assert_eq!(local.get(&overwritten).await.unwrap(), Bytes::from("new"));
+}
+
+#[tokio::test]
+async fn queue_fifo_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let mut queue = Queue::connect(global_addr).await.unwrap();
+    let key = Bytes::from("tasks");
+    for task in &["one", "two", "three"] {
+        queue
+            .push_back(key.clone(), Bytes::from(*task))
+            .await
+            .unwrap();
+    }
+
+    // VALUEs are dequeued in the order they were enqueued
+    for task in &["one", "two", "three"] {
+        assert_eq!(queue.pop_front(&key).await.unwrap().1, Bytes::from(*task));
+    }
+    assert!(queue.pop_front(&key).await.is_err());
+}
+
+#[tokio::test]
+async fn insert_with_ttl_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50102").await.unwrap();
+    let mut local = local.lock().await;
+
+    let key = Bytes::from("ephemeral");
+    let value = Bytes::from("value");
+    local
+        .insert_with_ttl(key.clone(), value.clone(), Duration::from_secs(1))
+        .await
+        .unwrap();
+    assert_eq!(local.get(&key).await.unwrap(), value);
+
+    // Mapping is gone from cache and Global once TTL elapses
+    time::sleep(Duration::from_millis(1500)).await;
+    assert!(local.get(&key).await.is_err());
+    assert!(!local.is_cached(&key));
+
+    // Sub-second TTLs hold on Global as long as they do in cache
+    let key = Bytes::from("fleeting");
+    local
+        .insert_with_ttl(key.clone(), value.clone(), Duration::from_millis(500))
+        .await
+        .unwrap();
+    let other = Local::new(global_addr, "127.0.0.1:50103").await.unwrap();
+    assert_eq!(other.lock().await.get(&key).await.unwrap(), value);
+    time::sleep(Duration::from_millis(800)).await;
+    assert_eq!(local.ttl(&key).await.unwrap(), -2);
+}
+
+#[tokio::test]
+async fn clear_all_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let admin = Local::new(global_addr, "127.0.0.1:50112").await.unwrap();
+    let mut admin = admin.lock().await;
+    let other = Local::new(global_addr, "127.0.0.1:50113").await.unwrap();
+    let mut other = other.lock().await;
+
+    let keys = [Bytes::from("k1"), Bytes::from("k2"), Bytes::from("k3")];
+    for key in &keys {
+        admin.insert(key.clone(), Bytes::from("v")).await.unwrap();
+        other.get(key).await.unwrap();
+    }
+
+    assert_eq!(admin.clear_all().await.unwrap(), keys.len());
+    assert!(!admin.is_cached(&keys[0]));
+
+    // Other node flushes it's cache on next update, after which KEYs are gone system-wide
+    assert!(other.is_cached(&keys[0]));
+    other.update().await;
+    for key in &keys {
+        assert!(!other.is_cached(key));
+        assert!(other.get(key).await.is_err());
+    }
+}
+
+#[tokio::test]
+async fn consistency_level_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let writer = Local::new(global_addr, "127.0.0.1:50122").await.unwrap();
+    let mut writer = writer.lock().await;
+    let reader = Local::new(global_addr, "127.0.0.1:50123").await.unwrap();
+    let mut reader = reader.lock().await;
+
+    let key = Bytes::from("config");
+    let (old, new) = (Bytes::from("old"), Bytes::from("new"));
+    writer.insert(key.clone(), old.clone()).await.unwrap();
+    reader.get(&key).await.unwrap();
+
+    // Replace VALUE on Global, reader's cache is stale until it's next update
+    writer.remove(&key).await.unwrap();
+    writer.update().await;
+    writer.insert(key.clone(), new.clone()).await.unwrap();
+
+    let eventual = reader
+        .get_with_consistency(&key, Consistency::Eventual)
+        .await
+        .unwrap();
+    assert_eq!(eventual, old);
+    let strong = reader
+        .get_with_consistency(&key, Consistency::Strong)
+        .await
+        .unwrap();
+    assert_eq!(strong, new);
+}
+
+#[tokio::test]
+async fn shutdown_draining_event_test() {
+    let global_addr = &free_addr().await;
+    let (tx, rx) = oneshot::channel::<()>();
+    let addr = global_addr.clone();
+    tokio::spawn(async move {
+        Global::start_server_with_shutdown(&addr, async {
+            rx.await.ok();
+        })
+        .await
+        .unwrap()
+    });
+    serving(global_addr).await;
+
+    let local = Local::new(global_addr, "127.0.0.1:50132").await.unwrap();
+    let mut local = local.lock().await;
+    assert!(!local.is_draining());
+
+    // Draining event is recieved while Global is still serving
+    tx.send(()).unwrap();
+    time::timeout(Duration::from_secs(5), async {
+        while !local.is_draining() {
+            local.update().await;
+            time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn error_kind_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50142").await.unwrap();
+    let mut local = local.lock().await;
+
+    let key = Bytes::from("taken");
+    local.insert(key.clone(), Bytes::from("v")).await.unwrap();
+    match local.insert(key.clone(), Bytes::from("w")).await {
+        Err(DstoreError::KeyOccupied(k)) => assert_eq!(k, key),
+        other => panic!("expected KeyOccupied, got {:?}", other),
+    }
+
+    let missing = Bytes::from("missing");
+    match local.get(&missing).await {
+        Err(DstoreError::NotFound(k)) => assert_eq!(k, missing),
+        other => panic!("expected NotFound, got {:?}", other),
+    }
+
+    // Nothing listens on this address
+    match Local::new("127.0.0.1:50149", "127.0.0.1:50143").await {
+        Err(DstoreError::Connection(_)) => {}
+        Err(e) => panic!("expected Connection, got {:?}", e),
+        Ok(_) => panic!("expected Connection error"),
+    }
+}
+
+#[tokio::test]
+async fn update_value_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let a = Local::new(global_addr, "127.0.0.1:50152").await.unwrap();
+    let mut a = a.lock().await;
+    let b = Local::new(global_addr, "127.0.0.1:50153").await.unwrap();
+    let mut b = b.lock().await;
+
+    let key = Bytes::from("counter");
+    a.insert(key.clone(), Bytes::from("1")).await.unwrap();
+    assert_eq!(b.get(&key).await.unwrap(), Bytes::from("1"));
+
+    a.update_value(key.clone(), Bytes::from("2")).await.unwrap();
+    assert_eq!(a.get(&key).await.unwrap(), Bytes::from("2"));
+
+    // B serves the stale copy until it processes the invalidation
+    assert_eq!(b.get(&key).await.unwrap(), Bytes::from("1"));
+    b.update().await;
+    assert_eq!(b.get(&key).await.unwrap(), Bytes::from("2"));
+
+    // Only existing KEYs can be overwritten
+    assert!(matches!(
+        a.update_value(Bytes::from("absent"), Bytes::from("x"))
+            .await,
+        Err(DstoreError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn weighted_sharding_test() {
+    let (small, large) = (TestGlobal::spawn().await, TestGlobal::spawn().await);
+    let (small, large) = (&small.addr(), &large.addr());
+
+    let sharded = ShardedLocal::new(&[(small, 1), (large, 3)], "127.0.0.1:50163")
+        .await
+        .unwrap();
+
+    // Larger Global should own roughly three quarters of KEYs
+    let total = 10_000;
+    let owned_by_large = (0..total)
+        .filter(|i| sharded.shard_of(&Bytes::from(format!("key-{}", i))) == 1)
+        .count();
+    let share = owned_by_large as f64 / total as f64;
+    assert!((0.70..0.80).contains(&share), "share was {}", share);
+
+    // Mappings round trip through their owning Global
+    let key = Bytes::from("routed");
+    sharded.insert(key.clone(), Bytes::from("v")).await.unwrap();
+    assert_eq!(sharded.get(&key).await.unwrap(), Bytes::from("v"));
+}
+
+#[tokio::test]
+async fn inspect_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let (a_addr, b_addr) = ("127.0.0.1:50172", "127.0.0.1:50173");
+    let a = Local::new(global_addr, a_addr).await.unwrap();
+    let mut a = a.lock().await;
+    let b = Local::new(global_addr, b_addr).await.unwrap();
+    let mut b = b.lock().await;
+
+    let key = Bytes::from("inspected");
+    a.insert_with_ttl(key.clone(), Bytes::from("value"), Duration::from_secs(60))
+        .await
+        .unwrap();
+    b.get(&key).await.unwrap();
+
+    let info = a.inspect(&key).await.unwrap();
+    assert_eq!(info.size, 5);
+    let created = info.version;
+    assert!(info.ttl.unwrap() <= Duration::from_secs(60));
+    assert_eq!(info.owner, Bytes::from(a_addr));
+    assert!(info.modified.elapsed().unwrap() < Duration::from_secs(5));
+    let mut holders = info.holders.clone();
+    holders.sort();
+    assert_eq!(holders, vec![Bytes::from(a_addr), Bytes::from(b_addr)]);
+    // KEYs can't be tagged yet
+    assert!(info.tags.is_empty());
+
+    // Overwriting moves version on and leaves only the writer holding VALUE
+    b.update_value(key.clone(), Bytes::from("other"))
+        .await
+        .unwrap();
+    let info = a.inspect(&key).await.unwrap();
+    assert!(info.version > created);
+    assert_eq!(info.owner, Bytes::from(a_addr));
+    assert_eq!(info.holders, vec![Bytes::from(b_addr)]);
+}
+
+#[tokio::test]
+async fn compare_and_swap_race_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let key = Bytes::from("lock");
+    let a = Local::new(global_addr, "127.0.0.1:50182").await.unwrap();
+    let b = Local::new(global_addr, "127.0.0.1:50183").await.unwrap();
+    a.lock()
+        .await
+        .insert(key.clone(), Bytes::from("free"))
+        .await
+        .unwrap();
+
+    // Both nodes race to acquire the lock, only one swap may succeed
+    let race = |local: Arc<Mutex<Local>>, owner: &'static str| {
+        let key = key.clone();
+        tokio::spawn(async move {
+            local
+                .lock()
+                .await
+                .compare_and_swap(key, Bytes::from("free"), Bytes::from(owner))
+                .await
+                .unwrap()
+        })
+    };
+    let (won_a, won_b) = tokio::join!(race(a.clone(), "a"), race(b.clone(), "b"));
+    let (won_a, won_b) = (won_a.unwrap(), won_b.unwrap());
+    assert!(won_a ^ won_b);
+
+    let winner = if won_a { "a" } else { "b" };
+    let value = b
+        .lock()
+        .await
+        .get_with_consistency(&key, Consistency::Strong)
+        .await
+        .unwrap();
+    assert_eq!(value, Bytes::from(winner));
+}
+
+#[tokio::test]
+async fn list_keys_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50192").await.unwrap();
+    let mut local = local.lock().await;
+    assert!(local.keys().await.unwrap().is_empty());
+
+    for key in &["x", "y", "z"] {
+        local
+            .insert(Bytes::from(*key), Bytes::from("v"))
+            .await
+            .unwrap();
+    }
+    let mut keys = local.keys().await.unwrap();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![Bytes::from("x"), Bytes::from("y"), Bytes::from("z")]
+    );
+}
+
+#[tokio::test]
+async fn event_log_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50202").await.unwrap();
+    let mut local = local.lock().await;
+
+    let key = Bytes::from("events");
+    for (i, event) in ["created", "updated", "deleted"].iter().enumerate() {
+        let offset = local.log_append(key.clone(), Bytes::from(*event)).await;
+        assert_eq!(offset.unwrap(), i as u64);
+    }
+
+    // Reading doesn't consume events, offsets remain stable
+    for _ in 0..2 {
+        let events = local.log_read(&key, 1, 10).await.unwrap();
+        assert_eq!(
+            events,
+            vec![(1, Bytes::from("updated")), (2, Bytes::from("deleted"))]
+        );
+    }
+    assert_eq!(local.log_read(&key, 0, 1).await.unwrap().len(), 1);
+    assert!(local.log_read(&key, 3, 10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn oversized_key_frame_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50212").await.unwrap();
+    let mut local = local.lock().await;
+
+    // KEY is sent as a single frame, so it can't exceed frame size limit
+    let key = Bytes::from(vec![b'k'; MAX_BYTE_SIZE + 1]);
+    match local.insert_file(key, Bytes::from("value")).await {
+        Err(DstoreError::FrameTooLarge { size, limit }) => {
+            assert_eq!(size, MAX_BYTE_SIZE + 1);
+            assert_eq!(limit, MAX_BYTE_SIZE);
+        }
+        other => panic!("expected FrameTooLarge, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn compression_threshold_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let writer = Local::new(global_addr, "127.0.0.1:50222").await.unwrap();
+    let mut writer = writer.lock().await;
+    writer.set_compression_threshold(Some(1024));
+    let reader = Local::new(global_addr, "127.0.0.1:50223").await.unwrap();
+    let mut reader = reader.lock().await;
+    reader.set_compression_threshold(Some(1024));
+
+    // Both small and compressed large VALUEs round trip unchanged
+    let small = Bytes::from("small");
+    let large = Bytes::from(vec![b'a'; 1024 * 1024]);
+    for (key, value) in &[("small", &small), ("large", &large)] {
+        let key = Bytes::from(*key);
+        writer.insert(key.clone(), (*value).clone()).await.unwrap();
+        assert_eq!(&reader.get(&key).await.unwrap(), *value);
+        assert_eq!(reader.inspect(&key).await.unwrap().size, value.len());
+    }
+}
+
+#[tokio::test]
+async fn reconcile_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let a = Local::new(global_addr, "127.0.0.1:50232").await.unwrap();
+    let mut a = a.lock().await;
+    let b = Local::new(global_addr, "127.0.0.1:50233").await.unwrap();
+    let mut b = b.lock().await;
+
+    let (k1, k2, k3) = (Bytes::from("k1"), Bytes::from("k2"), Bytes::from("k3"));
+    for key in &[&k1, &k2, &k3] {
+        a.insert((*key).clone(), Bytes::from("old")).await.unwrap();
+        b.get(key).await.unwrap();
+    }
+
+    // B is disconnected, missing invalidations as A diverges Global from B's cache
+    b.leave().await.unwrap();
+    a.remove(&k1).await.unwrap();
+    a.update_value(k2.clone(), Bytes::from("new"))
+        .await
+        .unwrap();
+
+    b.reconcile().await.unwrap();
+    assert!(!b.is_cached(&k1));
+    assert!(b.is_cached(&k2));
+    assert_eq!(b.get(&k2).await.unwrap(), Bytes::from("new"));
+    assert_eq!(b.get(&k3).await.unwrap(), Bytes::from("old"));
+    assert!(b.get(&k1).await.is_err());
+}
+
+#[tokio::test]
+async fn own_writes_keep_versions_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50834").await.unwrap();
+    let mut local = local.lock().await;
+    let (k1, k2) = (Bytes::from("k1"), Bytes::from("k2"));
+    for key in &[&k1, &k2] {
+        local
+            .insert((*key).clone(), Bytes::from("old"))
+            .await
+            .unwrap();
+    }
+    local
+        .update_value(k1.clone(), Bytes::from("new"))
+        .await
+        .unwrap();
+    assert!(local
+        .compare_and_swap(k2.clone(), Bytes::from("old"), Bytes::from("new"))
+        .await
+        .unwrap());
+
+    // Versions of it's own writes are known, so neither is refetched as stale
+    let fetched = local.stats().remote_fetches;
+    local.reconcile().await.unwrap();
+    assert_eq!(local.stats().remote_fetches, fetched);
+    for key in &[&k1, &k2] {
+        assert!(local.is_cached(key));
+    }
+}
+
+#[tokio::test]
+async fn overflow_reconciles_test() {
+    let mut global = Global::new();
+    global.set_max_queue_len(2);
+    let global = TestGlobal::spawn_with(global).await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "127.0.0.1:50835").await.unwrap();
+    let mut writer = writer.lock().await;
+    let config = LocalConfig {
+        background_updates: false,
+        ..LocalConfig::default()
+    };
+    let reader = Local::with_config(global_addr, "127.0.0.1:50836", config)
+        .await
+        .unwrap();
+    let mut reader = reader.lock().await;
+
+    let keys: Vec<Bytes> = ["a", "b", "c", "d", "e"]
+        .iter()
+        .map(|k| Bytes::from(*k))
+        .collect();
+    for key in &keys {
+        writer
+            .insert(key.clone(), Bytes::from("old"))
+            .await
+            .unwrap();
+    }
+    // Versions of KEYs fetched by every path are kept along with them
+    reader.get_many(&keys[..3]).await.unwrap();
+    reader.get_each(&keys[3..4], |_, _| {}).await.unwrap();
+    reader.get_file(&keys[4]).await.unwrap();
+
+    // Writes overflow reader's invalidation queue, reconciling rather than flushing on next update
+    for key in &keys[..3] {
+        writer
+            .update_value(key.clone(), Bytes::from("new"))
+            .await
+            .unwrap();
+    }
+    let fetched = reader.stats().remote_fetches;
+    reader.update().await;
+    assert_eq!(reader.stats().remote_fetches, fetched + 3);
+    for key in &keys {
+        assert!(reader.is_cached(key));
+    }
+    assert_eq!(reader.get(&keys[0]).await.unwrap(), "new");
+    assert_eq!(reader.get(&keys[4]).await.unwrap(), "old");
+}
+
+#[tokio::test]
+async fn pull_file_missing_key_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50242").await.unwrap();
+    let mut local = local.lock().await;
+
+    let missing = Bytes::from("missing");
+    match local.get_file(&missing).await {
+        Err(DstoreError::NotFound(k)) => assert_eq!(k, missing),
+        other => panic!("expected NotFound, got {:?}", other),
+    }
+
+    // Global survives the failed pull and keeps serving
+    local
+        .insert(missing.clone(), Bytes::from("v"))
+        .await
+        .unwrap();
+    assert_eq!(local.get_file(&missing).await.unwrap(), Bytes::from("v"));
+}
+
+#[tokio::test]
+async fn insert_batch_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50252").await.unwrap();
+    let mut local = local.lock().await;
+
+    local
+        .insert(Bytes::from("key0"), Bytes::from("taken"))
+        .await
+        .unwrap();
+    let pairs = (0..1000)
+        .map(|i| {
+            (
+                Bytes::from(format!("key{}", i)),
+                Bytes::from(format!("{}", i)),
+            )
+        })
+        .collect();
+    let summary = local.insert_batch(pairs).await.unwrap();
+    assert_eq!(
+        summary,
+        BatchSummary {
+            accepted: 999,
+            present: 1
+        }
+    );
+
+    // Accepted mappings are cached, the one already present retains it's VALUE
+    assert!(local.is_cached(&Bytes::from("key999")));
+    assert_eq!(
+        local.get(&Bytes::from("key500")).await.unwrap(),
+        Bytes::from("500")
+    );
+    assert_eq!(
+        local.get(&Bytes::from("key0")).await.unwrap(),
+        Bytes::from("taken")
+    );
+}
+
+#[tokio::test]
+async fn hot_keys_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50262").await.unwrap();
+    let mut local = local.lock().await;
+
+    let (hot, cold) = (Bytes::from("hot"), Bytes::from("cold"));
+    local.insert(hot.clone(), Bytes::from("v")).await.unwrap();
+    local.insert(cold.clone(), Bytes::from("v")).await.unwrap();
+
+    // Strong reads bypass cache, so each is served by Global
+    for _ in 0..50 {
+        local
+            .get_with_consistency(&hot, Consistency::Strong)
+            .await
+            .unwrap();
+    }
+    local
+        .get_with_consistency(&cold, Consistency::Strong)
+        .await
+        .unwrap();
+
+    let top = local.hot_keys(1).await.unwrap();
+    assert_eq!(
+        top,
+        vec![KeyAccess {
+            key: hot,
+            reads: 50,
+            writes: 1
+        }]
+    );
+}
+
+#[tokio::test]
+async fn upsert_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let a = Local::new(global_addr, "127.0.0.1:50272").await.unwrap();
+    let mut a = a.lock().await;
+    let b = Local::new(global_addr, "127.0.0.1:50273").await.unwrap();
+    let mut b = b.lock().await;
+
+    let key = Bytes::from("key");
+    let created = match a.upsert(key.clone(), Bytes::from("v1")).await.unwrap() {
+        Upsert::Created { version } => version,
+        upserted => panic!("Expected KEY to be created, was {:?}", upserted),
+    };
+    assert_eq!(b.get(&key).await.unwrap(), Bytes::from("v1"));
+
+    let updated = match a.upsert(key.clone(), Bytes::from("v2")).await.unwrap() {
+        Upsert::Updated { version } => version,
+        upserted => panic!("Expected KEY to be updated, was {:?}", upserted),
+    };
+    assert!(updated > created);
+    assert_eq!(a.inspect(&key).await.unwrap().version, updated);
+
+    // B's stale copy is invalidated
+    b.update().await;
+    assert_eq!(b.get(&key).await.unwrap(), Bytes::from("v2"));
+}
+
+#[tokio::test]
+async fn get_many_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let a = Local::new(global_addr, "127.0.0.1:50282").await.unwrap();
+    let mut a = a.lock().await;
+    let b = Local::new(global_addr, "127.0.0.1:50283").await.unwrap();
+    let mut b = b.lock().await;
+
+    let keys: Vec<Bytes> = (0..4).map(|i| Bytes::from(format!("k{}", i))).collect();
+    // Half the KEYs are cached by B, the other half only by A
+    for (i, key) in keys.iter().enumerate() {
+        let value = Bytes::from(format!("v{}", i));
+        match i < 2 {
+            true => b.insert(key.clone(), value).await.unwrap(),
+            false => a.insert(key.clone(), value).await.unwrap(),
+        }
+    }
+
+    let mut requested = keys.clone();
+    requested.push(Bytes::from("missing"));
+    let values = b.get_many(&requested).await.unwrap();
+    assert_eq!(values.len(), 4);
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(values[key], Bytes::from(format!("v{}", i)));
+        assert!(b.is_cached(key));
+    }
+
+    // Only the uncached half was read off Global
+    for access in b.hot_keys(10).await.unwrap() {
+        let fetched = access.key == keys[2] || access.key == keys[3];
+        assert_eq!(access.reads, fetched as u64);
+    }
+}
+
+#[tokio::test]
+async fn max_entries_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let writer = Local::new(global_addr, "127.0.0.1:50292").await.unwrap();
+    let mut writer = writer.lock().await;
+    let local = Local::with_max_entries(global_addr, "127.0.0.1:50293", 2)
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+
+    let (a, b, c) = (Bytes::from("a"), Bytes::from("b"), Bytes::from("c"));
+    writer.insert(a.clone(), Bytes::from("1")).await.unwrap();
+    local.get(&a).await.unwrap();
+    local.insert(b.clone(), Bytes::from("2")).await.unwrap();
+    local.insert(c.clone(), Bytes::from("3")).await.unwrap();
+
+    // Oldest mapping is evicted from cache only
+    assert!(!local.is_cached(&a));
+    assert!(local.is_cached(&b) && local.is_cached(&c));
+    assert_eq!(local.get(&a).await.unwrap(), Bytes::from("1"));
+    assert!(!local.is_cached(&b));
+}
+
+#[tokio::test]
+async fn get_each_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let a = Local::new(global_addr, "127.0.0.1:50302").await.unwrap();
+    let mut a = a.lock().await;
+    let b = Local::new(global_addr, "127.0.0.1:50303").await.unwrap();
+    let mut b = b.lock().await;
+
+    let (cached, remote, missing) = (
+        Bytes::from("cached"),
+        Bytes::from("remote"),
+        Bytes::from("missing"),
+    );
+    b.insert(cached.clone(), Bytes::from("1")).await.unwrap();
+    a.insert(remote.clone(), Bytes::from("2")).await.unwrap();
+
+    let mut results = vec![];
+    b.get_each(
+        &[remote.clone(), missing.clone(), cached.clone()],
+        |key, value| results.push((key.clone(), value.cloned())),
+    )
+    .await
+    .unwrap();
+
+    // Cached KEYs are reported ahead of those looked up on Global
+    assert_eq!(
+        results,
+        vec![
+            (cached, Some(Bytes::from("1"))),
+            (remote.clone(), Some(Bytes::from("2"))),
+            (missing, None),
+        ]
+    );
+    assert!(b.is_cached(&remote));
+}
+
+#[tokio::test]
+async fn global_max_entries_test() {
+    let global = Global::builder().max_entries(1).build().await.unwrap();
+    let global = TestGlobal::spawn_with(global).await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50312").await.unwrap();
+    let mut local = local.lock().await;
+
+    let (old, new) = (Bytes::from("old"), Bytes::from("new"));
+    local.insert(old.clone(), Bytes::from("1")).await.unwrap();
+    local.insert(new.clone(), Bytes::from("2")).await.unwrap();
+
+    // Evicted from Global, so dropped from cache on update
+    local.update().await;
+    assert!(!local.is_cached(&old));
+    assert!(local.get(&old).await.is_err());
+    assert_eq!(local.get(&new).await.unwrap(), Bytes::from("2"));
+}
+
+#[tokio::test]
+async fn update_interval_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let a = Local::new(global_addr, "127.0.0.1:50322").await.unwrap();
+    let config = LocalConfig {
+        update_interval: Duration::from_millis(100),
+        ..LocalConfig::default()
+    };
+    let b = Local::with_config(global_addr, "127.0.0.1:50323", config)
+        .await
+        .unwrap();
+
+    let key = Bytes::from("key");
+    a.lock()
+        .await
+        .insert(key.clone(), Bytes::from("old"))
+        .await
+        .unwrap();
+    b.lock().await.get(&key).await.unwrap();
+    a.lock()
+        .await
+        .update_value(key.clone(), Bytes::from("new"))
+        .await
+        .unwrap();
+
+    // B's updater drops the stale copy well within the default interval
+    time::sleep(Duration::from_millis(500)).await;
+    assert!(!b.lock().await.is_cached(&key));
+}
+
+#[tokio::test]
+async fn shutdown_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let a = Local::new(global_addr, "127.0.0.1:50332").await.unwrap();
+    let config = LocalConfig {
+        update_interval: Duration::from_millis(100),
+        ..LocalConfig::default()
+    };
+    let b = Local::with_config(global_addr, "127.0.0.1:50333", config)
+        .await
+        .unwrap();
+
+    let key = Bytes::from("key");
+    a.lock()
+        .await
+        .insert(key.clone(), Bytes::from("old"))
+        .await
+        .unwrap();
+    b.lock().await.get(&key).await.unwrap();
+    b.lock().await.shutdown(false).await.unwrap();
+    a.lock()
+        .await
+        .update_value(key.clone(), Bytes::from("new"))
+        .await
+        .unwrap();
+
+    // With the updater stopped, B's stale copy is never invalidated
+    time::sleep(Duration::from_millis(500)).await;
+    let mut b = b.lock().await;
+    assert!(b.is_cached(&key));
+    assert_eq!(b.get(&key).await.unwrap(), Bytes::from("old"));
+}
+
+#[tokio::test]
+async fn updater_doesnt_block_local_test() {
+    let global = TestGlobal::spawn().await;
+    let (proxy_addr, frozen) = &spawn_proxy(&global.addr()).await;
+    let config = LocalConfig {
+        update_interval: Duration::from_millis(50),
+        timeout: Some(Duration::from_secs(1)),
+        retries: 2,
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(proxy_addr, "127.0.0.1:50334", config)
+        .await
+        .unwrap();
+    let key = Bytes::from("key");
+    local
+        .lock()
+        .await
+        .insert(key.clone(), Bytes::from("value"))
+        .await
+        .unwrap();
+
+    // Cached reads are served while the updater waits on a hung Global
+    frozen.store(true, Ordering::SeqCst);
+    time::sleep(Duration::from_millis(200)).await;
+    let mut local = time::timeout(Duration::from_millis(100), local.lock())
+        .await
+        .unwrap();
+    assert_eq!(local.get(&key).await.unwrap(), Bytes::from("value"));
+}
+
+#[tokio::test]
+async fn dropped_local_leaves_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let config = LocalConfig {
+        update_interval: Duration::from_millis(100),
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(global_addr, "dropped", config)
+        .await
+        .unwrap();
+    assert!(local.lock().await.is_updating());
+    time::sleep(Duration::from_millis(250)).await;
+
+    // Updater doesn't keep Local alive, so it leaves cluster on drop
+    drop(local);
+    time::sleep(Duration::from_millis(300)).await;
+    let mut admin = Admin::connect(global_addr).await.unwrap();
+    assert!(admin.list_nodes().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn ping_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50342").await.unwrap();
+    let mut local = local.lock().await;
+    let latency = local.ping().await.unwrap();
+    assert!(latency > Duration::from_nanos(0) && latency < Duration::from_secs(1));
+
+    // Once Global has stopped serving, ping fails
+    global.shutdown().await;
+    assert!(local.ping().await.is_err());
+}
+
+#[tokio::test]
+async fn hincrby_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let local = Local::new(global_addr, "127.0.0.1:50352").await.unwrap();
+    let mut local = local.lock().await;
+
+    let key = Bytes::from("metrics");
+    let (views, clicks) = (Bytes::from("views"), Bytes::from("clicks"));
+    assert_eq!(local.hincrby(&key, &views, 5).await.unwrap(), 5);
+    assert_eq!(local.hincrby(&key, &clicks, 1).await.unwrap(), 1);
+    assert_eq!(local.hincrby(&key, &views, -2).await.unwrap(), 3);
+    assert_eq!(local.hincrby(&key, &clicks, 1).await.unwrap(), 2);
+
+    // Overflowing a field is an error that leaves it intact
+    assert!(local.hincrby(&key, &views, i64::MAX).await.is_err());
+    assert_eq!(local.hget(&key, &views).await.unwrap(), 3);
+    assert_eq!(local.hget(&key, &clicks).await.unwrap(), 2);
+    assert!(matches!(
+        local.hget(&key, &Bytes::from("missing")).await,
+        Err(DstoreError::NotFound(_))
+    ));
+
+    // Hash is a mapping like any other, gone once removed or cleared
+    local.remove(&key).await.unwrap();
+    assert!(local.hget(&key, &views).await.is_err());
+    assert_eq!(local.hincrby(&key, &views, 1).await.unwrap(), 1);
+    local.clear_all().await.unwrap();
+    assert!(local.hget(&key, &views).await.is_err());
+
+    // Plain VALUEs aren't mistaken for hashes
+    let plain = Bytes::from("plain");
+    local.insert(plain.clone(), Bytes::from("v")).await.unwrap();
+    assert!(local.hincrby(&plain, &views, 1).await.is_err());
+}
+
+#[tokio::test]
+async fn packet_size_boundary_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let writer = Local::new(global_addr, "127.0.0.1:50362").await.unwrap();
+    let mut writer = writer.lock().await;
+    let reader = Local::new(global_addr, "127.0.0.1:50363").await.unwrap();
+    let mut reader = reader.lock().await;
+
+    // VALUEs either side of the single packet limit round-trip through Global
+    for size in &[MAX_BYTE_SIZE - 1, MAX_BYTE_SIZE, MAX_BYTE_SIZE + 1] {
+        let key = Bytes::from(format!("boundary{}", size));
+        let value: Bytes = (0..*size).map(|i| (i % 251) as u8).collect();
+        writer.insert(key.clone(), value.clone()).await.unwrap();
+        assert_eq!(reader.get(&key).await.unwrap(), value);
+    }
+}
+
+#[tokio::test]
+async fn batched_invalidation_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+
+    let a = Local::new(global_addr, "127.0.0.1:50372").await.unwrap();
+    let mut a = a.lock().await;
+    let b = Local::new(global_addr, "127.0.0.1:50373").await.unwrap();
+    let mut b = b.lock().await;
+    b.set_compression_threshold(Some(1024));
+
+    let keys: Vec<Bytes> = (0..2000)
+        .map(|i| Bytes::from(format!("key{}", i)))
+        .collect();
+    a.insert_batch(keys.iter().map(|key| (key.clone(), key.clone())).collect())
+        .await
+        .unwrap();
+    assert_eq!(b.get_many(&keys).await.unwrap().len(), keys.len());
+
+    // Half the KEYs are removed, B drops all of them in one update
+    for key in &keys[..1000] {
+        a.remove(key).await.unwrap();
+    }
+    b.update().await;
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(b.is_cached(key), i >= 1000);
+    }
+}
+
+#[tokio::test]
+async fn embedded_service_test() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let global_addr = &listener.local_addr().unwrap().to_string();
+    // Global is served alongside whatever else the application adds to it's server
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(Global::new().into_service())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let local = Local::new(global_addr, "127.0.0.1:50382").await.unwrap();
+    let mut local = local.lock().await;
+    let key = Bytes::from("embedded");
+    local.insert(key.clone(), Bytes::from("v")).await.unwrap();
+    let value = local
+        .get_with_consistency(&key, Consistency::Strong)
+        .await
+        .unwrap();
+    assert_eq!(value, Bytes::from("v"));
+}
+
+#[tokio::test]
+async fn tls_test() {
+    let global_addr = &free_addr().await;
+    let addr = global_addr.clone();
+    tokio::spawn(async move {
+        let (cert, key) = (
+            include_bytes!("tls/server.pem"),
+            include_bytes!("tls/server.key"),
+        );
+        Global::start_server_tls(&addr, cert, key).await.unwrap()
+    });
+    serving(global_addr).await;
+
+    let config = LocalConfig {
+        tls: Some(TlsConfig {
+            ca_cert: include_bytes!("tls/ca.pem").to_vec(),
+            domain: "localhost".to_string(),
+        }),
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(global_addr, "127.0.0.1:50392", config)
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+
+    let key = Bytes::from("secret");
+    local.insert(key.clone(), Bytes::from("v")).await.unwrap();
+    let value = local
+        .get_with_consistency(&key, Consistency::Strong)
+        .await
+        .unwrap();
+    assert_eq!(value, Bytes::from("v"));
+
+    // A plaintext client can't talk to Global
+    assert!(Local::new(global_addr, "127.0.0.1:50393").await.is_err());
+
+    // Nor can an operator's, unlike one over TLS
+    let mut plaintext = Admin::connect(global_addr).await.unwrap();
+    assert!(plaintext.stats().await.is_err());
+    let mut admin = Admin::builder(global_addr)
+        .tls(TlsConfig {
+            ca_cert: include_bytes!("tls/ca.pem").to_vec(),
+            domain: "localhost".to_string(),
+        })
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(admin.stats().await.unwrap().keys, 1);
+}
+
+#[tokio::test]
+async fn watch_prefix_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50402").await.unwrap();
+    let mut local = local.lock().await;
+
+    for key in ["app/a", "app/b", "other/c"] {
+        local
+            .insert(Bytes::from(key), Bytes::from(key))
+            .await
+            .unwrap();
+    }
+
+    // Initial snapshot holds only existing KEYs under prefix
+    let mut watch = local.watch_prefix(b"app/").await.unwrap();
+    let snapshot = watch.snapshot();
+    assert_eq!(snapshot.len(), 2);
+    assert_eq!(snapshot[&Bytes::from("app/a")], Bytes::from("app/a"));
+    assert!(watch.get(b"other/c").is_none());
+
+    // Later writes under prefix are reported as they happen, others aren't
+    local
+        .insert(Bytes::from("other/d"), Bytes::from("d"))
+        .await
+        .unwrap();
+    local
+        .insert(Bytes::from("app/d"), Bytes::from("d"))
+        .await
+        .unwrap();
+    assert_eq!(
+        watch.next().await,
+        Some(WatchEvent::Put {
+            key: Bytes::from("app/d"),
+            value: Bytes::from("d")
+        })
+    );
+    assert_eq!(watch.get(b"app/d"), Some(Bytes::from("d")));
+
+    let _ = local.remove(&Bytes::from("app/a")).await;
+    assert_eq!(
+        watch.next().await,
+        Some(WatchEvent::Removed(Bytes::from("app/a")))
+    );
+    assert!(watch.get(b"app/a").is_none());
+}
+
+#[tokio::test]
+async fn token_test() {
+    let global = TestGlobal::spawn_with(Global::with_token("s3cret")).await;
+    let global_addr = &global.addr();
+
+    // Local carrying the expected token is served as usual
+    let config = LocalConfig {
+        token: Some("s3cret".to_string()),
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(global_addr, "127.0.0.1:50412", config)
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+    let key = Bytes::from("guarded");
+    local.insert(key.clone(), Bytes::from("v")).await.unwrap();
+    let value = local
+        .get_with_consistency(&key, Consistency::Strong)
+        .await
+        .unwrap();
+    assert_eq!(value, Bytes::from("v"));
+
+    // Locals with a missing or wrong token can't join
+    let missing = Local::new(global_addr, "127.0.0.1:50413").await;
+    assert!(matches!(missing, Err(DstoreError::Connection(_))));
+    let config = LocalConfig {
+        token: Some("guess".to_string()),
+        ..LocalConfig::default()
+    };
+    let wrong = Local::with_config(global_addr, "127.0.0.1:50414", config).await;
+    assert!(matches!(wrong, Err(DstoreError::Connection(_))));
+
+    // Nor can they reach any other RPC
+    let mut queue = Queue::connect(global_addr).await.unwrap();
+    match queue.push_back(key.clone(), Bytes::from("v")).await {
+        Err(DstoreError::Transport(status)) => {
+            assert_eq!(status.code(), tonic::Code::Unauthenticated)
+        }
+        other => panic!("expected unauthenticated, got {:?}", other),
+    }
+
+    // Queue clients carrying the token are served as usual
+    let mut queue = Queue::builder(global_addr)
+        .token("s3cret")
+        .connect()
+        .await
+        .unwrap();
+    queue
+        .push_back(key.clone(), Bytes::from("v"))
+        .await
+        .unwrap();
+    let (id, value) = queue.pop_front(&key).await.unwrap();
+    assert_eq!(value, Bytes::from("v"));
+    queue.ack(id).await.unwrap();
+}
+
+#[tokio::test]
+async fn cache_stats_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "127.0.0.1:50422").await.unwrap();
+    let reader = Local::new(global_addr, "127.0.0.1:50423").await.unwrap();
+    let key = Bytes::from("counted");
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), Bytes::from("v"))
+        .await
+        .unwrap();
+
+    let mut reader = reader.lock().await;
+    assert_eq!(reader.stats(), CacheStats::default());
+
+    // First read misses cache and fetches from Global, second is served from cache
+    reader.get(&key).await.unwrap();
+    reader.get(&key).await.unwrap();
+    assert_eq!(
+        reader.stats(),
+        CacheStats {
+            hits: 1,
+            misses: 1,
+            remote_fetches: 1,
+        }
+    );
+}
+
+#[tokio::test]
+async fn global_stats_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50432").await.unwrap();
+    let _other = Local::new(global_addr, "127.0.0.1:50433").await.unwrap();
+    let mut local = local.lock().await;
+    for (key, value) in [("a", "1"), ("bb", "22"), ("ccc", "333")] {
+        local
+            .insert(Bytes::from(key), Bytes::from(value))
+            .await
+            .unwrap();
+    }
+
+    let mut admin = Admin::connect(global_addr).await.unwrap();
+    assert_eq!(
+        admin.stats().await.unwrap(),
+        GlobalStats {
+            keys: 3,
+            bytes: 12,
+            nodes: 2,
+        }
+    );
+}
+
+#[tokio::test]
+async fn get_racing_remove_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let reader = Local::new(global_addr, "127.0.0.1:50442").await.unwrap();
+    let writer = Local::new(global_addr, "127.0.0.1:50443").await.unwrap();
+    let mut reader = reader.lock().await;
+    let mut writer = writer.lock().await;
+
+    // Remove KEY while it's being read, over both the single packet and streamed paths
+    let small = Bytes::from("v");
+    let large = Bytes::from(vec![b'x'; MAX_BYTE_SIZE + 1]);
+    for i in 0..40 {
+        let key = Bytes::from(format!("racing-{}", i));
+        let value = if i % 10 == 0 { &large } else { &small };
+        writer.insert(key.clone(), value.clone()).await.unwrap();
+
+        // Stagger removal so that it lands at different points of the read
+        let remove = async {
+            time::sleep(Duration::from_micros(i * 10)).await;
+            writer.remove(&key).await
+        };
+        let (got, _) = tokio::join!(reader.get(&key), remove);
+        match got {
+            Ok(got) => assert_eq!(&got, value),
+            Err(DstoreError::NotFound(missing)) => assert_eq!(missing, key),
+            Err(e) => panic!("expected VALUE or not found, got {}", e),
+        }
+    }
+}
+
+#[tokio::test]
+async fn fencing_token_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let old = Local::new(global_addr, "127.0.0.1:50452").await.unwrap();
+    let new = Local::new(global_addr, "127.0.0.1:50453").await.unwrap();
+    let mut old = old.lock().await;
+    let mut new = new.lock().await;
+
+    let first = old.promote().await.unwrap();
+    assert_eq!(old.fencing_token(), Some(first));
+    old.insert(Bytes::from("a"), Bytes::from("1"))
+        .await
+        .unwrap();
+
+    // Once another writer is promoted, the old one can no longer write
+    let second = new.promote().await.unwrap();
+    assert!(second > first);
+    match old.insert(Bytes::from("b"), Bytes::from("1")).await {
+        Err(DstoreError::Transport(status)) => assert_eq!(status.code(), tonic::Code::Aborted),
+        other => panic!("expected aborted, got {:?}", other),
+    }
+    new.insert(Bytes::from("b"), Bytes::from("2"))
+        .await
+        .unwrap();
+    let value = new
+        .get_with_consistency(&Bytes::from("b"), Consistency::Strong)
+        .await
+        .unwrap();
+    assert_eq!(value, Bytes::from("2"));
+}
+
+#[tokio::test]
+async fn bloom_filter_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50462").await.unwrap();
+    let mut local = local.lock().await;
+    let keys: Vec<Bytes> = (0..100)
+        .map(|i| Bytes::from(format!("present-{}", i)))
+        .collect();
+    for key in &keys {
+        local.insert(key.clone(), Bytes::from("v")).await.unwrap();
+    }
+
+    let filter: BloomFilter = local.bloom_filter().await.unwrap();
+    assert!(keys.iter().all(|key| filter.might_contain(key)));
+    let absent = (0..100)
+        .filter(|i| filter.might_contain(format!("absent-{}", i).as_bytes()))
+        .count();
+    assert!(absent < 10, "{} absent KEYs tested positive", absent);
+}
+
+#[tokio::test]
+async fn namespace_quota_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50472").await.unwrap();
+    let mut local = local.lock().await;
+    let mut admin = Admin::connect(global_addr).await.unwrap();
+    let quota = Quota {
+        max_keys: Some(3),
+        ..Quota::default()
+    };
+    admin.set_quota(b"limited", quota).await.unwrap();
+
+    for i in 0..3 {
+        let key = Bytes::from(format!("limited:{}", i));
+        local.insert(key, Bytes::from("v")).await.unwrap();
+    }
+    match local
+        .insert(Bytes::from("limited:3"), Bytes::from("v"))
+        .await
+    {
+        Err(DstoreError::Transport(status)) => {
+            assert_eq!(status.code(), tonic::Code::ResourceExhausted)
+        }
+        other => panic!("expected resource exhausted, got {:?}", other),
+    }
+    // Batches report KEYs over quota as rejected, rather than as already present
+    let pairs = vec![(Bytes::from("limited:3"), Bytes::from("v"))];
+    match local.insert_batch(pairs).await {
+        Err(DstoreError::Transport(status)) => {
+            assert_eq!(status.code(), tonic::Code::ResourceExhausted)
+        }
+        other => panic!("expected resource exhausted, got {:?}", other),
+    }
+    assert_eq!(
+        admin.usage(b"limited").await.unwrap(),
+        Usage {
+            keys: 3,
+            bytes: 3 * "limited:0v".len(),
+        }
+    );
+
+    // Other namespaces aren't affected
+    for i in 0..4 {
+        let key = Bytes::from(format!("other:{}", i));
+        local.insert(key, Bytes::from("v")).await.unwrap();
+    }
+    assert_eq!(admin.usage(b"other").await.unwrap().keys, 4);
+
+    // Removing a KEY makes room for another
+    let _ = local.remove(&Bytes::from("limited:0")).await;
+    local
+        .insert(Bytes::from("limited:3"), Bytes::from("v"))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn watch_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let watcher = Local::new(global_addr, "127.0.0.1:50482").await.unwrap();
+    let writer = Local::new(global_addr, "127.0.0.1:50483").await.unwrap();
+    let key = Bytes::from("watched");
+    let mut changes = Box::pin(watcher.lock().await.watch(&key).await.unwrap());
+
+    // Writes to other KEYs, even those extending watched KEY, aren't reported
+    let mut writer = writer.lock().await;
+    writer
+        .insert(Bytes::from("watched-not"), Bytes::from("v"))
+        .await
+        .unwrap();
+    writer.insert(key.clone(), Bytes::from("v")).await.unwrap();
+    let changed = time::timeout(Duration::from_millis(100), changes.next())
+        .await
+        .expect("notified within milliseconds of write");
+    assert_eq!(changed.unwrap().unwrap(), key);
+
+    let _ = writer.remove(&key).await;
+    let changed = time::timeout(Duration::from_millis(100), changes.next())
+        .await
+        .expect("notified within milliseconds of removal");
+    assert_eq!(changed.unwrap().unwrap(), key);
+}
+
+#[tokio::test]
+async fn increment_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let mut locals = vec![];
+    for i in 0..4 {
+        let local_addr = format!("127.0.0.1:5049{}", i + 2);
+        locals.push(Local::new(global_addr, &local_addr).await.unwrap());
+    }
+
+    // 100 tasks spread over several Locals, incrementing concurrently
+    let key = Bytes::from("counter");
+    let mut tasks = vec![];
+    for i in 0..100 {
+        let (local, key) = (locals[i % locals.len()].clone(), key.clone());
+        tasks.push(tokio::spawn(async move {
+            local.lock().await.increment(&key, 1).await.unwrap()
+        }));
+    }
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let mut local = locals[0].lock().await;
+    assert_eq!(local.increment(&key, 0).await.unwrap(), 100);
+    let value = local.get(&key).await.unwrap();
+    assert_eq!(i64::from_be_bytes(value[..].try_into().unwrap()), 100);
+    assert_eq!(local.increment(&key, -101).await.unwrap(), -1);
+
+    // VALUEs that aren't counters can't be incremented
+    local
+        .insert(Bytes::from("text"), Bytes::from("hello"))
+        .await
+        .unwrap();
+    assert!(local.increment(&Bytes::from("text"), 1).await.is_err());
+}
+
+#[tokio::test]
+async fn apply_batch_test() {
+    let local_addr = "127.0.0.1:50502";
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, local_addr).await.unwrap();
+    let mut local = local.lock().await;
+    let (a, b, c) = (Bytes::from("a"), Bytes::from("b"), Bytes::from("c"));
+    local.insert(a.clone(), Bytes::from("1")).await.unwrap();
+
+    // Copy A to B, delete A and set C, all at once
+    local
+        .apply_batch(vec![
+            BatchOp::Copy {
+                from: a.clone(),
+                to: b.clone(),
+            },
+            BatchOp::Remove(a.clone()),
+            BatchOp::Put {
+                key: c.clone(),
+                value: Bytes::from("3"),
+            },
+        ])
+        .await
+        .unwrap();
+    assert!(matches!(local.get(&a).await, Err(DstoreError::NotFound(_))));
+    assert_eq!(local.get(&b).await.unwrap(), Bytes::from("1"));
+    assert_eq!(local.get(&c).await.unwrap(), Bytes::from("3"));
+
+    // B is gone once renamed to A, so renaming it again fails and none of the batch takes effect
+    let result = local
+        .apply_batch(vec![
+            BatchOp::Put {
+                key: c.clone(),
+                value: Bytes::from("4"),
+            },
+            BatchOp::Rename {
+                from: b.clone(),
+                to: a.clone(),
+            },
+            BatchOp::Rename {
+                from: b.clone(),
+                to: c.clone(),
+            },
+        ])
+        .await;
+    assert!(result.is_err());
+    assert!(matches!(local.get(&a).await, Err(DstoreError::NotFound(_))));
+    assert_eq!(local.get(&b).await.unwrap(), Bytes::from("1"));
+    assert_eq!(local.get(&c).await.unwrap(), Bytes::from("3"));
+}
+
+#[tokio::test]
+async fn changes_test() {
+    let local_addr = "127.0.0.1:50512";
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, local_addr).await.unwrap();
+    let mut local = local.lock().await;
+    let (a, b) = (Bytes::from("a"), Bytes::from("b"));
+    local.insert(a.clone(), Bytes::from("1")).await.unwrap();
+    local.insert(b.clone(), Bytes::from("2")).await.unwrap();
+    local.remove(&a).await.unwrap();
+
+    let put = |key: &Bytes, value: &'static str| WatchEvent::Put {
+        key: key.clone(),
+        value: Bytes::from(value),
+    };
+    let mut changes = local.changes(0).await.unwrap();
+    let mut seen = vec![];
+    for _ in 0..3 {
+        seen.push(changes.next().await.unwrap().unwrap());
+    }
+    assert_eq!(
+        seen.iter().map(|m| m.event.clone()).collect::<Vec<_>>(),
+        vec![put(&a, "1"), put(&b, "2"), WatchEvent::Removed(a.clone())]
+    );
+    assert!(seen.windows(2).all(|w| w[1].sequence == w[0].sequence + 1));
+
+    // Mutations made while following are streamed as they happen
+    local
+        .update_value(b.clone(), Bytes::from("3"))
+        .await
+        .unwrap();
+    let Mutation { sequence, event } = changes.next().await.unwrap().unwrap();
+    assert_eq!(event, put(&b, "3"));
+    assert_eq!(sequence, seen[2].sequence + 1);
+
+    // Consumer resumes from the mutation after the last it saw
+    let mut resumed = local.changes(seen[1].sequence + 1).await.unwrap();
+    assert_eq!(resumed.next().await.unwrap().unwrap(), seen[2]);
+    assert_eq!(resumed.next().await.unwrap().unwrap().sequence, sequence);
+}
+
+#[tokio::test]
+async fn append_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "127.0.0.1:50522").await.unwrap();
+    let reader = Local::new(global_addr, "127.0.0.1:50523").await.unwrap();
+    let mut writer = writer.lock().await;
+    let key = Bytes::from("log");
+
+    // Chunks fit a packet each, but together grow VALUE past MAX_BYTE_SIZE
+    let chunks: Vec<Bytes> = (b'a'..=b'c')
+        .map(|byte| Bytes::from(vec![byte; MAX_BYTE_SIZE / 2]))
+        .collect();
+    for chunk in &chunks {
+        writer.append(&key, chunk.clone()).await.unwrap();
+    }
+
+    let expected = chunks.concat();
+    assert_eq!(writer.get(&key).await.unwrap(), expected);
+    assert_eq!(reader.lock().await.get_file(&key).await.unwrap(), expected);
+
+    let oversized = Bytes::from(vec![0; MAX_BYTE_SIZE]);
+    assert!(matches!(
+        writer.append(&key, oversized).await,
+        Err(DstoreError::FrameTooLarge { .. })
+    ));
+}
+
+#[tokio::test]
+async fn request_timeout_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let (proxy_addr, frozen) = &spawn_proxy(global_addr).await;
+    let config = LocalConfig {
+        update_interval: Duration::from_secs(60),
+        timeout: Some(Duration::from_millis(300)),
+        retries: 1,
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(proxy_addr, "127.0.0.1:50533", config.clone())
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+    let writer = Local::new(global_addr, "127.0.0.1:50534").await.unwrap();
+    let key = Bytes::from("hello");
+    let value = Bytes::from("world");
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), value)
+        .await
+        .unwrap();
+
+    // Global hangs, so get gives up after each attempt times out, rather than blocking
+    frozen.store(true, Ordering::SeqCst);
+    let start = Instant::now();
+    assert!(matches!(local.get(&key).await, Err(DstoreError::Timeout)));
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(600));
+    assert!(elapsed < Duration::from_secs(2));
+
+    // Joining a hung Global times out too
+    let joined = Local::with_config(proxy_addr, "127.0.0.1:50535", config).await;
+    assert!(matches!(joined, Err(DstoreError::Timeout)));
+}
+
+#[tokio::test]
+async fn global_restart_test() {
+    let global_addr = &free_addr().await;
+    let (tx, rx) = oneshot::channel::<()>();
+    let addr = global_addr.clone();
+    let server = tokio::spawn(async move {
+        Global::start_server_with_shutdown(&addr, async {
+            rx.await.ok();
+        })
+        .await
+        .unwrap()
+    });
+    serving(global_addr).await;
+    let config = LocalConfig {
+        update_interval: Duration::from_millis(100),
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(global_addr, "127.0.0.1:50542", config)
+        .await
+        .unwrap();
+    let key = Bytes::from("hello");
+    local
+        .lock()
+        .await
+        .insert(key.clone(), Bytes::from("world"))
+        .await
+        .unwrap();
+
+    // Global restarts without it's mappings or cluster
+    tx.send(()).unwrap();
+    server.await.unwrap();
+    let addr = global_addr.clone();
+    tokio::spawn(async move { Global::start_server(&addr).await.unwrap() });
+    serving(global_addr).await;
+    assert!(local.lock().await.ping().await.is_ok());
+
+    // Local rejoins, reconciling VALUEs that may since have changed, and is kept up to date thereafter
+    let writer = Local::new(global_addr, "127.0.0.1:50543").await.unwrap();
+    let mut writer = writer.lock().await;
+    writer
+        .insert(key.clone(), Bytes::from("there"))
+        .await
+        .unwrap();
+    time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(local.lock().await.get(&key).await.unwrap(), "there");
+    writer
+        .update_value(key.clone(), Bytes::from("again"))
+        .await
+        .unwrap();
+    time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(local.lock().await.get(&key).await.unwrap(), "again");
+}
+
+#[tokio::test]
+async fn remove_evicts_cache_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50552").await.unwrap();
+    let mut local = local.lock().await;
+    let key = Bytes::from("hello");
+
+    local
+        .insert(key.clone(), Bytes::from("world"))
+        .await
+        .unwrap();
+    assert!(local.is_cached(&key));
+    local.remove(&key).await.unwrap();
+    assert!(!local.is_cached(&key));
+    assert!(matches!(
+        local.get(&key).await,
+        Err(DstoreError::NotFound(_))
+    ));
+
+    // Removing a KEY that isn't cached is no failure, so long as Global held it
+    let writer = Local::new(global_addr, "127.0.0.1:50553").await.unwrap();
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), Bytes::from("again"))
+        .await
+        .unwrap();
+    local.remove(&key).await.unwrap();
+    assert!(matches!(
+        local.remove(&key).await,
+        Err(DstoreError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn try_get_test() {
+    let global = TestGlobal::spawn().await;
+    let (proxy_addr, frozen) = &spawn_proxy(&global.addr()).await;
+    let config = LocalConfig {
+        timeout: Some(Duration::from_millis(200)),
+        retries: 0,
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(proxy_addr, "127.0.0.1:50563", config)
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+    let key = Bytes::from("hello");
+    local
+        .insert(key.clone(), Bytes::from("world"))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        local.try_get(&key).await.unwrap(),
+        Some(Bytes::from("world"))
+    );
+    assert_eq!(local.try_get(&Bytes::from("absent")).await.unwrap(), None);
+
+    // A KEY that can't be looked up is an error, not a miss
+    frozen.store(true, Ordering::SeqCst);
+    assert!(local.try_get(&Bytes::from("absent")).await.is_err());
+}
+
+#[tokio::test]
+async fn preload_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "127.0.0.1:50572").await.unwrap();
+    let key = Bytes::from("hello");
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), Bytes::from("world"))
+        .await
+        .unwrap();
+
+    let config = LocalConfig {
+        preload: true,
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(global_addr, "127.0.0.1:50573", config)
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+    assert!(local.is_cached(&key));
+    assert_eq!(local.get(&key).await.unwrap(), Bytes::from("world"));
+    assert_eq!(
+        local.stats(),
+        CacheStats {
+            hits: 1,
+            misses: 0,
+            remote_fetches: 0
+        }
+    );
+}
+
+#[tokio::test]
+async fn insert_from_path_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let config = LocalConfig {
+        max_cached_value: Some(1024),
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(global_addr, "127.0.0.1:50582", config)
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+
+    // VALUE spans several frames, with a partial one at the end
+    let value: Vec<u8> = (0..2 * MAX_BYTE_SIZE + 100).map(|i| i as u8).collect();
+    let path = std::env::temp_dir().join("dstore-insert-from-path");
+    tokio::fs::write(&path, &value).await.unwrap();
+    let key = Bytes::from("large");
+    local.insert_from_path(key.clone(), &path).await.unwrap();
+    tokio::fs::remove_file(&path).await.unwrap();
+    assert!(!local.is_cached(&key));
+    assert!(matches!(
+        local.insert_from_reader(key.clone(), &b"small"[..]).await,
+        Err(DstoreError::KeyOccupied(_))
+    ));
+
+    let reader = Local::new(global_addr, "127.0.0.1:50583").await.unwrap();
+    assert_eq!(
+        reader.lock().await.get(&key).await.unwrap(),
+        Bytes::from(value)
+    );
+
+    // VALUEs within limit are cached as they're streamed
+    let key = Bytes::from("small");
+    local
+        .insert_from_reader(key.clone(), &b"world"[..])
+        .await
+        .unwrap();
+    assert!(local.is_cached(&key));
+    assert_eq!(local.get(&key).await.unwrap(), Bytes::from("world"));
+
+    // A reader failing midway leaves KEY unmapped, not truncated
+    struct Failing;
+    impl tokio::io::AsyncRead for Failing {
+        fn poll_read(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            _: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()))
+        }
+    }
+    let key = Bytes::from("broken");
+    let reader = vec![0; MAX_BYTE_SIZE + 1];
+    assert!(matches!(
+        local
+            .insert_from_reader(key.clone(), (&reader[..]).chain(Failing))
+            .await,
+        Err(DstoreError::Io(_))
+    ));
+    time::sleep(Duration::from_millis(100)).await;
+    assert!(matches!(
+        local.get(&key).await,
+        Err(DstoreError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn get_to_path_test() {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+    fn hash(value: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "127.0.0.1:50592").await.unwrap();
+    let value: Vec<u8> = (0..2 * MAX_BYTE_SIZE + 100)
+        .map(|i| (i % 251) as u8)
+        .collect();
+    let key = Bytes::from("large");
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), Bytes::from(value.clone()))
+        .await
+        .unwrap();
+
+    let local = Local::new(global_addr, "127.0.0.1:50593").await.unwrap();
+    let mut local = local.lock().await;
+    let path = std::env::temp_dir().join("dstore-get-to-path");
+    let written = local.get_to_path(&key, &path, false).await.unwrap();
+    let downloaded = tokio::fs::read(&path).await.unwrap();
+    tokio::fs::remove_file(&path).await.unwrap();
+    assert_eq!(written, value.len() as u64);
+    assert_eq!(downloaded.len(), value.len());
+    assert_eq!(hash(&downloaded), hash(&value));
+    assert!(!local.is_cached(&key));
+
+    // Opting into cache serves later reads locally
+    let mut sink = vec![];
+    local.get_to_writer(&key, &mut sink, true).await.unwrap();
+    assert_eq!(hash(&sink), hash(&value));
+    assert!(local.is_cached(&key));
+
+    assert!(matches!(
+        local
+            .get_to_writer(&Bytes::from("absent"), &mut sink, true)
+            .await,
+        Err(DstoreError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn chunk_size_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let config = LocalConfig {
+        chunk_size: 64 * 1024,
+        ..LocalConfig::default()
+    };
+    let writer = Local::with_config(global_addr, "127.0.0.1:50602", config)
+        .await
+        .unwrap();
+    let value: Vec<u8> = (0..MAX_BYTE_SIZE + 5000).map(|i| (i % 251) as u8).collect();
+    let key = Bytes::from("large");
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), Bytes::from(value.clone()))
+        .await
+        .unwrap();
+
+    // Reader frames transfers differently from writer
+    let config = LocalConfig {
+        chunk_size: 1000,
+        ..LocalConfig::default()
+    };
+    let reader = Local::with_config(global_addr, "127.0.0.1:50603", config)
+        .await
+        .unwrap();
+    assert_eq!(
+        reader.lock().await.get(&key).await.unwrap(),
+        Bytes::from(value)
+    );
+}
+
+#[tokio::test]
+async fn checksum_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "127.0.0.1:50612").await.unwrap();
+    let value: Vec<u8> = (0..MAX_BYTE_SIZE + 100).map(|i| (i % 251) as u8).collect();
+    let key = Bytes::from("large");
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), Bytes::from(value.clone()))
+        .await
+        .unwrap();
+
+    // Transfers verify with checksums on, as by default, and off
+    for (checksum, addr) in [(true, "127.0.0.1:50613"), (false, "127.0.0.1:50614")] {
+        let config = LocalConfig {
+            checksum,
+            ..LocalConfig::default()
+        };
+        let reader = Local::with_config(global_addr, addr, config).await.unwrap();
+        let mut sink = vec![];
+        reader
+            .lock()
+            .await
+            .get_to_writer(&key, &mut sink, false)
+            .await
+            .unwrap();
+        assert_eq!(sink, value);
+    }
+}
+
+#[tokio::test]
+async fn scan_prefix_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50622").await.unwrap();
+    let mut local = local.lock().await;
+    for (key, value) in [("a:2", "two"), ("b:1", "other"), ("a:1", "one")] {
+        local
+            .insert(Bytes::from(key), Bytes::from(value))
+            .await
+            .unwrap();
+    }
+
+    let reader = Local::new(global_addr, "127.0.0.1:50623").await.unwrap();
+    let mut reader = reader.lock().await;
+    assert_eq!(
+        reader.scan_prefix(&Bytes::from("a:")).await.unwrap(),
+        vec![
+            (Bytes::from("a:1"), Bytes::from("one")),
+            (Bytes::from("a:2"), Bytes::from("two")),
+        ]
+    );
+    assert!(reader.is_cached(&Bytes::from("a:1")));
+    assert!(!reader.is_cached(&Bytes::from("b:1")));
+    assert!(reader
+        .scan_prefix(&Bytes::from("c:"))
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn typed_store_test() {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct User {
+        name: String,
+        age: u32,
+        tags: Vec<String>,
+    }
+
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50632").await.unwrap();
+    let users: TypedStore<(String, u64), User> = TypedStore::new(local);
+    let key = ("user".to_string(), 123);
+    let user = User {
+        name: "Ada".to_string(),
+        age: 36,
+        tags: vec!["admin".to_string()],
+    };
+    users.insert(&key, &user).await.unwrap();
+
+    // Typed mappings are visible to other Locals through the same types
+    let reader = Local::new(global_addr, "127.0.0.1:50633").await.unwrap();
+    let reader: TypedStore<(String, u64), User> = TypedStore::new(reader);
+    assert_eq!(reader.get(&key).await.unwrap(), user);
+
+    // VALUEs that aren't of the expected type fail to deserialize
+    let other: TypedStore<(String, u64), bool> = TypedStore::new(reader.local());
+    assert!(matches!(
+        other.get(&key).await,
+        Err(DstoreError::Serialization(_))
+    ));
+
+    users.remove(&key).await.unwrap();
+    assert!(matches!(
+        users.get(&key).await,
+        Err(DstoreError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn string_helpers_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50642").await.unwrap();
+    let mut local = local.lock().await;
+    local.insert_str("greeting", "héllo wörld").await.unwrap();
+    assert_eq!(
+        local.get_str("greeting").await.unwrap(),
+        Some("héllo wörld".to_string())
+    );
+    assert_eq!(local.get_str("absent").await.unwrap(), None);
+
+    // A VALUE that isn't UTF-8 is an error, not a panic
+    local
+        .insert(Bytes::from("binary"), Bytes::from(vec![0xff, 0xfe, 0x00]))
+        .await
+        .unwrap();
+    assert!(matches!(
+        local.get_str("binary").await,
+        Err(DstoreError::Utf8(_))
+    ));
+}
+
+#[tokio::test]
+async fn blocking_dequeue_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let key = Bytes::from("tasks");
+
+    let mut consumer = Queue::connect(global_addr).await.unwrap();
+    let waiting = {
+        let key = key.clone();
+        tokio::spawn(async move {
+            let start = Instant::now();
+            let task = consumer
+                .pop_front_wait(&key, Duration::from_secs(5))
+                .await
+                .unwrap();
+            (task, start.elapsed())
+        })
+    };
+
+    time::sleep(Duration::from_millis(200)).await;
+    let mut producer = Queue::connect(global_addr).await.unwrap();
+    producer
+        .push_back(key.clone(), Bytes::from("task"))
+        .await
+        .unwrap();
+
+    // Consumer recieves the task as soon as it's pushed, rather than on timeout
+    let (task, elapsed) = waiting.await.unwrap();
+    assert_eq!(task.map(|(_, task)| task), Some(Bytes::from("task")));
+    assert!(elapsed >= Duration::from_millis(200) && elapsed < Duration::from_secs(5));
+
+    // Nothing is popped if nothing's pushed in time
+    let start = Instant::now();
+    assert_eq!(
+        producer
+            .pop_front_wait(&key, Duration::from_millis(100))
+            .await
+            .unwrap(),
+        None
+    );
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn queue_len_peek_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let mut queue = Queue::connect(global_addr).await.unwrap();
+    let key = Bytes::from("tasks");
+
+    // Unknown queue
+    assert_eq!(queue.len(&key).await.unwrap(), 0);
+    assert_eq!(queue.peek(&key).await.unwrap(), None);
+
+    // Single item, peeking doesn't consume it
+    queue
+        .push_back(key.clone(), Bytes::from("one"))
+        .await
+        .unwrap();
+    assert_eq!(queue.len(&key).await.unwrap(), 1);
+    assert_eq!(queue.peek(&key).await.unwrap(), Some(Bytes::from("one")));
+    assert_eq!(queue.len(&key).await.unwrap(), 1);
+
+    // Multiple items
+    for task in &["two", "three"] {
+        queue
+            .push_back(key.clone(), Bytes::from(*task))
+            .await
+            .unwrap();
+    }
+    assert_eq!(queue.len(&key).await.unwrap(), 3);
+    assert_eq!(queue.peek(&key).await.unwrap(), Some(Bytes::from("one")));
+    queue.pop_front(&key).await.unwrap();
+    assert_eq!(queue.len(&key).await.unwrap(), 2);
+    assert_eq!(queue.peek(&key).await.unwrap(), Some(Bytes::from("two")));
+
+    // Emptied queue
+    queue.pop_front(&key).await.unwrap();
+    queue.pop_front(&key).await.unwrap();
+    assert_eq!(queue.len(&key).await.unwrap(), 0);
+    assert_eq!(queue.peek(&key).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn bounded_queue_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let mut queue = Queue::connect(global_addr).await.unwrap();
+    let key = Bytes::from("tasks");
+    let options = QueueOptions {
+        capacity: Some(2),
+        ..QueueOptions::default()
+    };
+    queue.create(key.clone(), options).await.unwrap();
+
+    for task in &["one", "two"] {
+        queue
+            .push_back(key.clone(), Bytes::from(*task))
+            .await
+            .unwrap();
+    }
+    assert!(matches!(
+        queue.push_back(key.clone(), Bytes::from("three")).await,
+        Err(DstoreError::QueueFull(_))
+    ));
+    assert_eq!(queue.len(&key).await.unwrap(), 2);
+
+    // Room is made as VALUEs are popped
+    assert_eq!(queue.pop_front(&key).await.unwrap().1, Bytes::from("one"));
+    queue
+        .push_back(key.clone(), Bytes::from("three"))
+        .await
+        .unwrap();
+
+    // Lifting the bound lets the queue grow
+    queue
+        .create(key.clone(), QueueOptions::default())
+        .await
+        .unwrap();
+    queue
+        .push_back(key.clone(), Bytes::from("four"))
+        .await
+        .unwrap();
+    assert_eq!(queue.len(&key).await.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn queue_redelivery_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let mut queue = Queue::connect(global_addr).await.unwrap();
+    let key = Bytes::from("tasks");
+    let options = QueueOptions {
+        visibility_timeout: Duration::from_millis(300),
+        ..QueueOptions::default()
+    };
+    queue.create(key.clone(), options).await.unwrap();
+    for task in &["acked", "crashed"] {
+        queue
+            .push_back(key.clone(), Bytes::from(*task))
+            .await
+            .unwrap();
+    }
+
+    // Consumer acknowledges one task, crashing before acknowledging the other
+    let (acked, _) = queue.pop_front(&key).await.unwrap();
+    queue.ack(acked).await.unwrap();
+    let (crashed, task) = queue.pop_front(&key).await.unwrap();
+    assert_eq!(task, Bytes::from("crashed"));
+    assert!(queue.pop_front(&key).await.is_err());
+
+    // Unacknowledged task is redelivered once visibility timeout elapses
+    let (redelivered, task) = queue
+        .pop_front_wait(&key, Duration::from_secs(3))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(task, Bytes::from("crashed"));
+    assert!(queue.ack(crashed).await.is_err());
+    queue.ack(redelivered).await.unwrap();
+    assert!(queue.ack(acked).await.is_err());
+    assert_eq!(queue.len(&key).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn dead_letter_queue_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let mut queue = Queue::connect(global_addr).await.unwrap();
+    let key = Bytes::from("tasks");
+    let options = QueueOptions {
+        visibility_timeout: Duration::from_millis(100),
+        max_deliveries: Some(2),
+        ..QueueOptions::default()
+    };
+    queue.create(key.clone(), options).await.unwrap();
+    queue
+        .push_back(key.clone(), Bytes::from("poison"))
+        .await
+        .unwrap();
+
+    // Poison message is delivered as many times as allowed, never acknowledged
+    for _ in 0..2 {
+        let (_, task) = queue
+            .pop_front_wait(&key, Duration::from_secs(3))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task, Bytes::from("poison"));
+        assert!(queue.dead_letters(&key).await.unwrap().is_empty());
+    }
+
+    // Rather than being redelivered once more, it's moved to the dead-letter queue
+    assert_eq!(
+        queue
+            .pop_front_wait(&key, Duration::from_millis(1500))
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        queue.dead_letters(&key).await.unwrap(),
+        vec![Bytes::from("poison")]
+    );
+    let (id, task) = queue.pop_front(&Bytes::from("tasks:dlq")).await.unwrap();
+    assert_eq!(task, Bytes::from("poison"));
+    queue.ack(id).await.unwrap();
+    assert!(queue.dead_letters(&key).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn gzip_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let config = LocalConfig {
+        gzip: true,
+        ..LocalConfig::default()
+    };
+    let compressed = Local::with_config(global_addr, "127.0.0.1:50702", config.clone())
+        .await
+        .unwrap();
+    let value = Bytes::from("compressible ".repeat(200_000));
+    let key = Bytes::from("text");
+    compressed
+        .lock()
+        .await
+        .insert(key.clone(), value.clone())
+        .await
+        .unwrap();
+
+    // VALUE reads back intact, both with and without compression
+    let plain = Local::new(global_addr, "127.0.0.1:50703").await.unwrap();
+    assert_eq!(plain.lock().await.get(&key).await.unwrap(), value);
+    compressed.lock().await.remove(&key).await.unwrap();
+    assert!(compressed.lock().await.get(&key).await.is_err());
+
+    // Compressing Local falls back to uncompressed requests with a Global that doesn't accept them
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let old_addr = &listener.local_addr().unwrap().to_string();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(DstoreServer::new(Global::new()))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    let fallback = Local::with_config(old_addr, "127.0.0.1:50705", config)
+        .await
+        .unwrap();
+    fallback
+        .lock()
+        .await
+        .insert(key.clone(), value.clone())
+        .await
+        .unwrap();
+    assert_eq!(fallback.lock().await.get(&key).await.unwrap(), value);
+}
+
+#[tokio::test]
+async fn max_message_size_test() {
+    let limit = 16 * 1024 * 1024;
+    let mut global = Global::new();
+    global.set_max_message_size(limit);
+    let global = TestGlobal::spawn_with(global).await;
+    let global_addr = &global.addr();
+    let config = LocalConfig {
+        max_message_size: limit,
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(global_addr, "127.0.0.1:50712", config)
+        .await
+        .unwrap();
+
+    // VALUE of 8MB is sent whole in a single packet, rather than streamed
+    let value = Bytes::from(vec![7; 8 * 1024 * 1024]);
+    let key = Bytes::from("large");
+    local
+        .lock()
+        .await
+        .insert_single(key.clone(), value.clone())
+        .await
+        .unwrap();
+    let reader = Local::with_config(
+        global_addr,
+        "127.0.0.1:50713",
+        LocalConfig {
+            max_message_size: limit,
+            ..LocalConfig::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(reader.lock().await.get(&key).await.unwrap(), value);
+
+    // Global at the default limit rejects it in a single packet, it must be streamed there
+    let default_global = TestGlobal::spawn().await;
+    let default_addr = &default_global.addr();
+    let config = LocalConfig {
+        max_message_size: limit,
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(default_addr, "127.0.0.1:50715", config)
+        .await
+        .unwrap();
+    let err = local
+        .lock()
+        .await
+        .insert_single(key.clone(), value.clone())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, DstoreError::Transport(status) if status.code() == tonic::Code::InvalidArgument)
+    );
+    let plain = Local::new(default_addr, "127.0.0.1:50716").await.unwrap();
+    plain
+        .lock()
+        .await
+        .insert(key.clone(), value.clone())
+        .await
+        .unwrap();
+    assert_eq!(plain.lock().await.get(&key).await.unwrap(), value);
+}
+
+#[tokio::test]
+async fn graceful_shutdown_test() {
+    let global_addr = &free_addr().await;
+    let path = std::env::temp_dir().join("dstore-shutdown-snapshot");
+    let _ = std::fs::remove_file(&path);
+    let (tx, rx) = oneshot::channel::<()>();
+    let mut global = Global::new();
+    global.set_shutdown_snapshot(&path);
+    let addr = global_addr.clone();
+    let server = tokio::spawn(async move {
+        global
+            .serve_with_shutdown(&addr, async {
+                rx.await.ok();
+            })
+            .await
+            .map_err(|e| e.to_string())
+    });
+    serving(global_addr).await;
+
+    let local = Local::new(global_addr, "127.0.0.1:50722").await.unwrap();
+    local
+        .lock()
+        .await
+        .insert(Bytes::from("hello"), Bytes::from("world"))
+        .await
+        .unwrap();
+
+    // Server stops once triggered and Locals are given time to drain, saving a final snapshot on it's way out
+    tx.send(()).unwrap();
+    time::timeout(Duration::from_secs(10), server)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(Local::new(global_addr, "127.0.0.1:50723").await.is_err());
+    let restored = Global::new();
+    assert_eq!(restored.load_snapshot(&path).await.unwrap(), 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Scrape Prometheus metrics served by Global on `addr`
+#[cfg(feature = "metrics")]
+async fn scrape(addr: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.0\r\n\r\n")
+        .await
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).await.unwrap();
+    res
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn metrics_test() {
+    let (global_addr, metrics_addr) = (&free_addr().await, &free_addr().await);
+    let (addr, metrics) = (global_addr.clone(), metrics_addr.clone());
+    tokio::spawn(async move {
+        Global::start_server_with_metrics(&addr, &metrics)
+            .await
+            .unwrap()
+    });
+    serving(global_addr).await;
+
+    let local = Local::new(global_addr, "127.0.0.1:50733").await.unwrap();
+    let mut local = local.lock().await;
+    for key in ["a", "b", "c"] {
+        local
+            .insert(Bytes::from(key), Bytes::from("hello"))
+            .await
+            .unwrap();
+    }
+    local.remove(&Bytes::from("c")).await.unwrap();
+    let mut queue = Queue::connect(global_addr).await.unwrap();
+    queue
+        .push_back(Bytes::from("tasks"), Bytes::from("task"))
+        .await
+        .unwrap();
+
+    let metrics = scrape(metrics_addr).await;
+    assert!(metrics.starts_with("HTTP/1.0 200 OK"));
+    assert!(metrics.contains("dstore_requests_total{rpc=\"push\"} 3"));
+    assert!(metrics.contains("dstore_requests_total{rpc=\"remove\"} 1"));
+    assert!(metrics.contains("dstore_requests_total{rpc=\"join\"} 1"));
+    assert!(metrics.contains("dstore_bytes_total{direction=\"in\"} 18"));
+    assert!(metrics.contains("dstore_value_size_bytes_count 3"));
+    assert!(metrics.contains("dstore_keys 2"));
+    assert!(metrics.contains("dstore_nodes 1"));
+    assert!(metrics.contains("dstore_queued{kind=\"tasks\"} 1"));
+}
+
+#[tokio::test]
+async fn node_timeout_test() {
+    let mut global = Global::new();
+    global.set_node_timeout(Some(Duration::from_millis(500)));
+    let global = TestGlobal::spawn_with(global).await;
+    let global_addr = &global.addr();
+    let mut admin = Admin::connect(global_addr).await.unwrap();
+
+    // Live Local heartbeats often, while the silent one heartbeats only once on joining
+    let live = LocalConfig {
+        update_interval: Duration::from_millis(100),
+        ..LocalConfig::default()
+    };
+    let _live = Local::with_config(global_addr, "127.0.0.1:50742", live)
+        .await
+        .unwrap();
+    let silent = LocalConfig {
+        update_interval: Duration::from_secs(60),
+        ..LocalConfig::default()
+    };
+    let silent = Local::with_config(global_addr, "127.0.0.1:50743", silent)
+        .await
+        .unwrap();
+    assert_eq!(admin.stats().await.unwrap().nodes, 2);
+
+    // Silent Local is dropped from cluster once past the timeout
+    time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(admin.stats().await.unwrap().nodes, 1);
+
+    // It rejoins on heartbeating again
+    silent.lock().await.heartbeat().await;
+    assert_eq!(admin.stats().await.unwrap().nodes, 2);
+}
+
+#[tokio::test]
+async fn flush_all_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let a = Local::new(global_addr, "127.0.0.1:50752").await.unwrap();
+    let b = Local::new(global_addr, "127.0.0.1:50753").await.unwrap();
+    let (mut a, mut b) = (a.lock().await, b.lock().await);
+    for (local, key) in [(&mut a, "a"), (&mut b, "b")] {
+        local
+            .insert(Bytes::from(key), Bytes::from("v"))
+            .await
+            .unwrap();
+    }
+
+    let mut admin = Admin::connect(global_addr).await.unwrap();
+    assert_eq!(admin.flush_all().await.unwrap(), 2);
+    assert_eq!(admin.stats().await.unwrap().keys, 0);
+
+    // Both Locals drop their caches on next update
+    for (local, key) in [(&mut a, "a"), (&mut b, "b")] {
+        let key = Bytes::from(key);
+        assert!(local.is_cached(&key));
+        local.update().await;
+        assert!(!local.is_cached(&key));
+        assert!(local.get(&key).await.is_err());
+    }
+}
+
+#[tokio::test]
+async fn clear_cache_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50762").await.unwrap();
+    let mut local = local.lock().await;
+    let (key, other) = (Bytes::from("hello"), Bytes::from("other"));
+    for key in [&key, &other] {
+        local
+            .insert(key.clone(), Bytes::from("world"))
+            .await
+            .unwrap();
+    }
+
+    // Invalidating a single KEY leaves the rest cached
+    assert!(local.invalidate(&key));
+    assert!(!local.invalidate(&key));
+    assert!(local.is_cached(&other));
+    assert_eq!(local.get(&key).await.unwrap(), Bytes::from("world"));
+    assert_eq!(local.stats().remote_fetches, 1);
+
+    // Clearing cache leaves Global untouched, next read is fetched afresh from it
+    local.clear_cache();
+    assert!(!local.is_cached(&key) && !local.is_cached(&other));
+    assert_eq!(local.get(&key).await.unwrap(), Bytes::from("world"));
+    assert_eq!(local.stats().remote_fetches, 2);
+    assert!(local.is_cached(&key));
+}
+
+#[tokio::test]
+async fn expire_persist_ttl_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50772").await.unwrap();
+    let mut local = local.lock().await;
+    let other = Local::new(global_addr, "127.0.0.1:50773").await.unwrap();
+    let mut other = other.lock().await;
+    let key = Bytes::from("session");
+    assert_eq!(local.ttl(&key).await.unwrap(), -2);
+    assert!(matches!(
+        local.expire(&key, Duration::from_secs(10)).await,
+        Err(DstoreError::NotFound(_))
+    ));
+
+    local
+        .insert(key.clone(), Bytes::from("value"))
+        .await
+        .unwrap();
+    assert_eq!(local.ttl(&key).await.unwrap(), -1);
+    assert!(!local.persist(&key).await.unwrap());
+
+    // Setting a TTL on an existing KEY leaves it's version as is, but peers holding it drop their copy
+    other.get(&key).await.unwrap();
+    let version = local.inspect(&key).await.unwrap().version;
+    local.expire(&key, Duration::from_secs(60)).await.unwrap();
+    assert_eq!(local.ttl(&key).await.unwrap(), 60);
+    assert_eq!(local.inspect(&key).await.unwrap().version, version);
+    assert!(other.is_cached(&key));
+    other.update().await;
+    assert!(!other.is_cached(&key));
+
+    // Updating a TTL already set
+    local.expire(&key, Duration::from_secs(30)).await.unwrap();
+    assert_eq!(local.ttl(&key).await.unwrap(), 30);
+
+    // Clearing it, KEY no longer expires
+    assert!(local.persist(&key).await.unwrap());
+    assert_eq!(local.ttl(&key).await.unwrap(), -1);
+
+    // KEY is gone once a TTL set on it elapses
+    local.expire(&key, Duration::from_secs(1)).await.unwrap();
+    time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(local.ttl(&key).await.unwrap(), -2);
+    assert!(local.get(&key).await.is_err());
+
+    // Sub-second TTLs hold on Global as long as they do in cache
+    local
+        .insert(key.clone(), Bytes::from("value"))
+        .await
+        .unwrap();
+    local
+        .expire(&key, Duration::from_millis(500))
+        .await
+        .unwrap();
+    assert_eq!(local.ttl(&key).await.unwrap(), 1);
+    time::sleep(Duration::from_millis(800)).await;
+    assert_eq!(local.ttl(&key).await.unwrap(), -2);
+}
+
+#[tokio::test]
+async fn sliding_ttl_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50782").await.unwrap();
+    let mut local = local.lock().await;
+    let (session, fixed) = (Bytes::from("session"), Bytes::from("fixed"));
+    local
+        .insert_with_sliding_ttl(session.clone(), Bytes::from("user"), Duration::from_secs(2))
+        .await
+        .unwrap();
+    local
+        .insert_with_ttl(fixed.clone(), Bytes::from("user"), Duration::from_secs(2))
+        .await
+        .unwrap();
+    assert!(!local.is_cached(&session));
+
+    // Sliding mapping outlives it's TTL while read, the fixed one expires regardless
+    for _ in 0..6 {
+        time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(local.get(&session).await.unwrap(), Bytes::from("user"));
+    }
+    assert!(!local.is_cached(&session));
+    assert_eq!(local.ttl(&fixed).await.unwrap(), -2);
+
+    // It expires once left unread for it's TTL
+    time::sleep(Duration::from_millis(2500)).await;
+    assert!(local.get(&session).await.is_err());
+}
+
+#[tokio::test]
+async fn sliding_ttl_batch_reads_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "127.0.0.1:50832").await.unwrap();
+    let mut local = local.lock().await;
+    let session = Bytes::from("session");
+    local
+        .insert_with_sliding_ttl(session.clone(), Bytes::from("user"), Duration::from_secs(2))
+        .await
+        .unwrap();
+
+    // Reads of many KEYs at once, or streamed, extend the sliding TTL too and leave the mapping uncached
+    let keys = [session.clone()];
+    for round in 0..6 {
+        time::sleep(Duration::from_millis(500)).await;
+        match round % 5 {
+            0 => assert_eq!(local.get_many(&keys).await.unwrap().len(), 1),
+            1 => {
+                let mut found = 0;
+                local
+                    .get_each(&keys, |_, value| found += value.is_some() as usize)
+                    .await
+                    .unwrap();
+                assert_eq!(found, 1);
+            }
+            2 => assert_eq!(local.scan_prefix(&session).await.unwrap().len(), 1),
+            3 => assert_eq!(local.get_file(&session).await.unwrap(), "user"),
+            _ => {
+                let mut out = vec![];
+                let written = local.get_to_writer(&session, &mut out, true).await.unwrap();
+                assert_eq!(written, 4);
+            }
+        }
+        assert!(!local.is_cached(&session));
+    }
+
+    // Joining Locals aren't warmed with it either
+    let other = Local::builder(global_addr, "127.0.0.1:50833")
+        .preload(true)
+        .connect()
+        .await
+        .unwrap();
+    assert!(!other.lock().await.is_cached(&session));
+}
+
+#[tokio::test]
+async fn local_builder_test() {
+    let global = TestGlobal::spawn_with(Global::with_token("secret")).await;
+    let global_addr = &global.addr();
+    // Global rejects a Local built without it's token
+    assert!(Local::new(global_addr, "127.0.0.1:50792").await.is_err());
+
+    let local = Local::builder(global_addr, "127.0.0.1:50793")
+        .token("secret")
+        .max_entries(2)
+        .max_cached_value(8)
+        .timeout(Some(Duration::from_secs(1)))
+        .retries(0)
+        .connect()
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+    for key in ["a", "b", "c"] {
+        local
+            .insert(Bytes::from(key), Bytes::from("small"))
+            .await
+            .unwrap();
+    }
+    local
+        .insert(Bytes::from("large"), Bytes::from("too large to cache"))
+        .await
+        .unwrap();
+
+    // Cache holds atmost 2 mappings, none larger than 8 bytes
+    assert!(!local.is_cached(&Bytes::from("a")));
+    assert!(local.is_cached(&Bytes::from("b")) && local.is_cached(&Bytes::from("c")));
+    assert!(!local.is_cached(&Bytes::from("large")));
+    assert_eq!(
+        local.get(&Bytes::from("large")).await.unwrap(),
+        Bytes::from("too large to cache")
+    );
+}
+
+#[tokio::test]
+async fn global_builder_test() {
+    // Sweeps run only on starting, expired mappings then linger in Locals' caches
+    let global = Global::builder()
+        .token("secret")
+        .sweep_interval(Duration::from_secs(3600))
+        .build()
+        .await
+        .unwrap();
+    let global = TestGlobal::spawn_with(global).await;
+    let global_addr = &global.addr();
+    assert!(Local::new(global_addr, "127.0.0.1:50802").await.is_err());
+
+    let writer = Local::builder(global_addr, "127.0.0.1:50803")
+        .token("secret")
+        .connect()
+        .await
+        .unwrap();
+    let reader = Local::builder(global_addr, "127.0.0.1:50804")
+        .token("secret")
+        .connect()
+        .await
+        .unwrap();
+    let (mut writer, mut reader) = (writer.lock().await, reader.lock().await);
+    let key = Bytes::from("transient");
+    writer
+        .insert_with_ttl(key.clone(), Bytes::from("value"), Duration::from_secs(1))
+        .await
+        .unwrap();
+    reader.get(&key).await.unwrap();
+
+    // Mapping expires on Global, but isn't swept, so reader isn't told to drop it's copy
+    time::sleep(Duration::from_millis(2500)).await;
+    assert_eq!(writer.ttl(&key).await.unwrap(), -2);
+    reader.update().await;
+    assert!(reader.is_cached(&key));
+}
+
+#[tokio::test]
+async fn get_consistent_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let a = Local::new(global_addr, "127.0.0.1:50812").await.unwrap();
+    let b = Local::new(global_addr, "127.0.0.1:50813").await.unwrap();
+    let (mut a, mut b) = (a.lock().await, b.lock().await);
+    let key = Bytes::from("key");
+    a.insert(key.clone(), Bytes::from("old")).await.unwrap();
+
+    // Before it's next update, A serves it's stale copy from cache, unless asked for a consistent read
+    b.update_value(key.clone(), Bytes::from("new"))
+        .await
+        .unwrap();
+    assert_eq!(a.get(&key).await.unwrap(), Bytes::from("old"));
+    assert_eq!(a.get_consistent(&key).await.unwrap(), Bytes::from("new"));
+    assert_eq!(a.stats().remote_fetches, 1);
+
+    // Cache is refreshed with the fresh VALUE
+    assert_eq!(a.get(&key).await.unwrap(), Bytes::from("new"));
+    assert_eq!(a.stats().remote_fetches, 1);
+}
+
+#[tokio::test]
+async fn read_your_writes_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "127.0.0.1:50822").await.unwrap();
+    let peer = Local::new(global_addr, "127.0.0.1:50823").await.unwrap();
+    let (mut writer, mut peer) = (writer.lock().await, peer.lock().await);
+    let key = Bytes::from("key");
+    writer
+        .insert(key.clone(), Bytes::from("old"))
+        .await
+        .unwrap();
+    peer.get(&key).await.unwrap();
+
+    // Overwrite invalidates peer's copy, while writer keeps the VALUE it just wrote
+    writer
+        .update_value(key.clone(), Bytes::from("new"))
+        .await
+        .unwrap();
+    writer.update().await;
+    peer.update().await;
+    assert!(writer.is_cached(&key));
+    assert!(!peer.is_cached(&key));
+    assert_eq!(writer.get(&key).await.unwrap(), Bytes::from("new"));
+    assert_eq!(peer.get(&key).await.unwrap(), Bytes::from("new"));
+    assert_eq!(writer.stats().remote_fetches, 0);
+}
+
+#[tokio::test]
+async fn test_global_harness_test() {
+    let (first, second) = (TestGlobal::spawn().await, TestGlobal::spawn().await);
+    assert_ne!(first.addr(), second.addr());
+
+    // Globals are independent of one another
+    let a = first.local("a").await.unwrap();
+    let b = second.local("b").await.unwrap();
+    let key = Bytes::from("key");
+    a.lock()
+        .await
+        .insert(key.clone(), Bytes::from("first"))
+        .await
+        .unwrap();
+    b.lock()
+        .await
+        .insert(key.clone(), Bytes::from("second"))
+        .await
+        .unwrap();
+    assert_eq!(
+        a.lock().await.get(&key).await.unwrap(),
+        Bytes::from("first")
+    );
+
+    // Global stops serving once shut down
+    first.shutdown().await;
+    assert!(a.lock().await.ping().await.is_err());
+    assert!(b.lock().await.ping().await.is_ok());
+}
+
+#[tokio::test]
+async fn memory_global_chunking_test() {
+    // Small limits, so that boundaries between single packets and frames are cheap to reach
+    let (limit, chunk_size) = (1024, 100);
+    let mut global = Global::new();
+    global.set_max_message_size(limit);
+    let global = MemoryGlobal::spawn_with(global);
+    let config = LocalConfig {
+        max_message_size: limit,
+        chunk_size,
+        ..LocalConfig::default()
+    };
+    let writer = global
+        .local_with_config("writer", config.clone())
+        .await
+        .unwrap();
+    let reader = global.local_with_config("reader", config).await.unwrap();
+    let (mut writer, mut reader) = (writer.lock().await, reader.lock().await);
+
+    // Largest VALUE sent whole with a KEY of 5 bytes and a packet's overhead of 64 bytes, and sizes either side
+    let single = limit - 5 - 64;
+    for size in [single - 1, single, single + 1] {
+        let key = Bytes::from(format!("k{:04}", size));
+        let value = Bytes::from(vec![size as u8; size]);
+        writer.insert(key.clone(), value.clone()).await.unwrap();
+        assert_eq!(reader.get(&key).await.unwrap(), value);
+    }
+
+    // Streamed VALUEs split into whole frames, or with a partial last frame
+    for size in [10 * chunk_size - 1, 10 * chunk_size, 10 * chunk_size + 1] {
+        let key = Bytes::from(format!("f{}", size));
+        let value = Bytes::from((0..size).map(|i| i as u8).collect::<Vec<u8>>());
+        writer
+            .insert_file(key.clone(), value.clone())
+            .await
+            .unwrap();
+        assert_eq!(reader.get(&key).await.unwrap(), value);
+    }
+
+    // A VALUE too large to send whole is rejected by Global unless streamed
+    let key = Bytes::from("p");
+    let oversized = Bytes::from(vec![0; limit]);
+    assert!(writer
+        .insert_single(key.clone(), oversized.clone())
+        .await
+        .is_err());
+    writer.insert(key.clone(), oversized.clone()).await.unwrap();
+    assert_eq!(reader.get(&key).await.unwrap(), oversized);
+}
+
+#[tokio::test]
+async fn background_updates_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let updated = Local::new(global_addr, "updated").await.unwrap();
+    assert!(updated.lock().await.is_updating());
+    let manual = Local::builder(global_addr, "manual")
+        .update_interval(Duration::from_millis(10))
+        .background_updates(false)
+        .connect()
+        .await
+        .unwrap();
+    assert!(!manual.lock().await.is_updating());
+    // No updater task holds a reference to Local
+    assert_eq!(Arc::strong_count(&manual), 1);
+
+    // Cache isn't invalidated until updated by hand
+    let key = Bytes::from("key");
+    let mut manual = manual.lock().await;
+    manual
+        .insert(key.clone(), Bytes::from("old"))
+        .await
+        .unwrap();
+    updated.lock().await.remove(&key).await.unwrap();
+    time::sleep(Duration::from_millis(50)).await;
+    assert!(manual.is_cached(&key));
+    manual.update().await;
+    assert!(!manual.is_cached(&key));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_test() {
+    let path = std::env::temp_dir().join(format!("dstore-{}.sock", std::process::id()));
+    let global_addr = format!("unix:{}", path.display());
+    let addr = global_addr.clone();
+    tokio::spawn(async move { Global::start_server(&addr).await.unwrap() });
+    time::sleep(Duration::from_millis(100)).await;
+
+    let writer = Local::new(&global_addr, "writer").await.unwrap();
+    let reader = Local::new(&global_addr, "reader").await.unwrap();
+    let (key, value) = (Bytes::from("key"), Bytes::from("value"));
+    writer
+        .lock()
+        .await
+        .insert(key.clone(), value.clone())
+        .await
+        .unwrap();
+    assert_eq!(reader.lock().await.get(&key).await.unwrap(), value);
+
+    // Clients other than Local reach Global over the socket too
+    let mut admin = Admin::connect(&global_addr).await.unwrap();
+    assert_eq!(admin.stats().await.unwrap().keys, 1);
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn list_nodes_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let a = Local::new(global_addr, "node-a").await.unwrap();
+    let _b = Local::new(global_addr, "node-b").await.unwrap();
+    let mut admin = Admin::connect(global_addr).await.unwrap();
+    let nodes = admin.list_nodes().await.unwrap();
+    let addrs: Vec<&str> = nodes.iter().map(|node| node.addr.as_str()).collect();
+    assert_eq!(addrs, ["node-a", "node-b"]);
+    assert!(nodes.iter().all(|node| node.queued == 0));
+
+    // A removal is queued for every Local but the remover until they update
+    let key = Bytes::from("key");
+    let mut a = a.lock().await;
+    a.insert(key.clone(), Bytes::from("value")).await.unwrap();
+    a.remove(&key).await.unwrap();
+    let nodes = admin.list_nodes().await.unwrap();
+    assert_eq!(
+        nodes,
+        [
+            NodeInfo {
+                addr: "node-a".to_string(),
+                queued: 0
+            },
+            NodeInfo {
+                addr: "node-b".to_string(),
+                queued: 1
+            }
+        ]
+    );
+
+    // Removing a KEY that's already gone invalidates no one
+    assert!(matches!(
+        a.remove(&key).await,
+        Err(DstoreError::NotFound(_))
+    ));
+    let nodes = admin.list_nodes().await.unwrap();
+    assert_eq!(nodes[1].queued, 1);
+}
+
+#[tokio::test]
+async fn transaction_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let local = Local::new(global_addr, "writer").await.unwrap();
+    let peer = Local::new(global_addr, "peer").await.unwrap();
+    let (mut local, mut peer) = (local.lock().await, peer.lock().await);
+    let (from, to) = (Bytes::from("from"), Bytes::from("to"));
+    local
+        .insert(from.clone(), Bytes::from("100"))
+        .await
+        .unwrap();
+    local.insert(to.clone(), Bytes::from("0")).await.unwrap();
+    peer.get(&from).await.unwrap();
+    let transfer = vec![
+        BatchOp::Put {
+            key: from.clone(),
+            value: Bytes::from("50"),
+        },
+        BatchOp::Put {
+            key: to.clone(),
+            value: Bytes::from("50"),
+        },
+    ];
+
+    // Neither put is applied when a precondition fails
+    let stale = vec![Precondition::Equals {
+        key: from.clone(),
+        value: Bytes::from("90"),
+    }];
+    assert!(!local.transaction(transfer.clone(), stale).await.unwrap());
+    assert_eq!(
+        local.get_consistent(&from).await.unwrap(),
+        Bytes::from("100")
+    );
+    assert_eq!(local.get_consistent(&to).await.unwrap(), Bytes::from("0"));
+
+    // Both are applied once every precondition holds, invalidating peers' copies
+    let current = vec![
+        Precondition::Equals {
+            key: from.clone(),
+            value: Bytes::from("100"),
+        },
+        Precondition::Absent(Bytes::from("lock")),
+    ];
+    assert!(local.transaction(transfer, current).await.unwrap());
+    assert_eq!(local.get(&from).await.unwrap(), Bytes::from("50"));
+    assert_eq!(local.get(&to).await.unwrap(), Bytes::from("50"));
+    peer.update().await;
+    assert_eq!(peer.get(&from).await.unwrap(), Bytes::from("50"));
+
+    // A KEY expected absent that is mapped fails the transaction
+    let locked = vec![Precondition::Absent(to.clone())];
+    let remove = vec![BatchOp::Remove(from.clone())];
+    assert!(!local.transaction(remove, locked).await.unwrap());
+    assert!(local.get_consistent(&from).await.is_ok());
+}
+
+#[tokio::test]
+async fn optimistic_concurrency_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let a = Local::new(global_addr, "a").await.unwrap();
+    let b = Local::new(global_addr, "b").await.unwrap();
+    let (mut a, mut b) = (a.lock().await, b.lock().await);
+    let key = Bytes::from("counter");
+    let created = a
+        .put_if_version(key.clone(), Bytes::from("0"), 0)
+        .await
+        .unwrap();
+    assert_eq!(b.version(&key).await.unwrap(), created);
+
+    // Both read the same version and each increments the count it read
+    let (read_a, version_a) = a.get_with_version(&key).await.unwrap();
+    let (read_b, version_b) = b.get_with_version(&key).await.unwrap();
+    assert_eq!((read_a, version_a), (read_b, version_b));
+    let next = Bytes::from("1");
+    let updated = a
+        .put_if_version(key.clone(), next.clone(), version_a)
+        .await
+        .unwrap();
+    assert!(updated > version_a);
+
+    // B's write, which would lose A's update, is rejected until it re-reads
+    let err = b
+        .put_if_version(key.clone(), next.clone(), version_b)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        DstoreError::VersionConflict { current, .. } if current == updated
+    ));
+    let (read_b, version_b) = b.get_with_version(&key).await.unwrap();
+    assert_eq!((read_b, version_b), (next, updated));
+    let last = b
+        .put_if_version(key.clone(), Bytes::from("2"), version_b)
+        .await
+        .unwrap();
+    assert_eq!(
+        a.get_with_version(&key).await.unwrap(),
+        (Bytes::from("2"), last)
+    );
+
+    // Expecting KEY unmapped fails once it is
+    assert!(matches!(
+        a.put_if_version(key.clone(), Bytes::from("0"), 0).await,
+        Err(DstoreError::VersionConflict { current, .. }) if current == last
+    ));
+
+    // Versions never repeat for a KEY removed and mapped anew, so writes expecting the old mapping fail
+    a.remove(&key).await.unwrap();
+    a.insert(key.clone(), Bytes::from("0")).await.unwrap();
+    assert!(a.version(&key).await.unwrap() > last);
+    assert!(matches!(
+        b.put_if_version(key.clone(), Bytes::from("3"), last).await,
+        Err(DstoreError::VersionConflict { .. })
+    ));
+}
+
+#[tokio::test]
+async fn get_range_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "writer").await.unwrap();
+    let reader = Local::new(global_addr, "reader").await.unwrap();
+    let (mut writer, mut reader) = (writer.lock().await, reader.lock().await);
+    let key = Bytes::from("alphabet");
+    let value = Bytes::from("abcdefghijklmnopqrstuvwxyz");
+    writer.insert(key.clone(), value.clone()).await.unwrap();
+
+    // Writer has VALUE cached whole, reader fetches only the range from Global, both agree
+    for local in [&mut writer, &mut reader] {
+        // In bounds
+        assert_eq!(
+            local.get_range(&key, 2, 3).await.unwrap(),
+            Bytes::from("cde")
+        );
+        // Overlapping the end, cut short
+        assert_eq!(
+            local.get_range(&key, 23, 10).await.unwrap(),
+            Bytes::from("xyz")
+        );
+        assert_eq!(local.get_range(&key, 26, 1).await.unwrap(), Bytes::new());
+        // Starting beyond the end
+        assert!(matches!(
+            local.get_range(&key, 27, 1).await,
+            Err(DstoreError::OutOfRange(_))
+        ));
+    }
+    assert_eq!(writer.stats().remote_fetches, 0);
+    assert!(!reader.is_cached(&key));
+    assert!(matches!(
+        reader.get_range(&Bytes::from("missing"), 0, 1).await,
+        Err(DstoreError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn progress_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let config = LocalConfig {
+        chunk_size: 64 * 1024,
+        ..LocalConfig::default()
+    };
+    let writer = Local::with_config(global_addr, "writer", config.clone())
+        .await
+        .unwrap();
+    let reader = Local::with_config(global_addr, "reader", config)
+        .await
+        .unwrap();
+    let (mut writer, mut reader) = (writer.lock().await, reader.lock().await);
+    let value: Vec<u8> = (0..3 * MAX_BYTE_SIZE).map(|i| (i % 251) as u8).collect();
+    let total = value.len() as u64;
+
+    // Counts reported after each frame only ever grow, ending with all of VALUE
+    fn check(reports: &[(u64, Option<u64>)], last: (u64, Option<u64>)) {
+        assert!(reports.len() > 1);
+        assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(reports.last(), Some(&last));
+    }
+
+    let key = Bytes::from("file");
+    let mut sent = vec![];
+    writer
+        .insert_file_with_progress(key.clone(), Bytes::from(value.clone()), |done, total| {
+            sent.push((done, total))
+        })
+        .await
+        .unwrap();
+    check(&sent, (total, Some(total)));
+
+    let mut recieved = vec![];
+    let fetched = reader
+        .get_file_with_progress(&key, |done, total| recieved.push((done, total)))
+        .await
+        .unwrap();
+    assert_eq!(fetched, Bytes::from(value.clone()));
+    check(&recieved, (total, Some(total)));
+
+    // Cached VALUEs are reported done at once
+    let mut cached = vec![];
+    reader
+        .get_file_with_progress(&key, |done, total| cached.push((done, total)))
+        .await
+        .unwrap();
+    assert_eq!(cached, vec![(total, Some(total))]);
+
+    // Size of VALUE read from a reader isn't known upfront
+    let key = Bytes::from("streamed");
+    let mut sent = vec![];
+    writer
+        .insert_from_reader_with_progress(key.clone(), &value[..], |done, total| {
+            sent.push((done, total))
+        })
+        .await
+        .unwrap();
+    check(&sent, (total, None));
+
+    let (mut sink, mut written) = (vec![], vec![]);
+    reader
+        .get_to_writer_with_progress(&key, &mut sink, false, |done, total| {
+            written.push((done, total))
+        })
+        .await
+        .unwrap();
+    assert_eq!(sink, value);
+    check(&written, (total, Some(total)));
+}
+
+#[tokio::test]
+async fn admin_hot_keys_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "writer").await.unwrap();
+    let reader = Local::new(global_addr, "reader").await.unwrap();
+    let (mut writer, mut reader) = (writer.lock().await, reader.lock().await);
+    let (hot, cold) = (Bytes::from("hot"), Bytes::from("cold"));
+    writer.insert(hot.clone(), Bytes::from("v")).await.unwrap();
+    writer.insert(cold.clone(), Bytes::from("v")).await.unwrap();
+
+    // Accesses by every Local are counted together
+    for _ in 0..10 {
+        reader
+            .get_with_consistency(&hot, Consistency::Strong)
+            .await
+            .unwrap();
+    }
+    // A miss is fetched in a single pull, while looking up a KEY's version counts as a read too
+    reader.get(&cold).await.unwrap();
+    reader.version(&cold).await.unwrap();
+
+    let mut admin = Admin::connect(global_addr).await.unwrap();
+    assert_eq!(
+        admin.hot_keys(2).await.unwrap(),
+        vec![
+            KeyAccess {
+                key: hot,
+                reads: 10,
+                writes: 1
+            },
+            KeyAccess {
+                key: cold,
+                reads: 2,
+                writes: 1
+            }
+        ]
+    );
+    assert_eq!(admin.hot_keys(0).await.unwrap(), vec![]);
+}
+
+#[tokio::test]
+async fn remove_prefix_test() {
+    let global = TestGlobal::spawn().await;
+    let global_addr = &global.addr();
+    let writer = Local::new(global_addr, "writer").await.unwrap();
+    let peer = Local::new(global_addr, "peer").await.unwrap();
+    let (mut writer, mut peer) = (writer.lock().await, peer.lock().await);
+    let keys = ["sess:a", "sess:b", "user:c"].map(Bytes::from);
+    for key in &keys {
+        writer.insert(key.clone(), Bytes::from("v")).await.unwrap();
+        peer.get(key).await.unwrap();
+    }
+
+    let prefix = Bytes::from("sess:");
+    assert_eq!(writer.remove_prefix(&prefix).await.unwrap(), 2);
+    peer.update().await;
+
+    // Session KEYs are gone from Global and both caches, others are untouched
+    for local in [&mut writer, &mut peer] {
+        for key in &keys[..2] {
+            assert!(!local.is_cached(key));
+            assert!(matches!(
+                local.get(key).await,
+                Err(DstoreError::NotFound(_))
+            ));
+        }
+        assert!(local.is_cached(&keys[2]));
+        assert_eq!(local.get(&keys[2]).await.unwrap(), Bytes::from("v"));
+    }
+    assert_eq!(writer.remove_prefix(&prefix).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn lost_reply_test() {
+    let (global_addr, lose) = spawn_losing_global().await;
+    let config = LocalConfig {
+        timeout: Some(Duration::from_millis(200)),
+        retries: 2,
+        background_updates: false,
+        ..LocalConfig::default()
+    };
+    let local = Local::with_config(&global_addr, "writer", config)
+        .await
+        .unwrap();
+    let mut local = local.lock().await;
+    let key = Bytes::from("key");
+
+    // Writes whose reply is lost report they timed out, rather than repeating and reporting the outcome
+    // of the repeat, though Global applied them
+    *lose.lock().unwrap() = Some("Push");
+    assert!(matches!(
+        local.insert(key.clone(), Bytes::from("old")).await,
+        Err(DstoreError::Timeout)
+    ));
+    assert_eq!(
+        local
+            .get_with_consistency(&key, Consistency::Strong)
+            .await
+            .unwrap(),
+        Bytes::from("old")
+    );
+
+    *lose.lock().unwrap() = Some("CompareAndSwap");
+    assert!(matches!(
+        local
+            .compare_and_swap(key.clone(), Bytes::from("old"), Bytes::from("new"))
+            .await,
+        Err(DstoreError::Timeout)
+    ));
+    assert_eq!(
+        local
+            .get_with_consistency(&key, Consistency::Strong)
+            .await
+            .unwrap(),
+        Bytes::from("new")
+    );
+
+    *lose.lock().unwrap() = Some("Remove");
+    assert!(matches!(
+        local.remove(&key).await,
+        Err(DstoreError::Timeout)
+    ));
+    assert!(matches!(
+        local.get_with_consistency(&key, Consistency::Strong).await,
+        Err(DstoreError::NotFound(_))
+    ));
+
+    // Reads are still retried past a lost reply
+    local.insert(key.clone(), Bytes::from("v")).await.unwrap();
+    *lose.lock().unwrap() = Some("Pull");
+    assert_eq!(
+        local
+            .get_with_consistency(&key, Consistency::Strong)
+            .await
+            .unwrap(),
+        Bytes::from("v")
+    );
+
+    *lose.lock().unwrap() = Some("Overwrite");
+    assert!(matches!(
+        local.update_value(key.clone(), Bytes::from("w")).await,
+        Err(DstoreError::Timeout)
+    ));
+
+    // Invalidations in a lost reply are sent again, rather than lost with it
+    let config = LocalConfig {
+        timeout: Some(Duration::from_millis(200)),
+        retries: 2,
+        background_updates: false,
+        ..LocalConfig::default()
+    };
+    let reader = Local::with_config(&global_addr, "reader", config)
+        .await
+        .unwrap();
+    let mut reader = reader.lock().await;
+    assert_eq!(reader.get(&key).await.unwrap(), Bytes::from("w"));
+    local
+        .update_value(key.clone(), Bytes::from("x"))
+        .await
+        .unwrap();
+    *lose.lock().unwrap() = Some("UpdateBatch");
+    reader.update().await;
+    assert_eq!(reader.get(&key).await.unwrap(), Bytes::from("x"));
+}
+
+#[tokio::test]
+async fn admin_token_test() {
+    let global = TestGlobal::spawn_with(Global::with_token("secret")).await;
+    let global_addr = &global.addr();
+
+    // Operators without the token are turned away
+    let mut admin = Admin::connect(global_addr).await.unwrap();
+    match admin.stats().await {
+        Err(DstoreError::Transport(status)) => {
+            assert_eq!(status.code(), tonic::Code::Unauthenticated)
+        }
+        res => panic!("Expected request to be unauthenticated, got {:?}", res),
+    }
+
+    let mut admin = Admin::builder(global_addr)
+        .token("secret")
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(admin.stats().await.unwrap().nodes, 0);
+    let local = Local::builder(global_addr, "local")
+        .token("secret")
+        .connect()
+        .await
+        .unwrap();
+    local
+        .lock()
+        .await
+        .insert(Bytes::from("key"), Bytes::from("value"))
+        .await
+        .unwrap();
+    assert_eq!(admin.stats().await.unwrap().keys, 1);
+    assert_eq!(admin.flush_all().await.unwrap(), 1);
+}